@@ -0,0 +1,483 @@
+use anyhow::{
+    ensure,
+    Result,
+};
+use image::RgbaImage;
+use wgpu::util::{
+    BufferInitDescriptor,
+    DeviceExt,
+};
+use crate::examples::perspective_camera::texture::Texture;
+
+// Per axis, has to match `@workgroup_size` in the shaders.
+const WORKGROUP_SIZE: u32 = 8;
+const HISTOGRAM_WORKGROUP_SIZE: u32 = 16;
+const HISTOGRAM_BINS: usize = 256;
+
+// Inputs and outputs of every filter. Values are filtered as they are stored, sRGB images are not
+// linearized first, which is what image editors do too.
+pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Filter {
+    // Rec. 709 luma, alpha is kept
+    Grayscale,
+    // edges of the luma, white on black
+    Sobel,
+    // gaussian with a radius in pixels
+    Blur(u32),
+}
+
+// Pixel counts for every value of red, green, blue and alpha.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Histogram {
+    pub channels: [[u32; HISTOGRAM_BINS]; 4],
+}
+impl Histogram {
+    pub fn mean(&self, channel: usize) -> f32 {
+        let bins = &self.channels[channel];
+        let pixels: u32 = bins.iter().sum();
+        let total: u64 = bins.iter().enumerate().map(|(value, &count)| value as u64 * count as u64).sum();
+        total as f32 / pixels.max(1) as f32
+    }
+}
+
+// Compute shader filters on 2D textures. Every filter returns a new texture that can be read back with
+// `read_image` or passed to the next filter, the input is left alone.
+pub struct ImageFilters {
+    image_bind_group_layout: wgpu::BindGroupLayout,
+    kernel_bind_group_layout: wgpu::BindGroupLayout,
+    histogram_bind_group_layout: wgpu::BindGroupLayout,
+    grayscale_pipeline: wgpu::ComputePipeline,
+    sobel_pipeline: wgpu::ComputePipeline,
+    blur_horizontal_pipeline: wgpu::ComputePipeline,
+    blur_vertical_pipeline: wgpu::ComputePipeline,
+    histogram_pipeline: wgpu::ComputePipeline,
+}
+impl ImageFilters {
+    pub fn supported(adapter: &wgpu::Adapter) -> bool {
+        adapter.get_downlevel_capabilities().flags.contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+    }
+
+    pub fn new(device: &wgpu::Device) -> Self {
+        let filters_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Image Filters Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("filters.wgsl").into()),
+        });
+        let histogram_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Histogram Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("histogram.wgsl").into()),
+        });
+        let input_texture = wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let storage_buffer = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let image_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                input_texture,
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: FORMAT,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("image_bind_group_layout"),
+        });
+        let kernel_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[storage_buffer(0, true)],
+            label: Some("kernel_bind_group_layout"),
+        });
+        let histogram_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[input_texture, storage_buffer(1, false)],
+            label: Some("histogram_bind_group_layout"),
+        });
+
+        let pipeline = |label: &str, bind_group_layouts: &[&wgpu::BindGroupLayout], module: &wgpu::ShaderModule, entry_point: &str| {
+            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts,
+                push_constant_ranges: &[],
+            });
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(&layout),
+                module,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+        let blur_layouts = [&image_bind_group_layout, &kernel_bind_group_layout];
+        Self {
+            grayscale_pipeline: pipeline("Grayscale Pipeline", &[&image_bind_group_layout], &filters_shader, "grayscale"),
+            sobel_pipeline: pipeline("Sobel Pipeline", &[&image_bind_group_layout], &filters_shader, "sobel"),
+            blur_horizontal_pipeline: pipeline("Horizontal Blur Pipeline", &blur_layouts, &filters_shader, "blur_horizontal"),
+            blur_vertical_pipeline: pipeline("Vertical Blur Pipeline", &blur_layouts, &filters_shader, "blur_vertical"),
+            histogram_pipeline: pipeline("Histogram Pipeline", &[&histogram_bind_group_layout], &histogram_shader, "cs_main"),
+            image_bind_group_layout,
+            kernel_bind_group_layout,
+            histogram_bind_group_layout,
+        }
+    }
+
+    // `input` has to be bindable as a texture, e.g. `Texture::from_image` with `FORMAT`.
+    pub fn apply(&self, device: &wgpu::Device, queue: &wgpu::Queue, input: &Texture, filter: Filter) -> Texture {
+        let (width, height) = (input.texture.width(), input.texture.height());
+        let output = Texture::create_storage_target(device, "Filtered Texture", width, height, FORMAT);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {label: Some("Image Filter Encoder")});
+        match filter {
+            Filter::Grayscale => self.dispatch(device, &mut encoder, &self.grayscale_pipeline, input, &output, None),
+            Filter::Sobel => self.dispatch(device, &mut encoder, &self.sobel_pipeline, input, &output, None),
+            Filter::Blur(radius) => {
+                let kernel_buffer = device.create_buffer_init(&BufferInitDescriptor {
+                    label: Some("Blur Kernel Buffer"),
+                    contents: bytemuck::cast_slice(&gaussian_kernel(radius)),
+                    usage: wgpu::BufferUsages::STORAGE,
+                });
+                let kernel_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    layout: &self.kernel_bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: kernel_buffer.as_entire_binding(),
+                        },
+                    ],
+                    label: Some("kernel_bind_group"),
+                });
+                // Both passes write 8 bits, like an editor blurring one axis after the other.
+                let horizontal = Texture::create_storage_target(device, "Horizontal Blur Texture", width, height, FORMAT);
+                self.dispatch(device, &mut encoder, &self.blur_horizontal_pipeline, input, &horizontal, Some(&kernel_bind_group));
+                self.dispatch(device, &mut encoder, &self.blur_vertical_pipeline, &horizontal, &output, Some(&kernel_bind_group));
+            }
+        }
+        queue.submit(Some(encoder.finish()));
+        output
+    }
+
+    fn dispatch(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::ComputePipeline,
+        input: &Texture,
+        output: &Texture,
+        kernel_bind_group: Option<&wgpu::BindGroup>,
+    ) {
+        let image_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.image_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&input.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&output.view),
+                },
+            ],
+            label: Some("image_bind_group"),
+        });
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Image Filter Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(pipeline);
+        compute_pass.set_bind_group(0, &image_bind_group, &[]);
+        if let Some(kernel_bind_group) = kernel_bind_group {
+            compute_pass.set_bind_group(1, kernel_bind_group, &[]);
+        }
+        compute_pass.dispatch_workgroups(
+            output.texture.width().div_ceil(WORKGROUP_SIZE),
+            output.texture.height().div_ceil(WORKGROUP_SIZE),
+            1,
+        );
+    }
+
+    // Blocks until the counts are read back.
+    pub fn histogram(&self, device: &wgpu::Device, queue: &wgpu::Queue, input: &Texture) -> Histogram {
+        let size = (4 * HISTOGRAM_BINS * size_of::<u32>()) as wgpu::BufferAddress;
+        let bins_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Histogram Buffer"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Histogram Readback Buffer"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.histogram_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&input.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: bins_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("histogram_bind_group"),
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {label: Some("Histogram Encoder")});
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Histogram Pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&self.histogram_pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            compute_pass.dispatch_workgroups(
+                input.texture.width().div_ceil(HISTOGRAM_WORKGROUP_SIZE),
+                input.texture.height().div_ceil(HISTOGRAM_WORKGROUP_SIZE),
+                1,
+            );
+        }
+        encoder.copy_buffer_to_buffer(&bins_buffer, 0, &readback_buffer, 0, size);
+        queue.submit(Some(encoder.finish()));
+
+        let slice = readback_buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| {});
+        device.poll(wgpu::Maintain::Wait);
+        let data = slice.get_mapped_range();
+        let bins: &[u32] = bytemuck::cast_slice(&data);
+        let mut histogram = Histogram { channels: [[0; HISTOGRAM_BINS]; 4] };
+        for (channel, counts) in histogram.channels.iter_mut().zip(bins.chunks_exact(HISTOGRAM_BINS)) {
+            channel.copy_from_slice(counts);
+        }
+        histogram
+    }
+}
+
+// 2 * radius + 1 weights adding up to 1, the outermost ones two standard deviations from the center.
+pub fn gaussian_kernel(radius: u32) -> Vec<f32> {
+    let sigma = (radius as f32 / 2.0).max(0.5);
+    let radius = radius as i32;
+    let weights: Vec<f32> = (-radius..=radius)
+        .map(|offset| (-(offset * offset) as f32 / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total: f32 = weights.iter().sum();
+    weights.into_iter().map(|weight| weight / total).collect()
+}
+
+// Blocks until the texture is copied back, it needs `COPY_SRC` like `Texture::create_storage_target`
+// and `Texture::from_image`.
+pub fn read_image(device: &wgpu::Device, queue: &wgpu::Queue, texture: &Texture) -> Result<RgbaImage> {
+    ensure!(texture.texture.format() == FORMAT, "Can only read back {FORMAT:?} textures, got {:?}", texture.texture.format());
+    let (width, height) = (texture.texture.width(), texture.texture.height());
+    // rows of a copy are 256 byte aligned, the padding is dropped below
+    let padded_row = (4 * width).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Image Readback Buffer"),
+        size: (padded_row * height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {label: Some("Image Readback Encoder")});
+    encoder.copy_texture_to_buffer(
+        wgpu::TexelCopyTextureInfo {
+            aspect: wgpu::TextureAspect::All,
+            texture: &texture.texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+        },
+        wgpu::TexelCopyBufferInfo {
+            buffer: &readback_buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row),
+                rows_per_image: Some(height),
+            },
+        },
+        texture.texture.size(),
+    );
+    queue.submit(Some(encoder.finish()));
+
+    let slice = readback_buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |_| {});
+    device.poll(wgpu::Maintain::Wait);
+    let data = slice.get_mapped_range();
+    let mut pixels = Vec::with_capacity((4 * width * height) as usize);
+    for row in data.chunks_exact(padded_row as usize) {
+        pixels.extend_from_slice(&row[..(4 * width) as usize]);
+    }
+    Ok(RgbaImage::from_raw(width, height, pixels).expect("rows were copied without padding"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_std::task;
+
+    // Fails rather than skips without an adapter, so the tests never pass without running.
+    fn gpu() -> (wgpu::Device, wgpu::Queue) {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor { backends: wgpu::Backends::all(), ..Default::default()});
+        let adapter = task::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .filter(ImageFilters::supported)
+            .expect("the image filter tests need an adapter with compute shaders");
+        task::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).unwrap()
+    }
+
+    // Gradients, a hard edged square and some noise, with an odd size so rows need padding and
+    // the workgroups overhang the edges.
+    fn test_image() -> RgbaImage {
+        RgbaImage::from_fn(37, 23, |x, y| {
+            let noise = (x.wrapping_mul(73_856_093) ^ y.wrapping_mul(19_349_663)) % 40;
+            let square = if (10..20).contains(&x) && (5..15).contains(&y) { 120 } else { 0 };
+            image::Rgba([
+                (x * 6 + noise) as u8,
+                (y * 9 + square) as u8,
+                (255 - x * 3 - square) as u8,
+                (200 + noise) as u8,
+            ])
+        })
+    }
+
+    fn filter(device: &wgpu::Device, queue: &wgpu::Queue, image: &RgbaImage, filter: Filter) -> RgbaImage {
+        let filters = ImageFilters::new(device);
+        let input = Texture::from_image(device, queue, &image.clone().into(), None, FORMAT).unwrap();
+        let output = filters.apply(device, queue, &input, filter);
+        read_image(device, queue, &output).unwrap()
+    }
+
+    // The GPU rounds its own way, off by one is as close as a reference can get.
+    fn assert_close(actual: &RgbaImage, expected: &RgbaImage) {
+        assert_eq!(actual.dimensions(), expected.dimensions());
+        for ((x, y, actual), expected) in actual.enumerate_pixels().zip(expected.pixels()) {
+            for (a, e) in actual.0.iter().zip(expected.0) {
+                assert!(a.abs_diff(e) <= 1, "pixel ({x}, {y}) is {actual:?}, expected {expected:?}");
+            }
+        }
+    }
+
+    fn to_u8(value: f32) -> u8 {
+        (value.clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+
+    fn luma(pixel: &image::Rgba<u8>) -> f32 {
+        let [r, g, b, _] = pixel.0.map(|channel| channel as f32 / 255.0);
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+
+    fn clamped(image: &RgbaImage, x: i32, y: i32) -> &image::Rgba<u8> {
+        let x = x.clamp(0, image.width() as i32 - 1) as u32;
+        let y = y.clamp(0, image.height() as i32 - 1) as u32;
+        image.get_pixel(x, y)
+    }
+
+    fn blur_pass(image: &RgbaImage, kernel: &[f32], (dx, dy): (i32, i32)) -> RgbaImage {
+        let radius = (kernel.len() / 2) as i32;
+        RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+            let mut color = [0.0; 4];
+            for (i, weight) in (-radius..=radius).zip(kernel) {
+                let pixel = clamped(image, x as i32 + dx * i, y as i32 + dy * i);
+                for (sum, channel) in color.iter_mut().zip(pixel.0) {
+                    *sum += weight * channel as f32 / 255.0;
+                }
+            }
+            image::Rgba(color.map(to_u8))
+        })
+    }
+
+    #[test]
+    fn grayscale_matches_the_cpu() {
+        let (device, queue) = gpu();
+        let image = test_image();
+        let expected = RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+            let pixel = image.get_pixel(x, y);
+            let gray = to_u8(luma(pixel));
+            image::Rgba([gray, gray, gray, pixel.0[3]])
+        });
+        assert_close(&filter(&device, &queue, &image, Filter::Grayscale), &expected);
+    }
+
+    #[test]
+    fn sobel_matches_the_cpu() {
+        let (device, queue) = gpu();
+        let image = test_image();
+        let expected = RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+            let sample = |dx: i32, dy: i32| luma(clamped(&image, x as i32 + dx, y as i32 + dy));
+            let gx = sample(1, -1) + 2.0 * sample(1, 0) + sample(1, 1) - sample(-1, -1) - 2.0 * sample(-1, 0) - sample(-1, 1);
+            let gy = sample(-1, 1) + 2.0 * sample(0, 1) + sample(1, 1) - sample(-1, -1) - 2.0 * sample(0, -1) - sample(1, -1);
+            let edge = to_u8((gx * gx + gy * gy).sqrt());
+            image::Rgba([edge, edge, edge, 255])
+        });
+        assert_close(&filter(&device, &queue, &image, Filter::Sobel), &expected);
+    }
+
+    #[test]
+    fn blur_matches_the_cpu() {
+        let (device, queue) = gpu();
+        let image = test_image();
+        for radius in [0, 1, 3, 8] {
+            let kernel = gaussian_kernel(radius);
+            let expected = blur_pass(&blur_pass(&image, &kernel, (1, 0)), &kernel, (0, 1));
+            assert_close(&filter(&device, &queue, &image, Filter::Blur(radius)), &expected);
+        }
+    }
+
+    #[test]
+    fn histogram_matches_the_cpu() {
+        let (device, queue) = gpu();
+        let filters = ImageFilters::new(&device);
+        // bigger than one workgroup tile in both directions
+        let image = RgbaImage::from_fn(70, 45, |x, y| image::Rgba([(x * 3) as u8, (x * y) as u8, 7, 255]));
+        let input = Texture::from_image(&device, &queue, &image.clone().into(), None, FORMAT).unwrap();
+        let histogram = filters.histogram(&device, &queue, &input);
+
+        let mut expected = Histogram { channels: [[0; HISTOGRAM_BINS]; 4] };
+        for pixel in image.pixels() {
+            for (channel, value) in expected.channels.iter_mut().zip(pixel.0) {
+                channel[value as usize] += 1;
+            }
+        }
+        assert_eq!(histogram, expected);
+        assert_eq!(histogram.mean(2), 7.0);
+    }
+
+    // `filter in.png out.png --histogram` writes the input back out without filtering it.
+    #[test]
+    fn unfiltered_input_reads_back_unchanged() {
+        let (device, queue) = gpu();
+        let image = test_image();
+        let input = Texture::from_image(&device, &queue, &image.clone().into(), None, FORMAT).unwrap();
+        assert_eq!(read_image(&device, &queue, &input).unwrap(), image);
+    }
+
+    #[test]
+    fn gaussian_kernel_is_normalized_and_symmetric() {
+        assert_eq!(gaussian_kernel(0), vec![1.0]);
+        let kernel = gaussian_kernel(4);
+        assert_eq!(kernel.len(), 9);
+        assert!((kernel.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+        for i in 0..4 {
+            assert_eq!(kernel[i], kernel[8 - i]);
+            assert!(kernel[i] < kernel[i + 1]);
+        }
+    }
+}
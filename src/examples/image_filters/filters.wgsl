@@ -0,0 +1,74 @@
+// One invocation per pixel. Reads outside the image are clamped to the nearest edge pixel.
+
+@group(0) @binding(0)
+var t_input: texture_2d<f32>;
+@group(0) @binding(1)
+var t_output: texture_storage_2d<rgba8unorm, write>;
+
+// `gaussian_kernel`, 2 * radius + 1 weights adding up to 1
+@group(1) @binding(0)
+var<storage, read> weights: array<f32>;
+
+fn load_clamped(position: vec2<i32>) -> vec4<f32> {
+    let size = vec2<i32>(textureDimensions(t_input));
+    return textureLoad(t_input, clamp(position, vec2<i32>(0), size - 1), 0);
+}
+
+fn luma(color: vec4<f32>) -> f32 {
+    return dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+fn outside(id: vec3<u32>) -> bool {
+    return any(id.xy >= textureDimensions(t_input));
+}
+
+@compute @workgroup_size(8, 8)
+fn grayscale(@builtin(global_invocation_id) id: vec3<u32>) {
+    if outside(id) {
+        return;
+    }
+    let color = textureLoad(t_input, id.xy, 0);
+    textureStore(t_output, id.xy, vec4<f32>(vec3<f32>(luma(color)), color.a));
+}
+
+// Gradient magnitude of the luma, opaque white on strong edges.
+@compute @workgroup_size(8, 8)
+fn sobel(@builtin(global_invocation_id) id: vec3<u32>) {
+    if outside(id) {
+        return;
+    }
+    let center = vec2<i32>(id.xy);
+    var samples: array<f32, 9>;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            samples[(y + 1) * 3 + x + 1] = luma(load_clamped(center + vec2<i32>(x, y)));
+        }
+    }
+    let gx = (samples[2] + 2.0 * samples[5] + samples[8]) - (samples[0] + 2.0 * samples[3] + samples[6]);
+    let gy = (samples[6] + 2.0 * samples[7] + samples[8]) - (samples[0] + 2.0 * samples[1] + samples[2]);
+    let magnitude = min(sqrt(gx * gx + gy * gy), 1.0);
+    textureStore(t_output, id.xy, vec4<f32>(vec3<f32>(magnitude), 1.0));
+}
+
+// The gaussian is separable, a horizontal and a vertical pass make the 2D blur.
+fn blur(id: vec3<u32>, direction: vec2<i32>) {
+    if outside(id) {
+        return;
+    }
+    let radius = i32(arrayLength(&weights) / 2u);
+    var color = vec4<f32>(0.0);
+    for (var i = -radius; i <= radius; i++) {
+        color += weights[i + radius] * load_clamped(vec2<i32>(id.xy) + direction * i);
+    }
+    textureStore(t_output, id.xy, color);
+}
+
+@compute @workgroup_size(8, 8)
+fn blur_horizontal(@builtin(global_invocation_id) id: vec3<u32>) {
+    blur(id, vec2<i32>(1, 0));
+}
+
+@compute @workgroup_size(8, 8)
+fn blur_vertical(@builtin(global_invocation_id) id: vec3<u32>) {
+    blur(id, vec2<i32>(0, 1));
+}
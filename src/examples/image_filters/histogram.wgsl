@@ -0,0 +1,32 @@
+// 256 bins for each of red, green, blue and alpha, one after another. Every workgroup counts its
+// tile into workgroup memory first, so the global atomics are hit once per bin instead of once per pixel.
+
+@group(0) @binding(0)
+var t_input: texture_2d<f32>;
+@group(0) @binding(1)
+var<storage, read_write> bins: array<atomic<u32>, 1024>;
+
+// zero initialized at the start of every workgroup
+var<workgroup> tile_bins: array<atomic<u32>, 1024>;
+
+@compute @workgroup_size(16, 16)
+fn cs_main(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_index) index: u32,
+) {
+    // no early return, every invocation has to reach the barrier
+    if all(id.xy < textureDimensions(t_input)) {
+        let color = textureLoad(t_input, id.xy, 0);
+        for (var channel = 0u; channel < 4u; channel++) {
+            let value = u32(round(color[channel] * 255.0));
+            atomicAdd(&tile_bins[channel * 256u + value], 1u);
+        }
+    }
+    workgroupBarrier();
+    for (var bin = index; bin < 1024u; bin += 256u) {
+        let count = atomicLoad(&tile_bins[bin]);
+        if count > 0u {
+            atomicAdd(&bins[bin], count);
+        }
+    }
+}
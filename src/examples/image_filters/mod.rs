@@ -0,0 +1,103 @@
+pub mod filters;
+
+use std::path::PathBuf;
+use anyhow::{
+    bail,
+    Context,
+    Result,
+};
+use async_std::task;
+use crate::examples::image_filters::filters::{
+    read_image,
+    Filter,
+    Histogram,
+    ImageFilters,
+    FORMAT,
+};
+use crate::examples::perspective_camera::texture::Texture;
+
+// Block characters for `--histogram`, from empty to full.
+const BARS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+// Bins shown per channel, every bar sums up 8 of the 256 values.
+const BAR_COUNT: usize = 32;
+
+// cargo run -- filter in.png [out.png] [--grayscale] [--sobel] [--blur radius] [--histogram]
+// Filters run in the order given, `--histogram` prints the histogram of the result (or of the input
+// when there are no filters). No window is opened.
+pub fn run(args: &[String]) -> Result<()> {
+    let mut paths = Vec::new();
+    let mut steps = Vec::new();
+    let mut histogram = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--grayscale" => steps.push(Filter::Grayscale),
+            "--sobel" => steps.push(Filter::Sobel),
+            "--blur" => {
+                let radius = args.next().context("Missing value for `--blur`")?;
+                let radius = radius.parse().with_context(|| format!("`--blur` takes a radius in pixels, got `{radius}`"))?;
+                steps.push(Filter::Blur(radius));
+            }
+            "--histogram" => histogram = true,
+            flag if flag.starts_with("--") => bail!("Unknown filter: {flag}"),
+            path => paths.push(PathBuf::from(path)),
+        }
+    }
+    let (input_path, output_path) = match paths.as_slice() {
+        [input] => (input, None),
+        [input, output] => (input, Some(output)),
+        _ => bail!("Usage: filter in.png [out.png] [--grayscale] [--sobel] [--blur radius] [--histogram]"),
+    };
+    if steps.is_empty() && !histogram {
+        bail!("No filter given");
+    }
+    if !steps.is_empty() && output_path.is_none() {
+        bail!("Filtering needs an output image");
+    }
+
+    // by content rather than extension, like `image::load_from_memory`
+    let image = image::ImageReader::open(input_path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(image::ImageError::IoError)
+        .and_then(|reader| reader.decode())
+        .with_context(|| format!("Failed to load {}", input_path.display()))?;
+
+    // wgpu, no surface to be compatible with
+    let instance= wgpu::Instance::new(&wgpu::InstanceDescriptor { backends: wgpu::Backends::all(), ..Default::default()});
+    let adapter= task::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+        .context("No graphics adapter found")?;
+    if !ImageFilters::supported(&adapter) {
+        bail!("The image filters need compute shaders, which this adapter does not have");
+    }
+    let (device, queue)= task::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))?;
+
+    let filters = ImageFilters::new(&device);
+    let mut texture = Texture::from_image(&device, &queue, &image, Some("Input Texture"), FORMAT)?;
+    for step in steps {
+        texture = filters.apply(&device, &queue, &texture, step);
+    }
+    if let Some(output_path) = output_path {
+        read_image(&device, &queue, &texture)?
+            .save(output_path)
+            .with_context(|| format!("Failed to save {}", output_path.display()))?;
+    }
+    if histogram {
+        print_histogram(&filters.histogram(&device, &queue, &texture));
+    }
+    Ok(())
+}
+
+// One line of bars per channel, scaled to the channel's fullest bar.
+fn print_histogram(histogram: &Histogram) {
+    for (channel, name) in ["red", "green", "blue", "alpha"].into_iter().enumerate() {
+        let bars: Vec<u32> = histogram.channels[channel]
+            .chunks_exact(histogram.channels[channel].len() / BAR_COUNT)
+            .map(|bins| bins.iter().sum())
+            .collect();
+        let fullest = bars.iter().copied().max().unwrap_or(0).max(1);
+        let line: String = bars.iter()
+            .map(|&count| BARS[(count as usize * (BARS.len() - 1)).div_ceil(fullest as usize)])
+            .collect();
+        println!("{name:>5} |{line}| mean {:.1}", histogram.mean(channel));
+    }
+}
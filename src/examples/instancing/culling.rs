@@ -0,0 +1,197 @@
+use wgpu::util::{
+    BufferInitDescriptor,
+    DeviceExt,
+    DrawIndexedIndirectArgs,
+};
+use crate::examples::instancing::instance::{
+    InstanceBuffer,
+    InstanceRaw,
+};
+use crate::examples::perspective_camera::frustum::Frustum;
+
+const WORKGROUP_SIZE: u32 = 64;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct CullParams {
+    planes: [[f32; 4]; 6],
+    count: u32,
+    radius: f32,
+    _padding: [u32; 2],
+}
+
+// Frustum culling in a compute pass. Every instance of an `InstanceBuffer` is tested against the
+// frustum, the visible ones are compacted into `visible` and counted into an indirect draw, so the
+// CPU never learns how many there are. Draw with `visible_slice` as the instance buffer and
+// `draw_indexed_indirect(indirect_buffer(), 0)`.
+pub struct GpuCulling {
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    params_buffer: wgpu::Buffer,
+    visible_buffer: wgpu::Buffer,
+    indirect_buffer: wgpu::Buffer,
+    // of the instance buffer the bind group was made for
+    capacity: usize,
+    count: u32,
+    index_count: u32,
+}
+impl GpuCulling {
+    // Compute shaders and indirect draws are missing on WebGL2 and old GL drivers.
+    pub fn supported(adapter: &wgpu::Adapter) -> bool {
+        adapter.get_downlevel_capabilities().flags.contains(
+            wgpu::DownlevelFlags::COMPUTE_SHADERS | wgpu::DownlevelFlags::INDIRECT_EXECUTION)
+    }
+
+    // `index_count` is the mesh's, every indirect draw covers all of it.
+    pub fn new(device: &wgpu::Device, instances: &InstanceBuffer, index_count: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Culling Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("culling.wgsl").into()),
+        });
+        let storage = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage(1, true),
+                storage(2, false),
+                storage(3, false),
+            ],
+            label: Some("culling_bind_group_layout"),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Culling Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Culling Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("cs_main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Culling Params Buffer"),
+            size: size_of::<CullParams>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let indirect_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Indirect Draw Buffer"),
+            contents: DrawIndexedIndirectArgs { index_count, ..Default::default() }.as_bytes(),
+            usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+        let visible_buffer = Self::create_visible_buffer(device, instances.capacity());
+        let bind_group = Self::create_bind_group(device, &bind_group_layout, &params_buffer, instances, &visible_buffer, &indirect_buffer);
+        Self {
+            pipeline,
+            bind_group_layout,
+            bind_group,
+            params_buffer,
+            visible_buffer,
+            indirect_buffer,
+            capacity: instances.capacity(),
+            count: instances.count(),
+            index_count,
+        }
+    }
+
+    fn create_visible_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Visible Instance Buffer"),
+            size: (capacity.max(1) * size_of::<InstanceRaw>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        params_buffer: &wgpu::Buffer,
+        instances: &InstanceBuffer,
+        visible_buffer: &wgpu::Buffer,
+        indirect_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: instances.buffer().as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: visible_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: indirect_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("culling_bind_group"),
+        })
+    }
+
+    // Call after `instances` was updated for the frame, `radius` bounds the mesh at scale 1.
+    pub fn update(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, instances: &InstanceBuffer, frustum: &Frustum, radius: f32) {
+        if instances.capacity() != self.capacity {
+            self.capacity = instances.capacity();
+            self.visible_buffer = Self::create_visible_buffer(device, self.capacity);
+            self.bind_group = Self::create_bind_group(device, &self.bind_group_layout, &self.params_buffer, instances, &self.visible_buffer, &self.indirect_buffer);
+        }
+        self.count = instances.count();
+        let params = CullParams {
+            planes: frustum.planes.map(|plane| plane.normal.extend(plane.distance).into()),
+            count: self.count,
+            radius,
+            _padding: [0; 2],
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&[params]));
+        // The pass only adds to the instance count, it starts from zero every frame.
+        queue.write_buffer(&self.indirect_buffer, 0, DrawIndexedIndirectArgs { index_count: self.index_count, ..Default::default() }.as_bytes());
+    }
+
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder) {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Culling Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.pipeline);
+        compute_pass.set_bind_group(0, &self.bind_group, &[]);
+        compute_pass.dispatch_workgroups(self.count.div_ceil(WORKGROUP_SIZE), 1, 1);
+    }
+
+    pub fn visible_slice(&self) -> wgpu::BufferSlice<'_> {
+        self.visible_buffer.slice(..)
+    }
+
+    pub fn indirect_buffer(&self) -> &wgpu::Buffer {
+        &self.indirect_buffer
+    }
+}
@@ -0,0 +1,53 @@
+// One invocation per instance, the ones inside the frustum are appended to `visible`
+// and counted straight into the indirect draw's instance count.
+
+// `InstanceRaw`
+struct Instance {
+    model: mat4x4<f32>,
+    color: vec4<f32>,
+}
+
+struct CullParams {
+    // inward facing, xyz normal and w distance
+    planes: array<vec4<f32>, 6>,
+    count: u32,
+    // bounding sphere of the mesh, scaled by the instance
+    radius: f32,
+}
+
+// `wgpu::util::DrawIndexedIndirectArgs`
+struct DrawIndexedIndirectArgs {
+    index_count: u32,
+    instance_count: atomic<u32>,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: CullParams;
+@group(0) @binding(1)
+var<storage, read> instances: array<Instance>;
+@group(0) @binding(2)
+var<storage, read_write> visible: array<Instance>;
+@group(0) @binding(3)
+var<storage, read_write> draw: DrawIndexedIndirectArgs;
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.count {
+        return;
+    }
+    let instance = instances[id.x];
+    let center = instance.model[3].xyz;
+    let radius = params.radius * length(instance.model[0].xyz);
+    for (var i = 0u; i < 6u; i++) {
+        let plane = params.planes[i];
+        if dot(plane.xyz, center) + plane.w < -radius {
+            return;
+        }
+    }
+    // the order of `visible` changes from frame to frame, which does not matter for opaque cubes
+    let slot = atomicAdd(&draw.instance_count, 1u);
+    visible[slot] = instance;
+}
@@ -0,0 +1,106 @@
+use cgmath::{
+    Matrix4,
+    Quaternion,
+    Vector3,
+};
+
+// One copy of a mesh. `rotation` and a uniform `scale` keep the model matrix usable for normals.
+#[derive(Copy, Clone, Debug)]
+pub struct Instance {
+    pub position: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: f32,
+    // multiplies the texture, linear RGBA
+    pub color: [f32; 4],
+}
+impl Instance {
+    pub fn to_raw(self) -> InstanceRaw {
+        let model = Matrix4::from_translation(self.position)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_scale(self.scale);
+        InstanceRaw {
+            model: model.into(),
+            color: self.color,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct InstanceRaw {
+    model: [[f32; 4]; 4],
+    color: [f32; 4],
+}
+impl InstanceRaw {
+    // Advances once per instance instead of once per vertex. Starts at location 5, after the
+    // mesh's vertex attributes, a mat4x4 takes four locations.
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+            5 => Float32x4,
+            6 => Float32x4,
+            7 => Float32x4,
+            8 => Float32x4,
+            9 => Float32x4,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+// Per-instance data on the GPU, bound as a second vertex buffer next to the mesh
+// or read as an array of `InstanceRaw` by the culling pass.
+pub struct InstanceBuffer {
+    buffer: wgpu::Buffer,
+    capacity: usize,
+    count: u32,
+}
+impl InstanceBuffer {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, instances: &[Instance]) -> Self {
+        let buffer = Self::create_buffer(device, instances.len());
+        let mut instance_buffer = Self { buffer, capacity: instances.len(), count: 0 };
+        instance_buffer.update(device, queue, instances);
+        instance_buffer
+    }
+
+    fn create_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance Buffer"),
+            size: (capacity.max(1) * size_of::<InstanceRaw>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    // Uploads `instances`, call it whenever they change, e.g. every frame. The buffer is
+    // reallocated when there are more instances than it was created for.
+    pub fn update(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, instances: &[Instance]) {
+        if instances.len() > self.capacity {
+            self.capacity = instances.len().next_power_of_two();
+            self.buffer = Self::create_buffer(device, self.capacity);
+        }
+        let data: Vec<InstanceRaw> = instances.iter().copied().map(Instance::to_raw).collect();
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&data));
+        self.count = instances.len() as u32;
+    }
+
+    // The instance range for `draw_indexed`.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    // How many instances fit before `update` reallocates, which replaces `buffer`.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    pub fn slice(&self) -> wgpu::BufferSlice<'_> {
+        self.buffer.slice(..)
+    }
+}
@@ -0,0 +1,577 @@
+pub mod culling;
+pub mod instance;
+
+use std::sync::Arc;
+use winit::{
+    event_loop::{
+        EventLoop,
+        ActiveEventLoop,
+        ControlFlow,
+    },
+    event::{
+        DeviceEvent,
+        DeviceId,
+        StartCause,
+        WindowEvent
+    },
+    window::WindowId,
+    dpi::PhysicalSize,
+    application::ApplicationHandler,
+};
+use async_std::task;
+use cgmath::{
+    EuclideanSpace,
+    InnerSpace,
+    Point3,
+    Quaternion,
+    Rad,
+    Rotation3,
+    Vector3,
+};
+use crate::input::{
+    Action,
+    Input,
+};
+use crate::window::{
+    ManagedWindow,
+    WindowConfig,
+};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use crate::examples::perspective_camera::camera::{
+    Camera,
+    CameraController,
+};
+use crate::examples::perspective_camera::frustum::{
+    CullStats,
+    Frustum,
+};
+use crate::examples::perspective_camera::texture;
+use crate::examples::lighting::CameraUniform;
+use crate::examples::raster_mode::{
+    RasterMode,
+    RasterPipelines,
+};
+use crate::examples::lighting::mesh::{
+    Mesh,
+    MeshData,
+    Vertex,
+};
+use crate::examples::instancing::culling::GpuCulling;
+use crate::examples::instancing::instance::{
+    Instance,
+    InstanceBuffer,
+    InstanceRaw,
+};
+
+// Cubes per side of the grid, GRID_SIZE² are drawn with a single call.
+const GRID_SIZE: u32 = 64;
+const SPACING: f32 = 1.5;
+// Bounding sphere of the unit cube, half its diagonal.
+const CUBE_RADIUS: f32 = 0.866_025_4;
+
+// A spinning cube per grid cell, tinted by where it sits.
+struct Cube {
+    position: Vector3<f32>,
+    axis: Vector3<f32>,
+    // radians per second
+    speed: f32,
+    color: [f32; 4],
+}
+
+fn grid() -> Vec<Cube> {
+    let half = (GRID_SIZE - 1) as f32 * SPACING * 0.5;
+    (0..GRID_SIZE * GRID_SIZE)
+        .map(|index| {
+            let (x, z) = ((index % GRID_SIZE) as f32, (index / GRID_SIZE) as f32);
+            let (u, v) = (x / (GRID_SIZE - 1) as f32, z / (GRID_SIZE - 1) as f32);
+            Cube {
+                position: Vector3::new(x * SPACING - half, 0.0, z * SPACING - half),
+                axis: Vector3::new(u - 0.5, 1.0, v - 0.5).normalize(),
+                speed: 0.5 + 2.0 * ((x * 0.37 + z * 0.61).sin() * 0.5 + 0.5),
+                color: [0.3 + 0.7 * u, 0.5, 0.3 + 0.7 * v, 1.0],
+            }
+        })
+        .collect()
+}
+
+struct Application {
+    window_config: WindowConfig,
+    window: Option<ManagedWindow>,
+    input: Input,
+    camera: Camera,
+    camera_controller: CameraController,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group_layout: wgpu::BindGroupLayout,
+    camera_bind_group: wgpu::BindGroup,
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    // fill, wireframe and points, picked by `raster_mode`
+    render_pipelines: Option<RasterPipelines>,
+    raster_mode: RasterMode,
+    depth_texture: Option<texture::Texture>,
+    // drawn into and resolved to the frame, `None` with 1x MSAA
+    msaa_target: Option<wgpu::TextureView>,
+    sample_count: u32,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    diffuse_bind_group: wgpu::BindGroup,
+    mesh: Mesh,
+    cubes: Vec<Cube>,
+    instances: Vec<Instance>,
+    // the instances inside the camera's frustum, what `instance_buffer` holds
+    visible: Vec<Instance>,
+    cull_stats: CullStats,
+    instance_buffer: InstanceBuffer,
+    // `None` when the adapter has no compute shaders or indirect draws
+    gpu_culling: Option<GpuCulling>,
+    use_gpu_culling: bool,
+    time: f32,
+}
+
+impl Application {
+    fn init(&mut self, event_loop: &ActiveEventLoop) {
+        let window = Arc::new(event_loop.create_window(self.window_config.attributes(event_loop))
+            .expect("Failed to create window"));
+        let managed = ManagedWindow::new(
+            window,
+            &self.instance,
+            &self.adapter,
+            &self.device,
+            self.window_config.present_mode(),
+            None,
+        ).expect("Failed to create surface");
+        let surface_format = managed.surface_config.format;
+        self.sample_count = self.window_config.sample_count(&self.adapter, &self.device, &[surface_format, texture::Texture::DEPTH_FORMAT]);
+
+        // SHADER
+        let shader = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Instancing Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
+        });
+
+        // RENDER PIPELINE
+        let pipeline_layout = self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some("Instancing Pipeline Layout"),
+            bind_group_layouts: &[
+                &self.texture_bind_group_layout,
+                &self.camera_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = wgpu::RenderPipelineDescriptor {
+            label: Some("Instancing Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[
+                    Vertex::desc(),
+                    InstanceRaw::desc(),
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                ..wgpu::PrimitiveState::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: texture::Texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: self.sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        };
+
+        self.render_pipelines = Some(RasterPipelines::new(&self.device, &render_pipeline));
+        self.depth_texture = Some(texture::Texture::create_depth_texture(&self.device, &managed.surface_config, self.sample_count, "depth_texture"));
+        self.msaa_target = WindowConfig::create_msaa_view(&self.device, &managed.surface_config, managed.surface_config.format, self.sample_count);
+        self.camera.aspect = managed.aspect();
+        self.window = Some(managed);
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>) {
+        let Some(managed) = self.window.as_mut() else {
+            return;
+        };
+        if !managed.resize(&self.device, size) {
+            return;
+        }
+        self.depth_texture = Some(texture::Texture::create_depth_texture(&self.device, &managed.surface_config, self.sample_count, "depth_texture"));
+        self.msaa_target = WindowConfig::create_msaa_view(&self.device, &managed.surface_config, managed.surface_config.format, self.sample_count);
+        self.camera.aspect = managed.aspect();
+        managed.window.request_redraw();
+    }
+
+    // Spins every cube and uploads the ones the camera sees along with the camera.
+    // With GPU culling all of them are uploaded and the culling pass picks the visible ones.
+    fn update(&mut self) {
+        self.queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[CameraUniform::new(&self.camera)]));
+        self.time += self.input.dt();
+        for (instance, cube) in self.instances.iter_mut().zip(&self.cubes) {
+            instance.rotation = Quaternion::from_axis_angle(cube.axis, Rad(cube.speed * self.time));
+        }
+        let frustum = Frustum::from_view_projection(self.camera.build_view_projection_matrix());
+        if self.use_gpu_culling
+            && let Some(gpu_culling) = self.gpu_culling.as_mut() {
+            self.instance_buffer.update(&self.device, &self.queue, &self.instances);
+            gpu_culling.update(&self.device, &self.queue, &self.instance_buffer, &frustum, CUBE_RADIUS);
+            return;
+        }
+        let mut stats = CullStats::default();
+        self.visible.clear();
+        for instance in &self.instances {
+            let visible = frustum.intersects_sphere(Point3::from_vec(instance.position), CUBE_RADIUS * instance.scale);
+            stats.count(visible);
+            if visible {
+                self.visible.push(*instance);
+            }
+        }
+        if stats != self.cull_stats {
+            self.cull_stats = stats;
+            println!("Cubes: {stats}");
+        }
+        self.instance_buffer.update(&self.device, &self.queue, &self.visible);
+    }
+
+    fn render(&self) {
+        let (Some(managed), Some(depth_texture)) = (self.window.as_ref(), self.depth_texture.as_ref()) else {
+            return;
+        };
+        let Some(surface) = managed.surface.as_ref() else {
+            return;
+        };
+        let frame = surface.get_current_texture().unwrap();
+        let frame_view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {label: Some("Render Encoder")});
+        let gpu_culling = self.gpu_culling.as_ref().filter(|_| self.use_gpu_culling);
+        if let Some(gpu_culling) = gpu_culling {
+            gpu_culling.render(&mut encoder);
+        }
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: self.msaa_target.as_ref().unwrap_or(&frame_view),
+                resolve_target: self.msaa_target.as_ref().map(|_| &frame_view),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.1,
+                        g: 0.1,
+                        b: 0.12,
+                        a: 1.0 }),
+                    store: wgpu::StoreOp::Store,
+                }
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &depth_texture.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass
+            .set_pipeline(
+                self.render_pipelines
+                    .as_ref()
+                    .unwrap()
+                    .get_or_fill(self.raster_mode));
+        render_pass
+            .set_bind_group(
+                0,
+                &self.diffuse_bind_group,
+                &[]);
+        render_pass
+            .set_bind_group(
+                1,
+                &self.camera_bind_group,
+                &[]);
+        render_pass
+            .set_vertex_buffer(
+                0,
+                self.mesh.vertex_buffer.slice(..));
+        render_pass
+            .set_vertex_buffer(
+                1,
+                gpu_culling.map_or(self.instance_buffer.slice(), GpuCulling::visible_slice));
+        render_pass
+            .set_index_buffer(
+                self.mesh.index_buffer.slice(..),
+                wgpu::IndexFormat::Uint32);
+        match gpu_culling {
+            Some(gpu_culling) => render_pass
+                .draw_indexed_indirect(
+                    gpu_culling.indirect_buffer(),
+                    0),
+            None => render_pass
+                .draw_indexed(
+                    0..self.mesh.index_count,
+                    0,
+                    0..self.instance_buffer.count()),
+        }
+        drop(render_pass);
+
+        let command_buffer = encoder.finish();
+        self.queue.submit(Some(command_buffer));
+        frame.present();
+    }
+}
+impl ApplicationHandler for Application {
+    fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
+        let _ = (event_loop, cause);
+    }
+
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(managed) = self.window.as_mut() else {
+            self.init(event_loop);
+            return;
+        };
+        if let Err(error) = managed.resume(&self.instance, &self.device) {
+            eprintln!("{error:#}");
+            event_loop.exit();
+            return;
+        }
+        let size = managed.window.inner_size();
+        self.resize(size);
+    }
+
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, _event: ()) {}
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _window_id: WindowId, event: WindowEvent) {
+        self.input.handle_window_event(&event);
+        match event {
+            WindowEvent::CloseRequested => {
+                if let Some(managed) = self.window.as_ref() {
+                    self.window_config.store(&managed.window);
+                }
+                event_loop.exit();
+            }
+            WindowEvent::RedrawRequested => { self.render(); }
+            WindowEvent::Resized(size) => { self.resize(size); }
+            _ => {}
+        }
+    }
+
+    fn device_event(&mut self, _event_loop: &ActiveEventLoop, _device_id: DeviceId, event: DeviceEvent) {
+        self.input.handle_device_event(&event);
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.input.begin_frame();
+        let Some(window) = self.window.as_ref().map(|managed| managed.window.clone()) else {
+            self.input.end_frame();
+            return;
+        };
+        if self.window_config.handle_input(&window, &self.input) {
+            self.resize(window.inner_size());
+        }
+        if self.input.pressed(Action::CameraOrbit) {
+            window.set_cursor_visible(false);
+        }
+        if self.input.released(Action::CameraOrbit) {
+            window.set_cursor_visible(true);
+        }
+        if let Some(render_pipelines) = self.render_pipelines.as_ref() {
+            render_pipelines.handle_input(&mut self.raster_mode, &self.input);
+        }
+        if self.input.pressed(Action::ToggleGpuCulling) {
+            if self.gpu_culling.is_some() {
+                self.use_gpu_culling = !self.use_gpu_culling;
+                // the GPU keeps its counts to itself, print the CPU ones again when coming back
+                self.cull_stats = CullStats::default();
+                println!("Culling on the {}", if self.use_gpu_culling { "GPU" } else { "CPU" });
+            } else {
+                println!("Culling stays on the CPU, the adapter has no compute shaders or indirect draws");
+            }
+        }
+        self.camera_controller.update(&mut self.camera, &self.input);
+
+        // The cubes spin, redraw continuously.
+        self.update();
+        window.request_redraw();
+        event_loop.set_control_flow(ControlFlow::Poll);
+        self.input.end_frame();
+    }
+
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(managed) = self.window.as_mut() {
+            managed.suspend();
+        }
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {}
+
+    fn memory_warning(&mut self, _event_loop: &ActiveEventLoop) {}
+}
+
+pub fn run(window_config: WindowConfig, input: Input) {
+    // winit
+    let event_loop = EventLoop::new().unwrap();
+
+    // wgpu
+    let instance= wgpu::Instance::new(&wgpu::InstanceDescriptor { backends: wgpu::Backends::all(), ..Default::default()});
+    let adapter= task::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())).unwrap();
+    let (device, queue)= task::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+        // `PolygonMode::Line` for the wireframe raster mode
+        required_features: WindowConfig::msaa_features(&adapter)
+            | (adapter.features() & wgpu::Features::POLYGON_MODE_LINE),
+        ..Default::default()
+    }, None, )).unwrap();
+
+    // CAMERA
+    let settings = &window_config.settings;
+    let camera = Camera {
+        eye: (0.0, 35.0, 70.0).into(),
+        target: (0.0, 0.0, 0.0).into(),
+        up: Vector3::unit_y(),
+        aspect: settings.width as f32 / settings.height as f32,
+        fovy: 45.0,
+        znear: 0.1,
+        zfar: 300.0
+    };
+    let camera_buffer = device.create_buffer_init(&BufferInitDescriptor {
+        label: Some("Camera Buffer"),
+        contents: bytemuck::cast_slice(&[CameraUniform::new(&camera)]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+    let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None
+                },
+                count: None
+            }
+        ],
+        label: Some("camera_bind_group_layout"),
+    });
+    let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &camera_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding()
+            }
+        ],
+        label: Some("camera_bind_group"),
+    });
+
+    // TEXTURE
+    let texture_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+        label: Some("texture_bind_group_layout")
+    });
+    let diffuse_texture = texture::Texture::from_bytes(
+        &device,
+        &queue,
+        include_bytes!("../perspective_camera/UV_Grid.png"),
+        "diffuse_texture"
+    ).unwrap();
+    let diffuse_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &texture_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&diffuse_texture.view)
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&diffuse_texture.sampler)
+            },
+        ],
+        label: Some("diffuse_bind_group"),
+    });
+
+    // INSTANCES
+    let cubes = grid();
+    let instances: Vec<Instance> = cubes.iter()
+        .map(|cube| Instance {
+            position: cube.position,
+            rotation: Quaternion::from_axis_angle(cube.axis, Rad(0.0)),
+            scale: 1.0,
+            color: cube.color,
+        })
+        .collect();
+    let instance_buffer = InstanceBuffer::new(&device, &queue, &instances);
+    let mesh = Mesh::new(&device, "Cube", &MeshData::cube());
+    let gpu_culling = GpuCulling::supported(&adapter)
+        .then(|| GpuCulling::new(&device, &instance_buffer, mesh.index_count));
+    let use_gpu_culling = gpu_culling.is_some();
+
+    let mut application = Application{
+        window_config,
+        window: None,
+        input,
+        camera,
+        camera_controller: CameraController::new(15.0, 1.5, 0.005),
+        camera_buffer,
+        camera_bind_group_layout,
+        camera_bind_group,
+        instance,
+        adapter,
+        device,
+        queue,
+        render_pipelines: None,
+        raster_mode: RasterMode::Fill,
+        depth_texture: None,
+        msaa_target: None,
+        sample_count: 1,
+        texture_bind_group_layout,
+        diffuse_bind_group,
+        mesh,
+        cubes,
+        instances,
+        visible: Vec::new(),
+        cull_stats: CullStats::default(),
+        instance_buffer,
+        gpu_culling,
+        use_gpu_culling,
+        time: 0.0,
+    };
+
+    event_loop.run_app(&mut application).expect("Failed to start event_loop");
+    println!("instancing example");
+}
@@ -0,0 +1,61 @@
+// VERTEX SHADER
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    view_position: vec4<f32>,
+};
+@group(1) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) tex_coords: vec2<f32>,
+}
+
+// `InstanceRaw`, the model matrix arrives as four columns
+struct InstanceInput {
+    @location(5) model_0: vec4<f32>,
+    @location(6) model_1: vec4<f32>,
+    @location(7) model_2: vec4<f32>,
+    @location(8) model_3: vec4<f32>,
+    @location(9) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_normal: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    vertex: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    let model = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+    var out: VertexOutput;
+    // instances only rotate and scale uniformly, the model matrix works for normals
+    out.world_normal = (model * vec4<f32>(vertex.normal, 0.0)).xyz;
+    out.tex_coords = vertex.tex_coords;
+    out.color = instance.color;
+    out.clip_position = camera.view_proj * model * vec4<f32>(vertex.position, 1.0);
+    return out;
+}
+
+// FRAGMENT SHADER
+
+@group(0) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(0) @binding(1)
+var s_diffuse: sampler;
+
+const LIGHT_DIRECTION: vec3<f32> = vec3<f32>(0.4, 1.0, 0.3);
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let albedo = textureSample(t_diffuse, s_diffuse, in.tex_coords) * in.color;
+    let diffuse = max(dot(normalize(in.world_normal), normalize(LIGHT_DIRECTION)), 0.0);
+    return vec4<f32>(albedo.rgb * (0.2 + 0.8 * diffuse), albedo.a);
+}
@@ -0,0 +1,320 @@
+use std::ops::Range;
+use cgmath::{
+    InnerSpace,
+    Vector3,
+};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use crate::examples::lighting::mesh::MeshData;
+use crate::examples::lighting::model::Models;
+use crate::examples::perspective_camera::texture::Texture;
+use crate::examples::raster_mode::RasterMode;
+use crate::input::{
+    Action,
+    Input,
+};
+
+// Lines drawn from every vertex on top of the scene.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugVectors {
+    Off,
+    Normals,
+    // tangent and bitangent, the bitangent includes the handedness in `tangent.w`
+    Tangents,
+}
+impl DebugVectors {
+    pub fn next(self) -> Self {
+        match self {
+            DebugVectors::Off => DebugVectors::Normals,
+            DebugVectors::Normals => DebugVectors::Tangents,
+            DebugVectors::Tangents => DebugVectors::Off,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct WireframeVertex {
+    position: [f32; 3],
+    barycentric: [f32; 3],
+}
+impl WireframeVertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![
+            0 => Float32x3,
+            1 => Float32x3,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<WireframeVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct VectorVertex {
+    position: [f32; 3],
+    direction: [f32; 3],
+    // 0 normal, 1 tangent, 2 bitangent
+    kind: u32,
+    // 0 at the vertex, 1 at the tip
+    end: f32,
+}
+impl VectorVertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+            0 => Float32x3,
+            1 => Float32x3,
+            2 => Uint32,
+            3 => Float32,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<VectorVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+// The extra geometry `DebugView` draws for a mesh, made next to its `Mesh` from the same data.
+pub struct DebugMesh {
+    // every triangle with its own three vertices, only without `POLYGON_MODE_LINE`
+    wireframe_buffer: Option<wgpu::Buffer>,
+    wireframe_count: u32,
+    // normal lines first, then tangent and bitangent lines
+    vector_buffer: wgpu::Buffer,
+    // two per vertex of the mesh, for one line each
+    line_vertices: u32,
+}
+impl DebugMesh {
+    pub fn new(device: &wgpu::Device, label: &str, data: &MeshData) -> Self {
+        let wireframe_buffer = (!device.features().contains(wgpu::Features::POLYGON_MODE_LINE)).then(|| {
+            device.create_buffer_init(&BufferInitDescriptor {
+                label: Some(&format!("{label} Wireframe Buffer")),
+                contents: bytemuck::cast_slice(&wireframe_vertices(data)),
+                usage: wgpu::BufferUsages::VERTEX,
+            })
+        });
+        let vector_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some(&format!("{label} Vector Buffer")),
+            contents: bytemuck::cast_slice(&vector_vertices(data)),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        Self {
+            wireframe_buffer,
+            wireframe_count: data.indices.len() as u32,
+            vector_buffer,
+            line_vertices: 2 * data.vertices.len() as u32,
+        }
+    }
+}
+
+// Indices can't tell a shader which corner of its triangle a vertex is, so the triangles are unshared.
+fn wireframe_vertices(data: &MeshData) -> Vec<WireframeVertex> {
+    const CORNERS: [[f32; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    data.indices.chunks_exact(3)
+        .flat_map(|triangle| triangle.iter().zip(CORNERS))
+        .map(|(&index, barycentric)| WireframeVertex {
+            position: data.vertices[index as usize].position,
+            barycentric,
+        })
+        .collect()
+}
+
+// A line list, two vertices per line.
+fn vector_vertices(data: &MeshData) -> Vec<VectorVertex> {
+    let line = |position: [f32; 3], direction: Vector3<f32>, kind: u32| {
+        [0.0, 1.0].map(|end| VectorVertex { position, direction: direction.into(), kind, end })
+    };
+    let normals = data.vertices.iter()
+        .flat_map(|vertex| line(vertex.position, vertex.normal.into(), 0));
+    let tangents = data.vertices.iter().flat_map(|vertex| {
+        let normal = Vector3::from(vertex.normal);
+        let tangent = Vector3::new(vertex.tangent[0], vertex.tangent[1], vertex.tangent[2]);
+        let bitangent = normal.cross(tangent).normalize() * vertex.tangent[3];
+        line(vertex.position, tangent, 1).into_iter().chain(line(vertex.position, bitangent, 2))
+    });
+    normals.chain(tangents).collect()
+}
+
+// Wireframe fallback and normal/tangent lines, drawn into the scene pass after the objects.
+pub struct DebugView {
+    pub raster_mode: RasterMode,
+    pub vectors: DebugVectors,
+    // only without `POLYGON_MODE_LINE`, `RasterPipelines` has a wireframe variant otherwise
+    wireframe_pipeline: Option<wgpu::RenderPipeline>,
+    vector_pipeline: wgpu::RenderPipeline,
+}
+impl DebugView {
+    // `camera_bind_group_layout` has the `CameraUniform` at binding 0, `sample_count` matches the
+    // pass it is drawn in.
+    pub fn new(
+        device: &wgpu::Device,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        models: &Models,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Debug View Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("debug_view.wgsl").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some("Debug View Pipeline Layout"),
+            bind_group_layouts: &[camera_bind_group_layout, &models.bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |label: &str, entry_point: (&str, &str), buffer: wgpu::VertexBufferLayout, topology: wgpu::PrimitiveTopology, depth_write_enabled: bool| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some(entry_point.0),
+                    compilation_options: Default::default(),
+                    buffers: &[buffer],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some(entry_point.1),
+                    compilation_options: Default::default(),
+                    targets: &[Some(format.into())],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology,
+                    ..wgpu::PrimitiveState::default()
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: Texture::DEPTH_FORMAT,
+                    depth_write_enabled,
+                    depth_compare: wgpu::CompareFunction::LessEqual,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    ..Default::default()
+                },
+                multiview: None,
+                cache: None,
+            })
+        };
+        // stands in for the scene's own pipelines, so it writes depth like they do
+        let wireframe_pipeline = (!device.features().contains(wgpu::Features::POLYGON_MODE_LINE)).then(|| {
+            pipeline("Wireframe Pipeline", ("vs_wireframe", "fs_wireframe"), WireframeVertex::desc(), wgpu::PrimitiveTopology::TriangleList, true)
+        });
+        let vector_pipeline = pipeline("Vector Pipeline", ("vs_vector", "fs_vector"), VectorVertex::desc(), wgpu::PrimitiveTopology::LineList, false);
+        Self {
+            raster_mode: RasterMode::Fill,
+            vectors: DebugVectors::Off,
+            wireframe_pipeline,
+            vector_pipeline,
+        }
+    }
+
+    pub fn handle_input(&mut self, input: &Input) {
+        self.raster_mode.handle_input(input);
+        if input.pressed(Action::CycleDebugVectors) {
+            self.vectors = self.vectors.next();
+            println!("Debug vectors: {:?}", self.vectors);
+        }
+    }
+
+    // `draws` pairs every object's mesh with its slot in `models`, like `Shadows::render`.
+    pub fn render(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        camera_bind_group: &wgpu::BindGroup,
+        models: &Models,
+        draws: &[(&DebugMesh, usize)],
+    ) {
+        let wireframe_pipeline = self.wireframe_pipeline.as_ref().filter(|_| self.raster_mode == RasterMode::Wireframe);
+        if wireframe_pipeline.is_none() && self.vectors == DebugVectors::Off {
+            return;
+        }
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        if let Some(wireframe_pipeline) = wireframe_pipeline {
+            render_pass.set_pipeline(wireframe_pipeline);
+            for (mesh, slot) in draws {
+                let Some(wireframe_buffer) = mesh.wireframe_buffer.as_ref() else {
+                    continue;
+                };
+                render_pass.set_bind_group(1, &models.bind_group, &[models.offset(*slot)]);
+                render_pass.set_vertex_buffer(0, wireframe_buffer.slice(..));
+                render_pass.draw(0..mesh.wireframe_count, 0..1);
+            }
+        }
+        if self.vectors == DebugVectors::Off {
+            return;
+        }
+        render_pass.set_pipeline(&self.vector_pipeline);
+        for (mesh, slot) in draws {
+            render_pass.set_bind_group(1, &models.bind_group, &[models.offset(*slot)]);
+            render_pass.set_vertex_buffer(0, mesh.vector_buffer.slice(..));
+            render_pass.draw(vector_range(self.vectors, mesh.line_vertices), 0..1);
+        }
+    }
+}
+
+// The part of a mesh's `vector_vertices` drawn for `vectors`, `line_vertices` is the length of one
+// kind of line. Counted in lines per vertex, the tangent lines come after the normal lines.
+fn vector_range(vectors: DebugVectors, line_vertices: u32) -> Range<u32> {
+    let lines = match vectors {
+        DebugVectors::Off => 0..0,
+        DebugVectors::Normals => 0..1,
+        DebugVectors::Tangents => 1..3,
+    };
+    lines.start * line_vertices..lines.end * line_vertices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wireframe_vertices_put_every_corner_of_a_triangle_on_its_own_axis() {
+        let data = MeshData::cube();
+        let vertices = wireframe_vertices(&data);
+        assert_eq!(vertices.len(), data.indices.len());
+        for (triangle, indices) in vertices.chunks_exact(3).zip(data.indices.chunks_exact(3)) {
+            let corners: Vec<[f32; 3]> = triangle.iter().map(|vertex| vertex.barycentric).collect();
+            assert_eq!(corners, [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+            for (vertex, &index) in triangle.iter().zip(indices) {
+                assert_eq!(vertex.position, data.vertices[index as usize].position);
+            }
+        }
+    }
+
+    #[test]
+    fn vector_ranges_select_normals_or_tangent_frames() {
+        let data = MeshData::cube();
+        let vertices = vector_vertices(&data);
+        let line_vertices = 2 * data.vertices.len() as u32;
+        assert_eq!(vertices.len() as u32, 3 * line_vertices);
+        let kinds = |vectors| {
+            let range = vector_range(vectors, line_vertices);
+            vertices[range.start as usize..range.end as usize].iter().map(|vertex| vertex.kind).collect::<Vec<_>>()
+        };
+        assert!(kinds(DebugVectors::Off).is_empty());
+        assert_eq!(kinds(DebugVectors::Normals), vec![0; line_vertices as usize]);
+        assert_eq!(kinds(DebugVectors::Tangents), [1, 1, 2, 2].repeat(data.vertices.len()));
+
+        // lines start at their vertex and point along the vertex's frame
+        for (line, vertex) in vertices[..line_vertices as usize].chunks_exact(2).zip(&data.vertices) {
+            let ends: Vec<_> = line.iter().map(|end| (end.position, end.direction, end.end)).collect();
+            assert_eq!(ends, [
+                (vertex.position, vertex.normal, 0.0),
+                (vertex.position, vertex.normal, 1.0),
+            ]);
+        }
+        for (frame, vertex) in vertices[line_vertices as usize..].chunks_exact(4).zip(&data.vertices) {
+            assert_eq!(frame[0].direction, [vertex.tangent[0], vertex.tangent[1], vertex.tangent[2]]);
+            let bitangent = Vector3::from(frame[2].direction);
+            assert!(bitangent.dot(Vector3::from(vertex.normal)).abs() < 1e-6);
+            assert!(bitangent.dot(Vector3::from(frame[0].direction)).abs() < 1e-6);
+            assert!(frame.iter().all(|end| end.position == vertex.position));
+        }
+    }
+}
+
@@ -0,0 +1,93 @@
+// Wireframe from barycentric coordinates for adapters without `POLYGON_MODE_LINE`,
+// and lines along every vertex's normal or tangent frame.
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    view_position: vec4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct ModelUniform {
+    model: mat4x4<f32>,
+    normal: mat3x3<f32>,
+};
+@group(1) @binding(0) // dynamic offset selects the object
+var<uniform> model: ModelUniform;
+
+const WIREFRAME_COLOR = vec4<f32>(0.9, 0.9, 0.9, 1.0);
+// in pixels from the edge, on both of its triangles
+const WIREFRAME_WIDTH = 0.5;
+// world units
+const VECTOR_LENGTH = 0.15;
+
+// WIREFRAME
+
+struct WireframeInput {
+    @location(0) position: vec3<f32>,
+    // (1, 0, 0), (0, 1, 0) and (0, 0, 1) at the corners of every triangle
+    @location(1) barycentric: vec3<f32>,
+}
+
+struct WireframeOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) barycentric: vec3<f32>,
+}
+
+@vertex
+fn vs_wireframe(vertex: WireframeInput) -> WireframeOutput {
+    var out: WireframeOutput;
+    out.clip_position = camera.view_proj * model.model * vec4<f32>(vertex.position, 1.0);
+    out.barycentric = vertex.barycentric;
+    return out;
+}
+
+// Keeps the pixels close to an edge, the distance to the nearest one is the smallest coordinate.
+@fragment
+fn fs_wireframe(in: WireframeOutput) -> @location(0) vec4<f32> {
+    let pixels = in.barycentric / fwidth(in.barycentric);
+    if min(pixels.x, min(pixels.y, pixels.z)) > WIREFRAME_WIDTH {
+        discard;
+    }
+    return WIREFRAME_COLOR;
+}
+
+// VECTORS
+
+struct VectorInput {
+    @location(0) position: vec3<f32>,
+    @location(1) direction: vec3<f32>,
+    // 0 normal, 1 tangent, 2 bitangent
+    @location(2) kind: u32,
+    // 0 at the vertex, 1 at the tip
+    @location(3) end: f32,
+}
+
+struct VectorOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+@vertex
+fn vs_vector(vertex: VectorInput) -> VectorOutput {
+    // normals need the inverse transpose, tangents lie in the surface and move with it
+    var direction: vec3<f32>;
+    if vertex.kind == 0u {
+        direction = model.normal * vertex.direction;
+    } else {
+        direction = (model.model * vec4<f32>(vertex.direction, 0.0)).xyz;
+    }
+    let world_position = (model.model * vec4<f32>(vertex.position, 1.0)).xyz
+        + normalize(direction) * VECTOR_LENGTH * vertex.end;
+    var out: VectorOutput;
+    out.clip_position = camera.view_proj * vec4<f32>(world_position, 1.0);
+    // normals blue, tangents red, bitangents green like the axes of a normal map
+    var colors = array<vec3<f32>, 3>(vec3<f32>(0.2, 0.4, 1.0), vec3<f32>(1.0, 0.2, 0.2), vec3<f32>(0.2, 1.0, 0.2));
+    out.color = colors[vertex.kind];
+    return out;
+}
+
+@fragment
+fn fs_vector(in: VectorOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
@@ -0,0 +1,217 @@
+use cgmath::{
+    Deg,
+    InnerSpace,
+    Point3,
+    Vector3,
+};
+use crate::examples::perspective_camera::camera::Camera;
+use crate::examples::lighting::shadow::{
+    shadow_layers,
+    Shadows,
+};
+
+// Has to match `MAX_LIGHTS` in lights.wgsl.
+pub const MAX_LIGHTS: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightKind {
+    // Infinitely far away, `direction` points from the light into the scene.
+    Directional { direction: Vector3<f32> },
+    Point { position: Point3<f32>, range: f32 },
+    // Full intensity inside `inner`, fading out towards `outer`, both measured from `direction`.
+    Spot { position: Point3<f32>, direction: Vector3<f32>, range: f32, inner: Deg<f32>, outer: Deg<f32> },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Light {
+    pub kind: LightKind,
+    pub color: [f32; 3],
+    pub intensity: f32,
+}
+impl Light {
+    // `shadow` is the light's first layer in the shadow maps.
+    fn to_raw(self, shadow: Option<u32>) -> LightRaw {
+        let color = self.color;
+        let intensity = self.intensity;
+        let shadow = shadow.map_or(-1, |layer| layer as i32);
+        match self.kind {
+            LightKind::Directional { direction } => LightRaw {
+                kind: LIGHT_DIRECTIONAL,
+                direction: direction.normalize().into(),
+                color,
+                intensity,
+                shadow,
+                ..LightRaw::default()
+            },
+            LightKind::Point { position, range } => LightRaw {
+                position: position.into(),
+                kind: LIGHT_POINT,
+                range,
+                color,
+                intensity,
+                shadow,
+                ..LightRaw::default()
+            },
+            LightKind::Spot { position, direction, range, inner, outer } => LightRaw {
+                position: position.into(),
+                kind: LIGHT_SPOT,
+                direction: direction.normalize().into(),
+                range,
+                color,
+                intensity,
+                inner_cos: cgmath::Angle::cos(inner),
+                outer_cos: cgmath::Angle::cos(outer),
+                shadow,
+                _padding: 0.0,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shading {
+    Phong,
+    BlinnPhong,
+}
+
+const LIGHT_DIRECTIONAL: u32 = 0;
+const LIGHT_POINT: u32 = 1;
+const LIGHT_SPOT: u32 = 2;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct LightRaw {
+    position: [f32; 3],
+    kind: u32,
+    direction: [f32; 3],
+    range: f32,
+    color: [f32; 3],
+    intensity: f32,
+    inner_cos: f32,
+    outer_cos: f32,
+    // -1 without shadows
+    shadow: i32,
+    _padding: f32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct LightsUniform {
+    ambient: [f32; 3],
+    count: u32,
+    shading: u32,
+    _padding: [u32; 3],
+    lights: [LightRaw; MAX_LIGHTS],
+}
+
+// All lights of a scene in one uniform buffer, bound at a single group shared by every lit pipeline
+// together with their shadow maps. Change `lights`, `ambient` or `shading` and call `update` to upload them.
+pub struct Lights {
+    pub lights: Vec<Light>,
+    pub ambient: [f32; 3],
+    pub shading: Shading,
+    pub shadows: Shadows,
+    buffer: wgpu::Buffer,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+}
+impl Lights {
+    // `models_layout` is the layout of the `Models` the shadow pass draws with.
+    pub fn new(device: &wgpu::Device, models_layout: &wgpu::BindGroupLayout) -> Self {
+        let shadows = Shadows::new(device, models_layout);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Lights Buffer"),
+            size: size_of::<LightsUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        sample_type: wgpu::TextureSampleType::Depth,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+            ],
+            label: Some("lights_bind_group_layout"),
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding()
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&shadows.view)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&shadows.sampler)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: shadows.buffer.as_entire_binding()
+                },
+            ],
+            label: Some("lights_bind_group"),
+        });
+        Self {
+            lights: Vec::new(),
+            ambient: [0.03; 3],
+            shading: Shading::BlinnPhong,
+            shadows,
+            buffer,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    // Lights past `MAX_LIGHTS` are dropped. The cascades of the directional light's shadow follow `camera`.
+    pub fn update(&mut self, queue: &wgpu::Queue, camera: &Camera) {
+        let mut uniform = LightsUniform {
+            ambient: self.ambient,
+            count: self.lights.len().min(MAX_LIGHTS) as u32,
+            shading: self.shading as u32,
+            _padding: [0; 3],
+            lights: [LightRaw::default(); MAX_LIGHTS],
+        };
+        for ((raw, light), shadow) in uniform.lights.iter_mut().zip(&self.lights).zip(shadow_layers(&self.lights)) {
+            *raw = light.to_raw(shadow);
+        }
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[uniform]));
+        self.shadows.update(queue, &self.lights, camera);
+    }
+}
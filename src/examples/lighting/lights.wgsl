@@ -0,0 +1,51 @@
+// Light definitions shared by every lit shader, prepended to the shader source with `concat!`.
+// Layouts have to match `LightRaw`/`LightsUniform` in light.rs.
+
+const MAX_LIGHTS: u32 = 16u;
+const LIGHT_DIRECTIONAL: u32 = 0u;
+const LIGHT_POINT: u32 = 1u;
+const LIGHT_SPOT: u32 = 2u;
+const SHADING_PHONG: u32 = 0u;
+const SHADING_BLINN_PHONG: u32 = 1u;
+
+struct Light {
+    position: vec3<f32>,
+    kind: u32,
+    direction: vec3<f32>,
+    range: f32,
+    color: vec3<f32>,
+    intensity: f32,
+    inner_cos: f32,
+    outer_cos: f32,
+    // first layer in the shadow maps, -1 without shadows
+    shadow: i32,
+};
+
+struct Lights {
+    ambient: vec3<f32>,
+    count: u32,
+    shading: u32,
+    lights: array<Light, MAX_LIGHTS>,
+};
+
+// Unit vector from the surface towards the light and the light arriving there.
+struct LightSample {
+    direction: vec3<f32>,
+    radiance: vec3<f32>,
+};
+
+fn sample_light(light: Light, position: vec3<f32>) -> LightSample {
+    if light.kind == LIGHT_DIRECTIONAL {
+        return LightSample(-light.direction, light.color * light.intensity);
+    }
+    let to_light = light.position - position;
+    let distance = max(length(to_light), 0.0001);
+    let direction = to_light / distance;
+    // Inverse square falloff, windowed so it reaches exactly 0 at `range`.
+    let window = saturate(1.0 - pow(distance / light.range, 4.0));
+    var attenuation = window * window / (distance * distance);
+    if light.kind == LIGHT_SPOT {
+        attenuation *= smoothstep(light.outer_cos, light.inner_cos, dot(-direction, light.direction));
+    }
+    return LightSample(direction, light.color * light.intensity * attenuation);
+}
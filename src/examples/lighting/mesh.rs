@@ -0,0 +1,181 @@
+use std::f32::consts::{
+    PI,
+    TAU,
+};
+use cgmath::{
+    InnerSpace,
+    Vector3,
+    Zero,
+};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub tex_coords: [f32; 2],
+    // xyz along increasing u, w is the sign of the bitangent `cross(normal, tangent) * w`,
+    // which points up the image (decreasing v) like glTF's normal maps expect
+    pub tangent: [f32; 4],
+}
+impl Vertex {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+            0 => Float32x3,
+            1 => Float32x3,
+            2 => Float32x2,
+            3 => Float32x4,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+// Geometry on the CPU, generated or loaded. Becomes a `Mesh` once uploaded.
+pub struct MeshData {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+impl MeshData {
+    // Unit cube centered on the origin, every face has its own vertices so normals stay flat.
+    pub fn cube() -> Self {
+        let faces: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+            // normal, u axis, v axis
+            ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, -1.0, 0.0]),
+            ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, -1.0, 0.0]),
+            ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+            ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+            ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, -1.0, 0.0]),
+            ([0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, -1.0, 0.0]),
+        ];
+        let mut data = Self { vertices: Vec::new(), indices: Vec::new() };
+        for (normal, u, v) in faces {
+            let base = data.vertices.len() as u32;
+            for (s, t) in [(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0)] {
+                let position = std::array::from_fn(|axis| {
+                    0.5 * normal[axis] + (s - 0.5) * u[axis] + (t - 0.5) * v[axis]
+                });
+                data.vertices.push(Vertex { position, normal, tex_coords: [s, t], tangent: [0.0; 4] });
+            }
+            data.indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+        }
+        data.compute_tangents();
+        data
+    }
+
+    // Square in the XZ plane facing up, `size` wide, the texture repeats `tiles` times.
+    pub fn plane(size: f32, tiles: f32) -> Self {
+        let half = size * 0.5;
+        let vertex = |x: f32, z: f32, u: f32, v: f32| Vertex {
+            position: [x, 0.0, z],
+            normal: [0.0, 1.0, 0.0],
+            tex_coords: [u * tiles, v * tiles],
+            tangent: [0.0; 4],
+        };
+        let mut data = Self {
+            vertices: vec![
+                vertex(-half, -half, 0.0, 0.0),
+                vertex(-half, half, 0.0, 1.0),
+                vertex(half, half, 1.0, 1.0),
+                vertex(half, -half, 1.0, 0.0),
+            ],
+            indices: vec![0, 1, 2, 0, 2, 3],
+        };
+        data.compute_tangents();
+        data
+    }
+
+    // UV sphere with radius 0.5.
+    pub fn sphere(segments: u32, rings: u32) -> Self {
+        let mut data = Self { vertices: Vec::new(), indices: Vec::new() };
+        for ring in 0..=rings {
+            let v = ring as f32 / rings as f32;
+            let (sin_theta, cos_theta) = (v * PI).sin_cos();
+            for segment in 0..=segments {
+                let u = segment as f32 / segments as f32;
+                let (sin_phi, cos_phi) = (u * TAU).sin_cos();
+                let normal = [sin_theta * sin_phi, cos_theta, sin_theta * cos_phi];
+                data.vertices.push(Vertex {
+                    position: normal.map(|n| n * 0.5),
+                    normal,
+                    tex_coords: [u, v],
+                    tangent: [0.0; 4],
+                });
+            }
+        }
+        let stride = segments + 1;
+        for ring in 0..rings {
+            for segment in 0..segments {
+                let a = ring * stride + segment;
+                let b = a + stride;
+                data.indices.extend([a, b, a + 1, a + 1, b, b + 1]);
+            }
+        }
+        data.compute_tangents();
+        data
+    }
+
+    // Tangents from the texture coordinates, accumulated per triangle and orthogonalized per vertex.
+    // Vertices without a usable uv gradient (e.g. sphere poles) get any tangent perpendicular to the normal.
+    pub fn compute_tangents(&mut self) {
+        let mut tangents = vec![Vector3::zero(); self.vertices.len()];
+        let mut bitangents = vec![Vector3::zero(); self.vertices.len()];
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|corner| &self.vertices[triangle[corner] as usize]);
+            let edge1 = Vector3::from(b.position) - Vector3::from(a.position);
+            let edge2 = Vector3::from(c.position) - Vector3::from(a.position);
+            let (du1, dv1) = (b.tex_coords[0] - a.tex_coords[0], b.tex_coords[1] - a.tex_coords[1]);
+            let (du2, dv2) = (c.tex_coords[0] - a.tex_coords[0], c.tex_coords[1] - a.tex_coords[1]);
+            let determinant = du1 * dv2 - du2 * dv1;
+            if determinant.abs() < 1e-8 {
+                continue;
+            }
+            let tangent = (edge1 * dv2 - edge2 * dv1) / determinant;
+            let bitangent = (edge2 * du1 - edge1 * du2) / determinant;
+            for &index in triangle {
+                tangents[index as usize] += tangent;
+                bitangents[index as usize] += bitangent;
+            }
+        }
+        for (vertex, (tangent, bitangent)) in self.vertices.iter_mut().zip(tangents.into_iter().zip(bitangents)) {
+            let normal = Vector3::from(vertex.normal);
+            let mut tangent = tangent - normal * normal.dot(tangent);
+            if tangent.magnitude2() < 1e-12 {
+                let axis = if normal.x.abs() < 0.9 { Vector3::unit_x() } else { Vector3::unit_y() };
+                tangent = axis - normal * normal.dot(axis);
+            }
+            let tangent = tangent.normalize();
+            let handedness = if normal.cross(tangent).dot(-bitangent) < 0.0 { -1.0 } else { 1.0 };
+            vertex.tangent = tangent.extend(handedness).into();
+        }
+    }
+}
+
+pub struct Mesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub index_count: u32,
+}
+impl Mesh {
+    pub fn new(device: &wgpu::Device, label: &str, data: &MeshData) -> Self {
+        let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some(&format!("{label} Vertex Buffer")),
+            contents: bytemuck::cast_slice(&data.vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some(&format!("{label} Index Buffer")),
+            contents: bytemuck::cast_slice(&data.indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        Self {
+            vertex_buffer,
+            index_buffer,
+            index_count: data.indices.len() as u32,
+        }
+    }
+}
@@ -0,0 +1,708 @@
+pub mod debug_view;
+pub mod light;
+pub mod mesh;
+pub mod model;
+pub mod shadow;
+
+use std::sync::Arc;
+use winit::{
+    event_loop::{
+        EventLoop,
+        ActiveEventLoop,
+        ControlFlow,
+    },
+    event::{
+        DeviceEvent,
+        DeviceId,
+        StartCause,
+        WindowEvent
+    },
+    window::WindowId,
+    dpi::PhysicalSize,
+    application::ApplicationHandler,
+};
+use async_std::task;
+use cgmath::{
+    Deg,
+    Matrix4,
+    SquareMatrix,
+    Vector3,
+};
+use crate::input::{
+    Action,
+    Input,
+};
+use crate::window::{
+    ManagedWindow,
+    WindowConfig,
+};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use crate::examples::perspective_camera::camera::{
+    Camera,
+    CameraController,
+};
+use crate::examples::perspective_camera::texture;
+use crate::examples::lighting::debug_view::{
+    DebugMesh,
+    DebugView,
+};
+use crate::examples::raster_mode::{
+    RasterMode,
+    RasterPipelines,
+};
+use crate::examples::lighting::light::{
+    Light,
+    LightKind,
+    Lights,
+    Shading,
+};
+use crate::examples::lighting::mesh::{
+    Mesh,
+    MeshData,
+    Vertex,
+};
+use crate::examples::lighting::model::Models;
+use crate::examples::lighting::shadow::ShadowDebugView;
+use crate::examples::post_process::HDR_FORMAT;
+use crate::examples::post_process::tone_mapping::ToneMapper;
+use crate::examples::post_process::bloom::Bloom;
+use crate::examples::post_process::chain::{
+    Effect,
+    PostProcessChain,
+};
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CameraUniform {
+    view_proj: [[f32; 4]; 4],
+    view_position: [f32; 4],
+}
+impl CameraUniform {
+    pub fn new(camera: &Camera) -> Self {
+        Self {
+            view_proj: camera.build_view_projection_matrix().into(),
+            view_position: camera.eye.to_homogeneous().into(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct PhongMaterialUniform {
+    color: [f32; 4],
+    specular: f32,
+    shininess: f32,
+    _padding: [f32; 2],
+}
+
+struct PhongMaterial {
+    bind_group: wgpu::BindGroup,
+}
+impl PhongMaterial {
+    fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        diffuse: &texture::Texture,
+        color: [f32; 4],
+        specular: f32,
+        shininess: f32,
+    ) -> Self {
+        let uniform = PhongMaterialUniform { color, specular, shininess, _padding: [0.0; 2] };
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Phong Material Buffer"),
+            contents: bytemuck::cast_slice(&[uniform]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&diffuse.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&diffuse.sampler)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: buffer.as_entire_binding()
+                },
+            ],
+            label: Some("phong_material_bind_group")
+        });
+        Self { bind_group }
+    }
+}
+
+struct Object {
+    mesh: usize,
+    material: usize,
+    transform: Matrix4<f32>,
+}
+
+struct Application {
+    window_config: WindowConfig,
+    window: Option<ManagedWindow>,
+    input: Input,
+    camera: Camera,
+    camera_controller: CameraController,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group_layout: wgpu::BindGroupLayout,
+    camera_bind_group: wgpu::BindGroup,
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    // fill, wireframe and points, picked by `debug_view.raster_mode`
+    render_pipelines: Option<RasterPipelines>,
+    debug_view: Option<DebugView>,
+    tone_mapper: Option<ToneMapper>,
+    bloom: Option<Bloom>,
+    post_process: Option<PostProcessChain>,
+    shadow_debug: Option<ShadowDebugView>,
+    show_shadow_maps: bool,
+    depth_texture: Option<texture::Texture>,
+    // the scene is drawn into this and resolved to the tone mapper's target, `None` with 1x MSAA
+    msaa_target: Option<wgpu::TextureView>,
+    sample_count: u32,
+    material_bind_group_layout: wgpu::BindGroupLayout,
+    meshes: Vec<Mesh>,
+    // same order as `meshes`
+    debug_meshes: Vec<DebugMesh>,
+    materials: Vec<PhongMaterial>,
+    objects: Vec<Object>,
+    lights: Lights,
+    models: Models,
+    time: f32,
+}
+
+impl Application {
+    fn init(&mut self, event_loop: &ActiveEventLoop) {
+        let window = Arc::new(event_loop.create_window(self.window_config.attributes(event_loop))
+            .expect("Failed to create window"));
+        let managed = ManagedWindow::new(
+            window,
+            &self.instance,
+            &self.adapter,
+            &self.device,
+            self.window_config.present_mode(),
+            None,
+        ).expect("Failed to create surface");
+
+        self.sample_count = self.window_config.sample_count(&self.adapter, &self.device, &[HDR_FORMAT, texture::Texture::DEPTH_FORMAT]);
+
+        // SHADER, the shared light definitions go first
+        let shader = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Phong Shader"),
+            source: wgpu::ShaderSource::Wgsl(concat!(
+                include_str!("lights.wgsl"),
+                include_str!("shadows.wgsl"),
+                include_str!("phong.wgsl"),
+            ).into()),
+        });
+
+        // RENDER PIPELINE
+        let pipeline_layout = self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some("Phong Pipeline Layout"),
+            bind_group_layouts: &[
+                &self.camera_bind_group_layout,
+                &self.lights.bind_group_layout,
+                &self.material_bind_group_layout,
+                &self.models.bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = wgpu::RenderPipelineDescriptor {
+            label: Some("Phong Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[
+                    Vertex::desc()
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: HDR_FORMAT,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                ..wgpu::PrimitiveState::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: texture::Texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: self.sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        };
+
+        self.render_pipelines = Some(RasterPipelines::new(&self.device, &render_pipeline));
+        self.debug_view = Some(DebugView::new(&self.device, &self.camera_bind_group_layout, &self.models, HDR_FORMAT, self.sample_count));
+        self.shadow_debug = Some(ShadowDebugView::new(&self.device, &self.lights.shadows, HDR_FORMAT, self.sample_count));
+        let config = &managed.surface_config;
+        let tone_mapper = ToneMapper::new(&self.device, config.format, config.width, config.height);
+        self.bloom = Some(Bloom::new(&self.device, &tone_mapper.target));
+        self.tone_mapper = Some(tone_mapper);
+        self.post_process = Some(PostProcessChain::new(&self.device, &self.queue, config.format, config.width, config.height, &Effect::ALL));
+        self.depth_texture = Some(texture::Texture::create_depth_texture(&self.device, &managed.surface_config, self.sample_count, "depth_texture"));
+        self.msaa_target = WindowConfig::create_msaa_view(&self.device, &managed.surface_config, HDR_FORMAT, self.sample_count);
+        self.camera.aspect = managed.aspect();
+        self.window = Some(managed);
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>) {
+        let Some(managed) = self.window.as_mut() else {
+            return;
+        };
+        if !managed.resize(&self.device, size) {
+            return;
+        }
+        self.depth_texture = Some(texture::Texture::create_depth_texture(&self.device, &managed.surface_config, self.sample_count, "depth_texture"));
+        self.msaa_target = WindowConfig::create_msaa_view(&self.device, &managed.surface_config, HDR_FORMAT, self.sample_count);
+        if let Some(tone_mapper) = self.tone_mapper.as_mut() {
+            tone_mapper.resize(&self.device, managed.surface_config.width, managed.surface_config.height);
+            if let Some(bloom) = self.bloom.as_mut() {
+                bloom.resize(&self.device, &tone_mapper.target);
+            }
+        }
+        if let Some(post_process) = self.post_process.as_mut() {
+            post_process.resize(&self.device, managed.surface_config.width, managed.surface_config.height);
+        }
+        self.camera.aspect = managed.aspect();
+        managed.window.request_redraw();
+    }
+
+    // Moves the point light in a circle and uploads camera, lights and model matrices.
+    fn update(&mut self) {
+        self.time += self.input.dt();
+        if let Some(Light { kind: LightKind::Point { position, .. }, .. }) = self.lights.lights.get_mut(1) {
+            *position = (2.5 * self.time.cos(), 1.5, 2.5 * self.time.sin()).into();
+        }
+        self.lights.update(&self.queue, &self.camera);
+        if let Some(tone_mapper) = self.tone_mapper.as_ref() {
+            tone_mapper.update(&self.queue);
+        }
+        if let Some(bloom) = self.bloom.as_ref() {
+            bloom.update(&self.queue);
+        }
+        if let Some(post_process) = self.post_process.as_ref() {
+            post_process.update(&self.queue);
+        }
+        self.queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[CameraUniform::new(&self.camera)]));
+        for (index, object) in self.objects.iter().enumerate() {
+            self.models.write(&self.queue, index, object.transform);
+        }
+    }
+
+    fn render(&self) {
+        let (Some(managed), Some(depth_texture), Some(tone_mapper), Some(post_process)) =
+            (self.window.as_ref(), self.depth_texture.as_ref(), self.tone_mapper.as_ref(), self.post_process.as_ref()) else {
+            return;
+        };
+        let Some(surface) = managed.surface.as_ref() else {
+            return;
+        };
+        let frame = surface.get_current_texture().unwrap();
+        let frame_view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {label: Some("Render Encoder")});
+        let draws: Vec<_> = self.objects.iter()
+            .enumerate()
+            .map(|(index, object)| (&self.meshes[object.mesh], index))
+            .collect();
+        self.lights.shadows.render(&mut encoder, &self.models, &draws);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: self.msaa_target.as_ref().unwrap_or(&tone_mapper.target.view),
+                resolve_target: self.msaa_target.as_ref().map(|_| &tone_mapper.target.view),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.0,
+                        g: 0.0,
+                        b: 0.0,
+                        a: 1.0 }),
+                    store: wgpu::StoreOp::Store,
+                }
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &depth_texture.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        let raster_mode = self.debug_view.as_ref().map_or(RasterMode::Fill, |debug_view| debug_view.raster_mode);
+        // no pipeline for a wireframe without `POLYGON_MODE_LINE`, the debug view draws it
+        if let Some(render_pipeline) = self.render_pipelines.as_ref().and_then(|pipelines| pipelines.get(raster_mode)) {
+            render_pass
+                .set_pipeline(
+                    render_pipeline);
+            render_pass
+                .set_bind_group(
+                    0,
+                    &self.camera_bind_group,
+                    &[]);
+            render_pass
+                .set_bind_group(
+                    1,
+                    &self.lights.bind_group,
+                    &[]);
+            for (index, object) in self.objects.iter().enumerate() {
+                let mesh = &self.meshes[object.mesh];
+                render_pass
+                    .set_bind_group(
+                        2,
+                        &self.materials[object.material].bind_group,
+                        &[]);
+                render_pass
+                    .set_bind_group(
+                        3,
+                        &self.models.bind_group,
+                        &[self.models.offset(index)]);
+                render_pass
+                    .set_vertex_buffer(
+                        0,
+                        mesh.vertex_buffer.slice(..));
+                render_pass
+                    .set_index_buffer(
+                        mesh.index_buffer.slice(..),
+                        wgpu::IndexFormat::Uint32);
+                render_pass
+                    .draw_indexed(
+                        0..mesh.index_count,
+                        0,
+                        0..1);
+            }
+        }
+        if let Some(debug_view) = self.debug_view.as_ref() {
+            let debug_draws: Vec<_> = self.objects.iter()
+                .enumerate()
+                .map(|(index, object)| (&self.debug_meshes[object.mesh], index))
+                .collect();
+            debug_view.render(&mut render_pass, &self.camera_bind_group, &self.models, &debug_draws);
+        }
+        if self.show_shadow_maps && let Some(shadow_debug) = self.shadow_debug.as_ref() {
+            let size = (managed.surface_config.width, managed.surface_config.height);
+            shadow_debug.render(&mut render_pass, &self.lights.shadows, size);
+        }
+        drop(render_pass);
+        if let Some(bloom) = self.bloom.as_ref() {
+            bloom.render(&mut encoder, &tone_mapper.target.view);
+        }
+        tone_mapper.render(&mut encoder, post_process.input(&frame_view));
+        post_process.render(&mut encoder, &frame_view);
+
+        let command_buffer = encoder.finish();
+        self.queue.submit(Some(command_buffer));
+        frame.present();
+    }
+}
+impl ApplicationHandler for Application {
+    fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
+        let _ = (event_loop, cause);
+    }
+
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(managed) = self.window.as_mut() else {
+            self.init(event_loop);
+            return;
+        };
+        if let Err(error) = managed.resume(&self.instance, &self.device) {
+            eprintln!("{error:#}");
+            event_loop.exit();
+            return;
+        }
+        let size = managed.window.inner_size();
+        self.resize(size);
+    }
+
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, _event: ()) {}
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _window_id: WindowId, event: WindowEvent) {
+        self.input.handle_window_event(&event);
+        match event {
+            WindowEvent::CloseRequested => {
+                if let Some(managed) = self.window.as_ref() {
+                    self.window_config.store(&managed.window);
+                }
+                event_loop.exit();
+            }
+            WindowEvent::RedrawRequested => { self.render(); }
+            WindowEvent::Resized(size) => { self.resize(size); }
+            _ => {}
+        }
+    }
+
+    fn device_event(&mut self, _event_loop: &ActiveEventLoop, _device_id: DeviceId, event: DeviceEvent) {
+        self.input.handle_device_event(&event);
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.input.begin_frame();
+        let Some(window) = self.window.as_ref().map(|managed| managed.window.clone()) else {
+            self.input.end_frame();
+            return;
+        };
+        if self.window_config.handle_input(&window, &self.input) {
+            self.resize(window.inner_size());
+        }
+        if self.input.pressed(Action::CameraOrbit) {
+            window.set_cursor_visible(false);
+        }
+        if self.input.released(Action::CameraOrbit) {
+            window.set_cursor_visible(true);
+        }
+        if self.input.pressed(Action::CycleShading) {
+            self.lights.shading = match self.lights.shading {
+                Shading::Phong => Shading::BlinnPhong,
+                Shading::BlinnPhong => Shading::Phong,
+            };
+            println!("Shading: {:?}", self.lights.shading);
+        }
+        if self.input.pressed(Action::ToggleShadowDebug) {
+            self.show_shadow_maps = !self.show_shadow_maps;
+        }
+        if let Some(debug_view) = self.debug_view.as_mut() {
+            debug_view.handle_input(&self.input);
+        }
+        if let Some(tone_mapper) = self.tone_mapper.as_mut() {
+            tone_mapper.handle_input(&self.input);
+        }
+        if let Some(bloom) = self.bloom.as_mut() {
+            bloom.handle_input(&self.input);
+        }
+        if let Some(post_process) = self.post_process.as_mut() {
+            post_process.handle_input(&self.input);
+        }
+        self.camera_controller.update(&mut self.camera, &self.input);
+
+        // The lights are animated, redraw continuously.
+        self.update();
+        window.request_redraw();
+        event_loop.set_control_flow(ControlFlow::Poll);
+        self.input.end_frame();
+    }
+
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(managed) = self.window.as_mut() {
+            managed.suspend();
+        }
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {}
+
+    fn memory_warning(&mut self, _event_loop: &ActiveEventLoop) {}
+}
+
+// Sun, a point light circling the scene and a spot light from above.
+fn default_lights(lights: &mut Lights) {
+    lights.lights = vec![
+        Light {
+            kind: LightKind::Directional { direction: Vector3::new(-0.4, -1.0, -0.3) },
+            color: [1.0, 0.95, 0.85],
+            intensity: 0.6,
+        },
+        Light {
+            kind: LightKind::Point { position: (2.5, 1.5, 0.0).into(), range: 8.0 },
+            color: [1.0, 0.4, 0.2],
+            intensity: 4.0,
+        },
+        Light {
+            kind: LightKind::Spot {
+                position: (0.0, 4.0, 0.0).into(),
+                direction: -Vector3::unit_y(),
+                range: 10.0,
+                inner: Deg(15.0),
+                outer: Deg(25.0),
+            },
+            color: [0.3, 0.6, 1.0],
+            intensity: 20.0,
+        },
+    ];
+}
+
+pub fn run(window_config: WindowConfig, input: Input) {
+    // winit
+    let event_loop = EventLoop::new().unwrap();
+
+    // wgpu
+    let instance= wgpu::Instance::new(&wgpu::InstanceDescriptor { backends: wgpu::Backends::all(), ..Default::default()});
+    let adapter= task::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())).unwrap();
+    let (device, queue)= task::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+        // `PolygonMode::Line` for the debug view's wireframes
+        required_features: WindowConfig::msaa_features(&adapter)
+            | (adapter.features() & wgpu::Features::POLYGON_MODE_LINE),
+        ..Default::default()
+    }, None, )).unwrap();
+
+    // CAMERA
+    let settings = &window_config.settings;
+    let camera = Camera {
+        eye: (0.0, 3.0, 6.0).into(),
+        target: (0.0, 0.0, 0.0).into(),
+        up: Vector3::unit_y(),
+        aspect: settings.width as f32 / settings.height as f32,
+        fovy: 45.0,
+        znear: 0.1,
+        zfar: 100.0
+    };
+    let camera_buffer = device.create_buffer_init(&BufferInitDescriptor {
+        label: Some("Camera Buffer"),
+        contents: bytemuck::cast_slice(&[CameraUniform::new(&camera)]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+    let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None
+                },
+                count: None
+            }
+        ],
+        label: Some("camera_bind_group_layout"),
+    });
+    let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &camera_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding()
+            }
+        ],
+        label: Some("camera_bind_group"),
+    });
+
+    // MATERIALS
+    let material_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None
+                },
+                count: None
+            },
+        ],
+        label: Some("material_bind_group_layout")
+    });
+    let diffuse_texture = texture::Texture::from_bytes(
+        &device,
+        &queue,
+        include_bytes!("../perspective_camera/UV_Grid.png"),
+        "diffuse_texture"
+    ).unwrap();
+    let materials = vec![
+        // matte floor
+        PhongMaterial::new(&device, &material_bind_group_layout, &diffuse_texture, [0.8, 0.8, 0.8, 1.0], 0.1, 8.0),
+        // shiny objects
+        PhongMaterial::new(&device, &material_bind_group_layout, &diffuse_texture, [1.0, 1.0, 1.0, 1.0], 0.6, 64.0),
+    ];
+
+    // SCENE
+    let mesh_data = [
+        ("Plane", MeshData::plane(10.0, 5.0)),
+        ("Cube", MeshData::cube()),
+        ("Sphere", MeshData::sphere(32, 16)),
+    ];
+    let meshes = mesh_data.iter().map(|(label, data)| Mesh::new(&device, label, data)).collect();
+    let debug_meshes = mesh_data.iter().map(|(label, data)| DebugMesh::new(&device, label, data)).collect();
+    let objects = vec![
+        Object { mesh: 0, material: 0, transform: Matrix4::identity() },
+        Object { mesh: 1, material: 1, transform: Matrix4::from_translation((-1.5, 0.5, 0.0).into()) },
+        Object {
+            mesh: 1,
+            material: 1,
+            transform: Matrix4::from_translation((1.5, 0.75, -0.5).into())
+                * Matrix4::from_angle_y(Deg(30.0))
+                * Matrix4::from_nonuniform_scale(1.0, 1.5, 0.5),
+        },
+        Object { mesh: 2, material: 1, transform: Matrix4::from_translation((0.0, 0.5, 1.0).into()) },
+    ];
+    let models = Models::new(&device, objects.len() as u32);
+    let mut lights = Lights::new(&device, &models.bind_group_layout);
+    default_lights(&mut lights);
+
+    let mut application = Application{
+        window_config,
+        window: None,
+        input,
+        camera,
+        camera_controller: CameraController::new(4.0, 1.5, 0.005),
+        camera_buffer,
+        camera_bind_group_layout,
+        camera_bind_group,
+        instance,
+        adapter,
+        device,
+        queue,
+        render_pipelines: None,
+        debug_view: None,
+        tone_mapper: None,
+        bloom: None,
+        post_process: None,
+        shadow_debug: None,
+        show_shadow_maps: false,
+        depth_texture: None,
+        msaa_target: None,
+        sample_count: 1,
+        material_bind_group_layout,
+        meshes,
+        debug_meshes,
+        materials,
+        objects,
+        lights,
+        models,
+        time: 0.0,
+    };
+
+    event_loop.run_app(&mut application).expect("Failed to start event_loop");
+    println!("lighting example");
+}
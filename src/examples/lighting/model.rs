@@ -0,0 +1,89 @@
+use cgmath::{
+    Matrix,
+    Matrix3,
+    Matrix4,
+    SquareMatrix,
+};
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ModelUniform {
+    model: [[f32; 4]; 4],
+    // mat3x3 columns are padded to 16 bytes in WGSL
+    normal: [[f32; 4]; 3],
+}
+impl ModelUniform {
+    pub fn new(model: Matrix4<f32>) -> Self {
+        // Inverse transpose of the upper 3x3, plain `model` would skew normals under non-uniform scale.
+        let upper = Matrix3::from_cols(model.x.truncate(), model.y.truncate(), model.z.truncate());
+        let normal = upper.invert().unwrap_or(Matrix3::identity()).transpose();
+        Self {
+            model: model.into(),
+            normal: [normal.x.extend(0.0).into(), normal.y.extend(0.0).into(), normal.z.extend(0.0).into()],
+        }
+    }
+}
+
+// Model and normal matrices of every object in one uniform buffer,
+// each draw selects its slot with a dynamic offset.
+pub struct Models {
+    buffer: wgpu::Buffer,
+    stride: u32,
+    capacity: u32,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+}
+impl Models {
+    pub fn new(device: &wgpu::Device, capacity: u32) -> Self {
+        let stride = wgpu::util::align_to(
+            size_of::<ModelUniform>() as u32,
+            device.limits().min_uniform_buffer_offset_alignment,
+        );
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Model Buffer"),
+            size: (stride * capacity) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(size_of::<ModelUniform>() as u64),
+                    },
+                    count: None
+                }
+            ],
+            label: Some("model_bind_group_layout"),
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &buffer,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(size_of::<ModelUniform>() as u64),
+                    }),
+                }
+            ],
+            label: Some("model_bind_group"),
+        });
+        Self { buffer, stride, capacity, bind_group_layout, bind_group }
+    }
+
+    pub fn write(&self, queue: &wgpu::Queue, index: usize, model: Matrix4<f32>) {
+        assert!(index < self.capacity as usize, "model slot {index} out of {}", self.capacity);
+        queue.write_buffer(&self.buffer, self.offset(index) as wgpu::BufferAddress, bytemuck::cast_slice(&[ModelUniform::new(model)]));
+    }
+
+    // Dynamic offset for `set_bind_group`.
+    pub fn offset(&self, index: usize) -> u32 {
+        index as u32 * self.stride
+    }
+}
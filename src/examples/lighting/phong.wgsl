@@ -0,0 +1,85 @@
+// VERTEX SHADER, lights.wgsl and shadows.wgsl are prepended
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    view_position: vec4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+@group(1) @binding(0)
+var<uniform> lights: Lights;
+
+struct ModelUniform {
+    model: mat4x4<f32>,
+    // inverse transpose of the model matrix, keeps normals perpendicular under non-uniform scale
+    normal: mat3x3<f32>,
+};
+@group(3) @binding(0) // dynamic offset selects the object
+var<uniform> model: ModelUniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    vertex: VertexInput,
+) -> VertexOutput {
+    let world_position = model.model * vec4<f32>(vertex.position, 1.0);
+    var out: VertexOutput;
+    out.world_position = world_position.xyz;
+    out.world_normal = model.normal * vertex.normal;
+    out.tex_coords = vertex.tex_coords;
+    out.clip_position = camera.view_proj * world_position;
+    return out;
+}
+
+// FRAGMENT SHADER
+
+struct PhongMaterial {
+    color: vec4<f32>,
+    specular: f32,
+    shininess: f32,
+};
+@group(2) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(2) @binding(1)
+var s_diffuse: sampler;
+@group(2) @binding(2)
+var<uniform> material: PhongMaterial;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let albedo = textureSample(t_diffuse, s_diffuse, in.tex_coords) * material.color;
+    let normal = normalize(in.world_normal);
+    let view_direction = normalize(camera.view_position.xyz - in.world_position);
+
+    var color = lights.ambient * albedo.rgb;
+    for (var i = 0u; i < min(lights.count, MAX_LIGHTS); i++) {
+        let light = sample_light(lights.lights[i], in.world_position);
+        let shadow = sample_shadow(lights.lights[i], in.world_position, normal);
+        let diffuse = max(dot(normal, light.direction), 0.0);
+        var specular = 0.0;
+        if diffuse > 0.0 {
+            if lights.shading == SHADING_BLINN_PHONG {
+                let half_direction = normalize(light.direction + view_direction);
+                specular = pow(max(dot(normal, half_direction), 0.0), material.shininess);
+            } else {
+                let reflect_direction = reflect(-light.direction, normal);
+                specular = pow(max(dot(view_direction, reflect_direction), 0.0), material.shininess);
+            }
+        }
+        color += light.radiance * shadow * (albedo.rgb * diffuse + material.specular * specular);
+    }
+    return vec4<f32>(color, albedo.a);
+}
@@ -0,0 +1,462 @@
+use cgmath::{
+    Deg,
+    EuclideanSpace,
+    InnerSpace,
+    Matrix4,
+    Point3,
+    SquareMatrix,
+    Vector3,
+    Vector4,
+};
+use crate::examples::perspective_camera::camera::{
+    Camera,
+    OPENGL_TO_WGPU_MATRIX,
+};
+use crate::examples::lighting::light::{
+    Light,
+    LightKind,
+};
+use crate::examples::lighting::mesh::{
+    Mesh,
+    Vertex,
+};
+use crate::examples::lighting::model::Models;
+
+pub const SHADOW_MAP_SIZE: u32 = 1024;
+// Has to match `CASCADE_COUNT` and `SHADOW_LAYERS` in shadows.wgsl.
+pub const CASCADE_COUNT: usize = 4;
+pub const MAX_SPOT_SHADOWS: usize = 4;
+pub const SHADOW_LAYERS: usize = CASCADE_COUNT + MAX_SPOT_SHADOWS;
+// Cascades cover the camera frustum up to here, further away everything is lit.
+const SHADOW_DISTANCE: f32 = 30.0;
+// 0 splits the distance evenly, 1 logarithmically.
+const SPLIT_LAMBDA: f32 = 0.6;
+// How far behind a cascade casters are still caught.
+const CASTER_MARGIN: f32 = 20.0;
+const SPOT_NEAR: f32 = 0.05;
+
+// Layer of each light's first shadow map. The first directional light gets `CASCADE_COUNT` layers
+// starting at 0, the first `MAX_SPOT_SHADOWS` spot lights one each. Point lights cast no shadows.
+pub fn shadow_layers(lights: &[Light]) -> Vec<Option<u32>> {
+    let mut directional = false;
+    let mut spots = 0;
+    lights.iter()
+        .map(|light| match light.kind {
+            LightKind::Directional { .. } if !directional => {
+                directional = true;
+                Some(0)
+            }
+            LightKind::Spot { .. } if spots < MAX_SPOT_SHADOWS => {
+                spots += 1;
+                Some((CASCADE_COUNT + spots - 1) as u32)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ShadowsUniform {
+    view_proj: [[[f32; 4]; 4]; SHADOW_LAYERS],
+    // x: world size of a texel, per unit of distance for perspective layers,
+    // y: 1 for perspective layers, z/w: their near and far planes
+    params: [[f32; 4]; SHADOW_LAYERS],
+    camera_position: [f32; 4],
+    camera_forward: [f32; 4],
+    // far end of each cascade, measured along `camera_forward`
+    cascade_splits: [f32; CASCADE_COUNT],
+}
+
+// View projection of the layer a shadow pass renders, selected with a dynamic offset.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ShadowPassUniform {
+    view_proj: [[f32; 4]; 4],
+}
+
+fn up_for(direction: Vector3<f32>) -> Vector3<f32> {
+    if direction.y.abs() > 0.99 { Vector3::unit_z() } else { Vector3::unit_y() }
+}
+
+// Far ends of the cascades between `near` and `far`, blending even and logarithmic splits.
+fn cascade_splits(near: f32, far: f32) -> [f32; CASCADE_COUNT] {
+    std::array::from_fn(|index| {
+        let fraction = (index + 1) as f32 / CASCADE_COUNT as f32;
+        let logarithmic = near * (far / near).powf(fraction);
+        let uniform = near + (far - near) * fraction;
+        uniform + (logarithmic - uniform) * SPLIT_LAMBDA
+    })
+}
+
+// Orthographic projection around the bounding sphere of the camera frustum between `near` and `far`.
+// The sphere keeps the size constant while the camera turns and the snap to whole texels keeps
+// edges from crawling while it moves. Returns the view projection and the world size of a texel.
+fn cascade_matrix(camera: &Camera, direction: Vector3<f32>, near: f32, far: f32) -> (Matrix4<f32>, f32) {
+    let view = Matrix4::look_at_rh(camera.eye, camera.target, camera.up);
+    let proj = cgmath::perspective(Deg(camera.fovy), camera.aspect, near, far);
+    let inverse = (OPENGL_TO_WGPU_MATRIX * proj * view).invert()
+        .expect("view projection matrix is not invertible");
+    let corners: Vec<Point3<f32>> = [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)].iter()
+        .flat_map(|&(x, y)| [0.0, 1.0].map(|z| Point3::from_homogeneous(inverse * Vector4::new(x, y, z, 1.0))))
+        .collect();
+    let center = Point3::centroid(&corners);
+    let radius = corners.iter()
+        .map(|corner| (corner - center).magnitude())
+        .fold(0.0, f32::max);
+    let radius = (radius * 16.0).ceil() / 16.0;
+
+    let eye = center - direction * (radius + CASTER_MARGIN);
+    let light_view = Matrix4::look_at_rh(eye, center, up_for(direction));
+    let light_proj = cgmath::ortho(-radius, radius, -radius, radius, 0.0, 2.0 * radius + CASTER_MARGIN);
+    let view_proj = OPENGL_TO_WGPU_MATRIX * light_proj * light_view;
+
+    let half_size = SHADOW_MAP_SIZE as f32 / 2.0;
+    let origin = view_proj * Vector4::new(0.0, 0.0, 0.0, 1.0);
+    let snap = Vector3::new(
+        ((origin.x * half_size).round() - origin.x * half_size) / half_size,
+        ((origin.y * half_size).round() - origin.y * half_size) / half_size,
+        0.0,
+    );
+    (Matrix4::from_translation(snap) * view_proj, 2.0 * radius / SHADOW_MAP_SIZE as f32)
+}
+
+// Depth maps of the shadow casting lights in one texture array, rendered by `render` and sampled
+// through the lights bind group (bindings 1 to 3, see shadows.wgsl).
+// `Lights::update` keeps them in sync with the lights and the camera.
+pub struct Shadows {
+    pub view: wgpu::TextureView,
+    layer_views: Vec<wgpu::TextureView>,
+    pub sampler: wgpu::Sampler,
+    pub buffer: wgpu::Buffer,
+    pass_buffer: wgpu::Buffer,
+    pass_stride: u32,
+    pass_bind_group: wgpu::BindGroup,
+    render_pipeline: wgpu::RenderPipeline,
+    // layers written by the last `update`
+    active: Vec<u32>,
+}
+impl Shadows {
+    pub fn new(device: &wgpu::Device, models_layout: &wgpu::BindGroupLayout) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Maps"),
+            size: wgpu::Extent3d {
+                width: SHADOW_MAP_SIZE,
+                height: SHADOW_MAP_SIZE,
+                depth_or_array_layers: SHADOW_LAYERS as u32,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        let layer_views = (0..SHADOW_LAYERS as u32)
+            .map(|layer| texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("Shadow Map Layer"),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                base_array_layer: layer,
+                array_layer_count: Some(1),
+                ..Default::default()
+            }))
+            .collect();
+        // Linear filtering compares 4 texels at once, on top of the PCF in the shader.
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shadows Buffer"),
+            size: size_of::<ShadowsUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // SHADOW PASS
+        let pass_stride = wgpu::util::align_to(
+            size_of::<ShadowPassUniform>() as u32,
+            device.limits().min_uniform_buffer_offset_alignment,
+        );
+        let pass_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shadow Pass Buffer"),
+            size: (pass_stride * SHADOW_LAYERS as u32) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let pass_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(size_of::<ShadowPassUniform>() as u64),
+                    },
+                    count: None
+                }
+            ],
+            label: Some("shadow_pass_bind_group_layout"),
+        });
+        let pass_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &pass_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &pass_buffer,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(size_of::<ShadowPassUniform>() as u64),
+                    }),
+                }
+            ],
+            label: Some("shadow_pass_bind_group"),
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shadow Depth Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shadow_depth.wgsl").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some("Shadow Pipeline Layout"),
+            bind_group_layouts: &[
+                &pass_bind_group_layout,
+                models_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shadow Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[
+                    Vertex::desc()
+                ],
+            },
+            // depth only
+            fragment: None,
+            // planes are single sided but still have to cast shadows
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                // pushes the stored depth back a little, more on surfaces seen at an angle
+                bias: wgpu::DepthBiasState {
+                    constant: 2,
+                    slope_scale: 2.0,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            view,
+            layer_views,
+            sampler,
+            buffer,
+            pass_buffer,
+            pass_stride,
+            pass_bind_group,
+            render_pipeline,
+            active: Vec::new(),
+        }
+    }
+
+    // Fits the cascades to `camera` and points the spot light maps along their lights.
+    pub fn update(&mut self, queue: &wgpu::Queue, lights: &[Light], camera: &Camera) {
+        let far = camera.zfar.min(SHADOW_DISTANCE);
+        let splits = cascade_splits(camera.znear, far);
+        let forward = (camera.target - camera.eye).normalize();
+        let mut uniform = ShadowsUniform {
+            view_proj: [Matrix4::identity().into(); SHADOW_LAYERS],
+            params: [[0.0; 4]; SHADOW_LAYERS],
+            camera_position: camera.eye.to_homogeneous().into(),
+            camera_forward: forward.extend(0.0).into(),
+            cascade_splits: splits,
+        };
+        self.active.clear();
+        for (light, layer) in lights.iter().zip(shadow_layers(lights)) {
+            let Some(layer) = layer.map(|layer| layer as usize) else {
+                continue;
+            };
+            match light.kind {
+                LightKind::Directional { direction } => {
+                    let mut near = camera.znear;
+                    for (cascade, &split) in splits.iter().enumerate() {
+                        let (view_proj, texel_size) = cascade_matrix(camera, direction.normalize(), near, split);
+                        uniform.view_proj[layer + cascade] = view_proj.into();
+                        uniform.params[layer + cascade] = [texel_size, 0.0, 0.0, 0.0];
+                        self.active.push((layer + cascade) as u32);
+                        near = split;
+                    }
+                }
+                LightKind::Spot { position, direction, range, outer, .. } => {
+                    let direction = direction.normalize();
+                    let view = Matrix4::look_at_rh(position, position + direction, up_for(direction));
+                    let proj = cgmath::perspective(outer * 2.0, 1.0, SPOT_NEAR, range);
+                    uniform.view_proj[layer] = (OPENGL_TO_WGPU_MATRIX * proj * view).into();
+                    let texel_size = 2.0 * cgmath::Angle::tan(outer) / SHADOW_MAP_SIZE as f32;
+                    uniform.params[layer] = [texel_size, 1.0, SPOT_NEAR, range];
+                    self.active.push(layer as u32);
+                }
+                LightKind::Point { .. } => {}
+            }
+        }
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[uniform]));
+        for &layer in &self.active {
+            let pass = ShadowPassUniform { view_proj: uniform.view_proj[layer as usize] };
+            queue.write_buffer(&self.pass_buffer, (layer * self.pass_stride) as wgpu::BufferAddress, bytemuck::cast_slice(&[pass]));
+        }
+    }
+
+    // Renders the depth of every draw, a mesh and its slot in `models`, into each active layer.
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, models: &Models, draws: &[(&Mesh, usize)]) {
+        for &layer in &self.active {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Shadow Pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.layer_views[layer as usize],
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(0, &self.pass_bind_group, &[layer * self.pass_stride]);
+            for &(mesh, model) in draws {
+                render_pass.set_bind_group(1, &models.bind_group, &[models.offset(model)]);
+                render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..mesh.index_count, 0, 0..1);
+            }
+        }
+    }
+}
+
+// Draws every active shadow map as a tile along the bottom of the target, for debugging.
+pub struct ShadowDebugView {
+    bind_group: wgpu::BindGroup,
+    render_pipeline: wgpu::RenderPipeline,
+}
+impl ShadowDebugView {
+    // `sample_count` matches the pass it is drawn in.
+    pub fn new(device: &wgpu::Device, shadows: &Shadows, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+            ],
+            label: Some("shadow_debug_bind_group_layout"),
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&shadows.view)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: shadows.buffer.as_entire_binding()
+                },
+            ],
+            label: Some("shadow_debug_bind_group"),
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shadow Debug Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shadow_debug.wgsl").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some("Shadow Debug Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shadow Debug Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            // drawn inside the scene pass, on top of everything
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: crate::examples::perspective_camera::texture::Texture::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+        Self { bind_group, render_pipeline }
+    }
+
+    // `size` is the size of the pass's attachments.
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass, shadows: &Shadows, size: (u32, u32)) {
+        let tile = (size.0 as f32 / SHADOW_LAYERS as f32).min(size.1 as f32 / 3.0);
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        for (index, &layer) in shadows.active.iter().enumerate() {
+            render_pass.set_viewport(index as f32 * tile, size.1 as f32 - tile, tile, tile, 0.0, 1.0);
+            // the instance index selects the layer
+            render_pass.draw(0..3, layer..layer + 1);
+        }
+        render_pass.set_viewport(0.0, 0.0, size.0 as f32, size.1 as f32, 0.0, 1.0);
+    }
+}
@@ -0,0 +1,41 @@
+// One shadow map layer per viewport, perspective depth is linearized so it does not all look white.
+
+struct Shadows {
+    view_proj: array<mat4x4<f32>, 8>,
+    params: array<vec4<f32>, 8>,
+};
+// bound as plain floats, GL cannot read depth textures without a comparison
+@group(0) @binding(0)
+var t_shadow: texture_2d_array<f32>;
+@group(0) @binding(1)
+var<uniform> shadows: Shadows;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) layer: u32,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, @builtin(instance_index) layer: u32) -> VertexOutput {
+    let position = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(position, 0.0, 1.0);
+    out.uv = vec2<f32>(position.x + 1.0, 1.0 - position.y) * 0.5;
+    out.layer = layer;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(t_shadow));
+    let texel = vec2<i32>(clamp(in.uv * size, vec2<f32>(0.0), size - 1.0));
+    var depth = textureLoad(t_shadow, texel, in.layer, 0).r;
+    let params = shadows.params[in.layer];
+    if params.y > 0.0 {
+        let near = params.z;
+        let far = params.w;
+        depth = near * far / (far - depth * (far - near)) / far;
+    }
+    return vec4<f32>(vec3<f32>(depth), 1.0);
+}
@@ -0,0 +1,19 @@
+// Depth only pass from a light, one layer of the shadow map array at a time.
+
+struct ShadowPass {
+    view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0) // dynamic offset selects the layer
+var<uniform> shadow_pass: ShadowPass;
+
+struct ModelUniform {
+    model: mat4x4<f32>,
+    normal: mat3x3<f32>,
+};
+@group(1) @binding(0) // dynamic offset selects the object
+var<uniform> model: ModelUniform;
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>) -> @builtin(position) vec4<f32> {
+    return shadow_pass.view_proj * model.model * vec4<f32>(position, 1.0);
+}
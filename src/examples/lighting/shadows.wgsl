@@ -0,0 +1,59 @@
+// Shadow maps of the lights, prepended after lights.wgsl. Layouts have to match `ShadowsUniform` in shadow.rs.
+
+const CASCADE_COUNT: u32 = 4u;
+const SHADOW_LAYERS: u32 = 8u;
+
+struct Shadows {
+    view_proj: array<mat4x4<f32>, SHADOW_LAYERS>,
+    // x: texel size in world units (per unit of distance for perspective layers), y: 1 if perspective
+    params: array<vec4<f32>, SHADOW_LAYERS>,
+    camera_position: vec4<f32>,
+    camera_forward: vec4<f32>,
+    cascade_splits: vec4<f32>,
+};
+@group(1) @binding(1)
+var t_shadow: texture_depth_2d_array;
+@group(1) @binding(2)
+var s_shadow: sampler_comparison;
+@group(1) @binding(3)
+var<uniform> shadows: Shadows;
+
+// Fraction of `light` reaching `position`, 3x3 percentage closer filtering on top of the
+// sampler's own 2x2. The position is pushed along `normal` by about a texel against acne.
+fn sample_shadow(light: Light, position: vec3<f32>, normal: vec3<f32>) -> f32 {
+    if light.shadow < 0 {
+        return 1.0;
+    }
+    var layer = u32(light.shadow);
+    if light.kind == LIGHT_DIRECTIONAL {
+        let depth = dot(position - shadows.camera_position.xyz, shadows.camera_forward.xyz);
+        var cascade = 0u;
+        for (var i = 0u; i < CASCADE_COUNT; i++) {
+            if depth > shadows.cascade_splits[i] {
+                cascade = i + 1u;
+            }
+        }
+        if cascade >= CASCADE_COUNT {
+            return 1.0;
+        }
+        layer += cascade;
+    }
+    var texel_size = shadows.params[layer].x;
+    if shadows.params[layer].y > 0.0 {
+        texel_size *= distance(light.position, position);
+    }
+    let clip = shadows.view_proj[layer] * vec4<f32>(position + normal * texel_size * 1.5, 1.0);
+    let ndc = clip.xyz / clip.w;
+    if ndc.z > 1.0 {
+        return 1.0;
+    }
+    let uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+    let texel = 1.0 / vec2<f32>(textureDimensions(t_shadow));
+    var lit = 0.0;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            lit += textureSampleCompareLevel(t_shadow, s_shadow, uv + vec2<f32>(f32(x), f32(y)) * texel, layer, ndc.z);
+        }
+    }
+    return lit / 9.0;
+}
@@ -2,3 +2,10 @@ pub mod triangle;
 pub mod buffers_and_indexes;
 pub mod textures_and_bind_groups;
 pub mod perspective_camera;
+pub mod lighting;
+pub mod pbr;
+pub mod post_process;
+pub mod instancing;
+pub mod particles;
+pub mod image_filters;
+pub mod raster_mode;
@@ -0,0 +1,438 @@
+pub mod system;
+
+use std::sync::Arc;
+use winit::{
+    event_loop::{
+        EventLoop,
+        ActiveEventLoop,
+        ControlFlow,
+    },
+    event::{
+        DeviceEvent,
+        DeviceId,
+        StartCause,
+        WindowEvent
+    },
+    window::WindowId,
+    dpi::PhysicalSize,
+    application::ApplicationHandler,
+};
+use async_std::task;
+use cgmath::{
+    InnerSpace,
+    Vector3,
+};
+use crate::input::{
+    Action,
+    Input,
+};
+use crate::window::{
+    ManagedWindow,
+    WindowConfig,
+};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use crate::examples::perspective_camera::camera::{
+    Camera,
+    CameraController,
+};
+use crate::examples::raster_mode::{
+    RasterMode,
+    RasterPipelines,
+};
+use crate::examples::particles::system::{
+    Emitter,
+    EmitterParameter,
+    Particle,
+    ParticleSystem,
+};
+
+const INITIAL_PARTICLES: u32 = 1 << 17;
+// applied by one press of the parameter keys
+const PARAMETER_STEP: f32 = 1.25;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct CameraUniform {
+    view_proj: [[f32; 4]; 4],
+    right: [f32; 3],
+    size: f32,
+    up: [f32; 3],
+    _padding: f32,
+}
+impl CameraUniform {
+    // `right` and `up` span the plane the particle quads are drawn in, facing the camera.
+    fn new(camera: &Camera, size: f32) -> Self {
+        let forward = (camera.target - camera.eye).normalize();
+        let right = forward.cross(camera.up).normalize();
+        let up = right.cross(forward);
+        Self {
+            view_proj: camera.build_view_projection_matrix().into(),
+            right: right.into(),
+            size,
+            up: up.into(),
+            _padding: 0.0,
+        }
+    }
+}
+
+struct Application {
+    window_config: WindowConfig,
+    window: Option<ManagedWindow>,
+    input: Input,
+    camera: Camera,
+    camera_controller: CameraController,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group_layout: wgpu::BindGroupLayout,
+    camera_bind_group: wgpu::BindGroup,
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    // fill, wireframe and points, picked by `raster_mode`
+    render_pipelines: Option<RasterPipelines>,
+    raster_mode: RasterMode,
+    // drawn into and resolved to the frame, `None` with 1x MSAA
+    msaa_target: Option<wgpu::TextureView>,
+    sample_count: u32,
+    particles: ParticleSystem,
+    emitter: Emitter,
+    // what the parameter keys change
+    parameter: EmitterParameter,
+}
+
+impl Application {
+    fn init(&mut self, event_loop: &ActiveEventLoop) {
+        let window = Arc::new(event_loop.create_window(self.window_config.attributes(event_loop))
+            .expect("Failed to create window"));
+        let managed = ManagedWindow::new(
+            window,
+            &self.instance,
+            &self.adapter,
+            &self.device,
+            self.window_config.present_mode(),
+            None,
+        ).expect("Failed to create surface");
+        let surface_format = managed.surface_config.format;
+        self.sample_count = self.window_config.sample_count(&self.adapter, &self.device, &[surface_format]);
+
+        // SHADER
+        let shader = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Particle Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("render.wgsl").into()),
+        });
+
+        // RENDER PIPELINE
+        let pipeline_layout = self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some("Particle Pipeline Layout"),
+            bind_group_layouts: &[
+                &self.camera_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = wgpu::RenderPipelineDescriptor {
+            label: Some("Particle Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[
+                    Particle::desc(),
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                // additive, particles glow where they overlap and need no sorting
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::Zero,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: self.sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        };
+
+        self.render_pipelines = Some(RasterPipelines::new(&self.device, &render_pipeline));
+        self.msaa_target = WindowConfig::create_msaa_view(&self.device, &managed.surface_config, managed.surface_config.format, self.sample_count);
+        self.camera.aspect = managed.aspect();
+        self.window = Some(managed);
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>) {
+        let Some(managed) = self.window.as_mut() else {
+            return;
+        };
+        if !managed.resize(&self.device, size) {
+            return;
+        }
+        self.msaa_target = WindowConfig::create_msaa_view(&self.device, &managed.surface_config, managed.surface_config.format, self.sample_count);
+        self.camera.aspect = managed.aspect();
+        managed.window.request_redraw();
+    }
+
+    // Count and emitter changes from the keyboard, printed so the values can be read off.
+    fn handle_input(&mut self) {
+        let count = self.particles.count();
+        if self.input.pressed(Action::ParticleCountUp) {
+            self.particles.set_count(&self.device, count.saturating_mul(2));
+            println!("{} particles", self.particles.count());
+        }
+        if self.input.pressed(Action::ParticleCountDown) {
+            self.particles.set_count(&self.device, count / 2);
+            println!("{} particles", self.particles.count());
+        }
+        if self.input.pressed(Action::NextEmitterParameter) {
+            self.parameter = self.parameter.next();
+            println!("Adjusting {:?}: {}", self.parameter, self.emitter.get(self.parameter));
+        }
+        for (action, factor) in [(Action::EmitterParameterUp, PARAMETER_STEP), (Action::EmitterParameterDown, 1.0 / PARAMETER_STEP)] {
+            if self.input.pressed(action) {
+                self.emitter.scale(self.parameter, factor);
+                println!("{:?}: {}", self.parameter, self.emitter.get(self.parameter));
+            }
+        }
+    }
+
+    fn render(&self) {
+        let Some(managed) = self.window.as_ref() else {
+            return;
+        };
+        let Some(surface) = managed.surface.as_ref() else {
+            return;
+        };
+        let frame = surface.get_current_texture().unwrap();
+        let frame_view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {label: Some("Render Encoder")});
+        self.particles.simulate(&mut encoder);
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: self.msaa_target.as_ref().unwrap_or(&frame_view),
+                resolve_target: self.msaa_target.as_ref().map(|_| &frame_view),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.01,
+                        g: 0.01,
+                        b: 0.02,
+                        a: 1.0 }),
+                    store: wgpu::StoreOp::Store,
+                }
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass
+            .set_pipeline(
+                self.render_pipelines
+                    .as_ref()
+                    .unwrap()
+                    .get_or_fill(self.raster_mode));
+        render_pass
+            .set_bind_group(
+                0,
+                &self.camera_bind_group,
+                &[]);
+        render_pass
+            .set_vertex_buffer(
+                0,
+                self.particles.slice());
+        render_pass
+            .draw(
+                0..6,
+                0..self.particles.count());
+        drop(render_pass);
+
+        let command_buffer = encoder.finish();
+        self.queue.submit(Some(command_buffer));
+        frame.present();
+    }
+}
+impl ApplicationHandler for Application {
+    fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
+        let _ = (event_loop, cause);
+    }
+
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(managed) = self.window.as_mut() else {
+            self.init(event_loop);
+            return;
+        };
+        if let Err(error) = managed.resume(&self.instance, &self.device) {
+            eprintln!("{error:#}");
+            event_loop.exit();
+            return;
+        }
+        let size = managed.window.inner_size();
+        self.resize(size);
+    }
+
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, _event: ()) {}
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _window_id: WindowId, event: WindowEvent) {
+        self.input.handle_window_event(&event);
+        match event {
+            WindowEvent::CloseRequested => {
+                if let Some(managed) = self.window.as_ref() {
+                    self.window_config.store(&managed.window);
+                }
+                event_loop.exit();
+            }
+            WindowEvent::RedrawRequested => { self.render(); }
+            WindowEvent::Resized(size) => { self.resize(size); }
+            _ => {}
+        }
+    }
+
+    fn device_event(&mut self, _event_loop: &ActiveEventLoop, _device_id: DeviceId, event: DeviceEvent) {
+        self.input.handle_device_event(&event);
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.input.begin_frame();
+        let Some(window) = self.window.as_ref().map(|managed| managed.window.clone()) else {
+            self.input.end_frame();
+            return;
+        };
+        if self.window_config.handle_input(&window, &self.input) {
+            self.resize(window.inner_size());
+        }
+        if self.input.pressed(Action::CameraOrbit) {
+            window.set_cursor_visible(false);
+        }
+        if self.input.released(Action::CameraOrbit) {
+            window.set_cursor_visible(true);
+        }
+        if let Some(render_pipelines) = self.render_pipelines.as_ref() {
+            render_pipelines.handle_input(&mut self.raster_mode, &self.input);
+        }
+        self.handle_input();
+        self.camera_controller.update(&mut self.camera, &self.input);
+
+        // The simulation never stops, redraw continuously.
+        self.particles.update(&self.queue, &self.emitter, self.input.dt());
+        self.queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[CameraUniform::new(&self.camera, self.emitter.size)]));
+        window.request_redraw();
+        event_loop.set_control_flow(ControlFlow::Poll);
+        self.input.end_frame();
+    }
+
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(managed) = self.window.as_mut() {
+            managed.suspend();
+        }
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {}
+
+    fn memory_warning(&mut self, _event_loop: &ActiveEventLoop) {}
+}
+
+pub fn run(window_config: WindowConfig, input: Input) {
+    // winit
+    let event_loop = EventLoop::new().unwrap();
+
+    // wgpu
+    let instance= wgpu::Instance::new(&wgpu::InstanceDescriptor { backends: wgpu::Backends::all(), ..Default::default()});
+    let adapter= task::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())).unwrap();
+    assert!(
+        adapter.get_downlevel_capabilities().flags.contains(wgpu::DownlevelFlags::COMPUTE_SHADERS),
+        "The particle simulation needs compute shaders, which this adapter does not have");
+    let (device, queue)= task::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+        // `PolygonMode::Line` for the wireframe raster mode
+        required_features: WindowConfig::msaa_features(&adapter)
+            | (adapter.features() & wgpu::Features::POLYGON_MODE_LINE),
+        ..Default::default()
+    }, None, )).unwrap();
+
+    // CAMERA
+    let settings = &window_config.settings;
+    let camera = Camera {
+        eye: (0.0, 4.0, 14.0).into(),
+        target: (0.0, 4.0, 0.0).into(),
+        up: Vector3::unit_y(),
+        aspect: settings.width as f32 / settings.height as f32,
+        fovy: 45.0,
+        znear: 0.1,
+        zfar: 200.0
+    };
+    let emitter = Emitter::default();
+    let camera_buffer = device.create_buffer_init(&BufferInitDescriptor {
+        label: Some("Camera Buffer"),
+        contents: bytemuck::cast_slice(&[CameraUniform::new(&camera, emitter.size)]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+    let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None
+                },
+                count: None
+            }
+        ],
+        label: Some("camera_bind_group_layout"),
+    });
+    let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &camera_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding()
+            }
+        ],
+        label: Some("camera_bind_group"),
+    });
+
+    // PARTICLES
+    let particles = ParticleSystem::new(&device, INITIAL_PARTICLES);
+
+    let mut application = Application{
+        window_config,
+        window: None,
+        input,
+        camera,
+        camera_controller: CameraController::new(10.0, 1.5, 0.005),
+        camera_buffer,
+        camera_bind_group_layout,
+        camera_bind_group,
+        instance,
+        adapter,
+        device,
+        queue,
+        render_pipelines: None,
+        raster_mode: RasterMode::Fill,
+        msaa_target: None,
+        sample_count: 1,
+        particles,
+        emitter,
+        parameter: EmitterParameter::Rate,
+    };
+
+    event_loop.run_app(&mut application).expect("Failed to start event_loop");
+}
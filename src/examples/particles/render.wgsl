@@ -0,0 +1,61 @@
+// Every particle is an instance, drawn as a camera facing quad made from six vertices.
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    // world space, to span the quads in the view plane
+    right: vec3<f32>,
+    size: f32,
+    up: vec3<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+// `Particle`
+struct ParticleInput {
+    @location(0) position_age: vec4<f32>,
+    @location(1) velocity_lifetime: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) corner: vec2<f32>,
+    // 0 when born, 1 when dead
+    @location(1) life: f32,
+}
+
+const CORNERS: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(1.0, -1.0),
+    vec2<f32>(1.0, 1.0),
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(1.0, 1.0),
+    vec2<f32>(-1.0, 1.0),
+);
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    particle: ParticleInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    // dead like in the simulation, which includes the zeroed ones that never spawned
+    let dead = particle.position_age.w >= particle.velocity_lifetime.w;
+    let life = select(particle.position_age.w / particle.velocity_lifetime.w, 1.0, dead);
+    var corners = CORNERS;
+    let corner = corners[vertex_index];
+    // dead particles collapse to a point and produce no fragments
+    let size = select(camera.size * (1.0 - 0.5 * life), 0.0, dead);
+    let position = particle.position_age.xyz + (camera.right * corner.x + camera.up * corner.y) * size;
+    out.clip_position = camera.view_proj * vec4<f32>(position, 1.0);
+    out.corner = corner;
+    out.life = life;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let falloff = 1.0 - smoothstep(0.0, 1.0, length(in.corner));
+    let color = mix(vec3<f32>(1.0, 0.85, 0.4), vec3<f32>(0.9, 0.15, 0.05), in.life);
+    // blended additively, fades out towards the end of its life
+    return vec4<f32>(color * falloff * (1.0 - in.life), 1.0);
+}
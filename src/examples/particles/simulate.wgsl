@@ -0,0 +1,105 @@
+// One invocation per particle. Dead particles are respawned at the emitter while this frame's
+// spawn budget lasts, live ones fall, get pushed around by curl noise and age.
+
+// `Particle`
+struct Particle {
+    position: vec3<f32>,
+    age: f32,
+    velocity: vec3<f32>,
+    lifetime: f32,
+}
+
+// `SimulationParams`
+struct SimulationParams {
+    emitter_position: vec3<f32>,
+    dt: f32,
+    time: f32,
+    speed: f32,
+    // half angle of the emission cone around +y, in radians
+    spread: f32,
+    lifetime: f32,
+    gravity: f32,
+    curl_strength: f32,
+    curl_scale: f32,
+    count: u32,
+    spawn_count: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: SimulationParams;
+@group(0) @binding(1)
+var<storage, read_write> particles: array<Particle>;
+// how many particles were respawned this frame, reset to 0 before every dispatch
+@group(0) @binding(2)
+var<storage, read_write> spawned: atomic<u32>;
+
+const PI: f32 = 3.14159265;
+
+// PCG hash, good enough randomness from an integer seed.
+fn hash(seed: u32) -> u32 {
+    let state = seed * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// Uniform in 0..1, advances `seed`.
+fn random(seed: ptr<function, u32>) -> f32 {
+    *seed = hash(*seed);
+    return f32(*seed) / 4294967295.0;
+}
+
+// Smooth vector field, the potential the curl is taken of.
+fn potential(p: vec3<f32>) -> vec3<f32> {
+    let t = params.time * 0.3;
+    return vec3<f32>(
+        sin(p.y * 1.3 + t) + sin(p.z * 2.1 - t * 0.7) * 0.5,
+        sin(p.z * 1.7 - t * 0.8) + sin(p.x * 2.3 + t) * 0.5,
+        sin(p.x * 1.1 + t * 1.2) + sin(p.y * 1.9 - t) * 0.5,
+    );
+}
+
+// Curl of `potential` by central differences. Divergence free, so particles swirl instead of
+// bunching up.
+fn curl(p: vec3<f32>) -> vec3<f32> {
+    let e = 0.01;
+    let dx = vec3<f32>(e, 0.0, 0.0);
+    let dy = vec3<f32>(0.0, e, 0.0);
+    let dz = vec3<f32>(0.0, 0.0, e);
+    let ddx = potential(p + dx) - potential(p - dx);
+    let ddy = potential(p + dy) - potential(p - dy);
+    let ddz = potential(p + dz) - potential(p - dz);
+    return vec3<f32>(
+        ddy.z - ddz.y,
+        ddz.x - ddx.z,
+        ddx.y - ddy.x,
+    ) / (2.0 * e);
+}
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.count {
+        return;
+    }
+    var particle = particles[id.x];
+    if particle.age >= particle.lifetime {
+        if atomicAdd(&spawned, 1u) >= params.spawn_count {
+            return;
+        }
+        var seed = hash(id.x ^ hash(bitcast<u32>(params.time)));
+        let angle = random(&seed) * 2.0 * PI;
+        // uniform over the cap of the cone
+        let cos_theta = 1.0 - random(&seed) * (1.0 - cos(params.spread));
+        let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+        let direction = vec3<f32>(sin_theta * cos(angle), cos_theta, sin_theta * sin(angle));
+        particle.position = params.emitter_position;
+        particle.velocity = direction * params.speed * (0.75 + 0.5 * random(&seed));
+        particle.age = 0.0;
+        particle.lifetime = params.lifetime * (0.5 + 0.5 * random(&seed));
+    } else {
+        particle.velocity.y -= params.gravity * params.dt;
+        particle.velocity += curl(particle.position * params.curl_scale) * params.curl_strength * params.dt;
+        particle.position += particle.velocity * params.dt;
+        particle.age += params.dt;
+    }
+    particles[id.x] = particle;
+}
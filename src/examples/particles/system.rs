@@ -0,0 +1,320 @@
+use std::f32::consts::PI;
+use cgmath::Point3;
+
+const WORKGROUP_SIZE: u32 = 64;
+pub const MIN_PARTICLES: u32 = 1 << 10;
+// 64 MiB of particles, half of the default storage buffer binding limit
+pub const MAX_PARTICLES: u32 = 1 << 21;
+
+// A particle is dead once `age` reaches `lifetime`, zeroed memory is all dead particles.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Particle {
+    position: [f32; 3],
+    age: f32,
+    velocity: [f32; 3],
+    lifetime: f32,
+}
+impl Particle {
+    // One instance per particle, read straight from the simulation's storage buffer.
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![
+            0 => Float32x4,
+            1 => Float32x4,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<Particle>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct SimulationParams {
+    emitter_position: [f32; 3],
+    dt: f32,
+    time: f32,
+    speed: f32,
+    spread: f32,
+    lifetime: f32,
+    gravity: f32,
+    curl_strength: f32,
+    curl_scale: f32,
+    count: u32,
+    spawn_count: u32,
+    _padding: [u32; 3],
+}
+
+// Where particles come from and what happens to them, read by the simulation every frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Emitter {
+    pub position: Point3<f32>,
+    // particles per second, as long as there are dead ones to respawn
+    pub rate: f32,
+    // initial speed, randomized by ±25%
+    pub speed: f32,
+    // half angle of the emission cone around +y, in radians
+    pub spread: f32,
+    // seconds, randomized down to half
+    pub lifetime: f32,
+    pub gravity: f32,
+    pub curl_strength: f32,
+    // frequency of the curl noise, larger is a finer swirl
+    pub curl_scale: f32,
+    // half width of a particle's quad when it is born
+    pub size: f32,
+}
+impl Default for Emitter {
+    fn default() -> Self {
+        Self {
+            position: Point3::new(0.0, 0.0, 0.0),
+            rate: 20000.0,
+            speed: 6.0,
+            spread: 0.35,
+            lifetime: 4.0,
+            gravity: 2.5,
+            curl_strength: 1.5,
+            curl_scale: 0.6,
+            size: 0.04,
+        }
+    }
+}
+
+// The emitter values that can be changed at runtime, one at a time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmitterParameter {
+    Rate,
+    Speed,
+    Spread,
+    Lifetime,
+    Gravity,
+    CurlStrength,
+    CurlScale,
+    Size,
+}
+impl EmitterParameter {
+    pub const ALL: [Self; 8] = [
+        Self::Rate,
+        Self::Speed,
+        Self::Spread,
+        Self::Lifetime,
+        Self::Gravity,
+        Self::CurlStrength,
+        Self::CurlScale,
+        Self::Size,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&parameter| parameter == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+impl Emitter {
+    pub fn get(&self, parameter: EmitterParameter) -> f32 {
+        match parameter {
+            EmitterParameter::Rate => self.rate,
+            EmitterParameter::Speed => self.speed,
+            EmitterParameter::Spread => self.spread,
+            EmitterParameter::Lifetime => self.lifetime,
+            EmitterParameter::Gravity => self.gravity,
+            EmitterParameter::CurlStrength => self.curl_strength,
+            EmitterParameter::CurlScale => self.curl_scale,
+            EmitterParameter::Size => self.size,
+        }
+    }
+
+    // Multiplies the parameter by `factor`, the spread stays a cone.
+    pub fn scale(&mut self, parameter: EmitterParameter, factor: f32) {
+        let value = match parameter {
+            EmitterParameter::Rate => &mut self.rate,
+            EmitterParameter::Speed => &mut self.speed,
+            EmitterParameter::Spread => &mut self.spread,
+            EmitterParameter::Lifetime => &mut self.lifetime,
+            EmitterParameter::Gravity => &mut self.gravity,
+            EmitterParameter::CurlStrength => &mut self.curl_strength,
+            EmitterParameter::CurlScale => &mut self.curl_scale,
+            EmitterParameter::Size => &mut self.size,
+        };
+        *value *= factor;
+        self.spread = self.spread.min(PI);
+    }
+}
+
+// Particles simulated in a compute pass, they never leave the GPU. The storage buffer doubles as
+// the instance buffer for drawing, bind `slice` and draw `count` instances.
+pub struct ParticleSystem {
+    count: u32,
+    particle_buffer: wgpu::Buffer,
+    params_buffer: wgpu::Buffer,
+    spawned_buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::ComputePipeline,
+    time: f32,
+    // fraction of a particle left over from the last frame's emission
+    spawn_budget: f32,
+}
+impl ParticleSystem {
+    pub fn new(device: &wgpu::Device, count: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Particle Simulation Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("simulate.wgsl").into()),
+        });
+        let storage = |binding: u32| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage(1),
+                storage(2),
+            ],
+            label: Some("particle_bind_group_layout"),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Particle Simulation Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Particle Simulation Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("cs_main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Params Buffer"),
+            size: size_of::<SimulationParams>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let spawned_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Spawn Counter"),
+            size: size_of::<u32>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let particle_buffer = Self::create_particle_buffer(device, count);
+        let bind_group = Self::create_bind_group(device, &bind_group_layout, &params_buffer, &particle_buffer, &spawned_buffer);
+        Self {
+            count,
+            particle_buffer,
+            params_buffer,
+            spawned_buffer,
+            bind_group_layout,
+            bind_group,
+            pipeline,
+            time: 0.0,
+            spawn_budget: 0.0,
+        }
+    }
+
+    // wgpu zeroes new buffers, every particle starts out dead.
+    fn create_particle_buffer(device: &wgpu::Device, count: u32) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Buffer"),
+            size: (count as usize * size_of::<Particle>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        params_buffer: &wgpu::Buffer,
+        particle_buffer: &wgpu::Buffer,
+        spawned_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: particle_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: spawned_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("particle_bind_group"),
+        })
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    // Starts over with `count` dead particles, clamped to `MIN_PARTICLES..=MAX_PARTICLES`.
+    pub fn set_count(&mut self, device: &wgpu::Device, count: u32) {
+        self.count = count.clamp(MIN_PARTICLES, MAX_PARTICLES);
+        self.particle_buffer = Self::create_particle_buffer(device, self.count);
+        self.bind_group = Self::create_bind_group(device, &self.bind_group_layout, &self.params_buffer, &self.particle_buffer, &self.spawned_buffer);
+    }
+
+    // Advances the clock and hands this frame's emitter to the next `simulate`.
+    pub fn update(&mut self, queue: &wgpu::Queue, emitter: &Emitter, dt: f32) {
+        // a long stall would throw every particle far out of the frame
+        let dt = dt.min(0.1);
+        self.time += dt;
+        self.spawn_budget = (self.spawn_budget + emitter.rate * dt).min(self.count as f32);
+        let spawn_count = self.spawn_budget.floor();
+        self.spawn_budget -= spawn_count;
+        let params = SimulationParams {
+            emitter_position: emitter.position.into(),
+            dt,
+            time: self.time,
+            speed: emitter.speed,
+            spread: emitter.spread,
+            lifetime: emitter.lifetime,
+            gravity: emitter.gravity,
+            curl_strength: emitter.curl_strength,
+            curl_scale: emitter.curl_scale,
+            count: self.count,
+            spawn_count: spawn_count as u32,
+            _padding: [0; 3],
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&[params]));
+        queue.write_buffer(&self.spawned_buffer, 0, bytemuck::cast_slice(&[0u32]));
+    }
+
+    pub fn simulate(&self, encoder: &mut wgpu::CommandEncoder) {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Particle Simulation Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.pipeline);
+        compute_pass.set_bind_group(0, &self.bind_group, &[]);
+        compute_pass.dispatch_workgroups(self.count.div_ceil(WORKGROUP_SIZE), 1, 1);
+    }
+
+    pub fn slice(&self) -> wgpu::BufferSlice<'_> {
+        self.particle_buffer.slice(..)
+    }
+}
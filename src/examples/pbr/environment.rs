@@ -0,0 +1,304 @@
+use std::{
+    cell::Cell,
+    f32::consts::PI,
+    fs,
+    path::Path,
+};
+use anyhow::{
+    Context,
+    Result,
+};
+use cgmath::{
+    InnerSpace,
+    Vector3,
+};
+use crate::examples::perspective_camera::texture::Texture;
+
+pub const ENVIRONMENT_SIZE: u32 = 512;
+const IRRADIANCE_SIZE: u32 = 32;
+const PREFILTERED_SIZE: u32 = 128;
+// roughness 0, 0.25, 0.5, 0.75 and 1 on mips 0 to 4
+pub const PREFILTERED_MIPS: u32 = 5;
+const BRDF_LUT_SIZE: u32 = 256;
+const SAMPLE_COUNT: u32 = 512;
+// Filterable and renderable everywhere, enough range for the sun.
+const CUBE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct IblUniform {
+    face: u32,
+    roughness: f32,
+    source_size: f32,
+    sample_count: u32,
+}
+
+// Which of ibl.wgsl's inputs a pass reads.
+#[derive(Clone, Copy)]
+enum Source {
+    Equirect,
+    Face,
+    Environment,
+    None,
+}
+
+// Image based lighting: the environment as a cubemap plus everything the split sum approximation needs.
+// Computed once with render passes when the environment is loaded.
+pub struct Environment {
+    pub cubemap: Texture,
+    pub irradiance: Texture,
+    pub prefiltered: Texture,
+    pub brdf_lut: Texture,
+}
+impl Environment {
+    pub fn load(device: &wgpu::Device, queue: &wgpu::Queue, path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let equirect = Texture::from_hdr_bytes(device, queue, &bytes, "equirect")
+            .with_context(|| format!("Failed to load {}", path.display()))?;
+        Ok(Self::from_equirect(device, queue, &equirect))
+    }
+
+    // Gradient sky with a sun in `sun_direction` (pointing at the sun) over a dark ground.
+    pub fn procedural_sky(device: &wgpu::Device, queue: &wgpu::Queue, sun_direction: Vector3<f32>) -> Self {
+        let sun_direction = sun_direction.normalize();
+        let (width, height) = (512, 256);
+        let image = image::Rgba32FImage::from_fn(width, height, |x, y| {
+            // inverse of the lookup in `fs_equirect_to_cube`
+            let phi = ((x as f32 + 0.5) / width as f32 - 0.5) * 2.0 * PI;
+            let theta = (y as f32 + 0.5) / height as f32 * PI;
+            let direction = Vector3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+            let horizon = Vector3::new(0.9, 0.85, 0.8);
+            let zenith = Vector3::new(0.25, 0.45, 0.9);
+            let ground = Vector3::new(0.15, 0.13, 0.12);
+            let mut color = if direction.y > 0.0 {
+                horizon + (zenith - horizon) * direction.y.sqrt()
+            } else {
+                horizon * 0.3 + (ground - horizon * 0.3) * (-direction.y).sqrt().min(1.0)
+            };
+            let sun = direction.dot(sun_direction);
+            color += Vector3::new(1.0, 0.9, 0.7) * (if sun > 0.9995 { 200.0 } else { 0.0 } + sun.max(0.0).powf(64.0) * 2.0);
+            image::Rgba([color.x, color.y, color.z, 1.0])
+        });
+        let equirect = Texture::from_hdr_image(device, queue, &image, Some("procedural_sky"));
+        Self::from_equirect(device, queue, &equirect)
+    }
+
+    pub fn from_equirect(device: &wgpu::Device, queue: &wgpu::Queue, equirect: &Texture) -> Self {
+        let environment_mips = ENVIRONMENT_SIZE.ilog2() + 1;
+        let cubemap = Texture::create_cube(device, "environment_cubemap", ENVIRONMENT_SIZE, environment_mips, CUBE_FORMAT);
+        let irradiance = Texture::create_cube(device, "irradiance_cubemap", IRRADIANCE_SIZE, 1, CUBE_FORMAT);
+        let prefiltered = Texture::create_cube(device, "prefiltered_cubemap", PREFILTERED_SIZE, PREFILTERED_MIPS, CUBE_FORMAT);
+        let brdf_lut = Texture::create_render_target(device, "brdf_lut", BRDF_LUT_SIZE, BRDF_LUT_SIZE, wgpu::TextureFormat::Rg16Float);
+
+        let draw_count = 6 * (environment_mips + 1 + PREFILTERED_MIPS) + 1;
+        let baker = Baker::new(device, queue, draw_count);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {label: Some("IBL Encoder")});
+        for face in 0..6 {
+            let uniform = IblUniform { face, roughness: 0.0, source_size: ENVIRONMENT_SIZE as f32, sample_count: SAMPLE_COUNT };
+            baker.draw(&mut encoder, &baker.equirect_to_cube_pipeline, uniform, Source::Equirect, &equirect.view, &cubemap.face_view(face, 0));
+            for mip in 1..environment_mips {
+                baker.draw(&mut encoder, &baker.downsample_pipeline, uniform, Source::Face, &cubemap.face_view(face, mip - 1), &cubemap.face_view(face, mip));
+            }
+        }
+        for face in 0..6 {
+            let uniform = IblUniform { face, roughness: 0.0, source_size: ENVIRONMENT_SIZE as f32, sample_count: SAMPLE_COUNT };
+            baker.draw(&mut encoder, &baker.irradiance_pipeline, uniform, Source::Environment, &cubemap.view, &irradiance.face_view(face, 0));
+            for mip in 0..PREFILTERED_MIPS {
+                let roughness = mip as f32 / (PREFILTERED_MIPS - 1) as f32;
+                let uniform = IblUniform { roughness, ..uniform };
+                baker.draw(&mut encoder, &baker.prefilter_pipeline, uniform, Source::Environment, &cubemap.view, &prefiltered.face_view(face, mip));
+            }
+        }
+        let uniform = IblUniform { face: 0, roughness: 0.0, source_size: 0.0, sample_count: SAMPLE_COUNT };
+        baker.draw(&mut encoder, &baker.brdf_pipeline, uniform, Source::None, &cubemap.view, &brdf_lut.view);
+        queue.submit(Some(encoder.finish()));
+
+        Self { cubemap, irradiance, prefiltered, brdf_lut }
+    }
+}
+
+// Pipelines and bindings for the passes of ibl.wgsl, only alive while an environment is computed.
+struct Baker<'a> {
+    device: &'a wgpu::Device,
+    queue: &'a wgpu::Queue,
+    sampler: wgpu::Sampler,
+    // one uniform slot per draw, written as the draws are recorded and uploaded before the submit
+    uniform_buffer: wgpu::Buffer,
+    uniform_stride: u64,
+    next_uniform: Cell<u64>,
+    // 1x1 placeholders for the inputs a pass does not read
+    empty_equirect: Texture,
+    empty_face: wgpu::TextureView,
+    empty_cube: Texture,
+    bind_group_layout: wgpu::BindGroupLayout,
+    equirect_to_cube_pipeline: wgpu::RenderPipeline,
+    downsample_pipeline: wgpu::RenderPipeline,
+    irradiance_pipeline: wgpu::RenderPipeline,
+    prefilter_pipeline: wgpu::RenderPipeline,
+    brdf_pipeline: wgpu::RenderPipeline,
+}
+impl<'a> Baker<'a> {
+    fn new(device: &'a wgpu::Device, queue: &'a wgpu::Queue, draw_count: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("IBL Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("ibl.wgsl").into()),
+        });
+        let texture_entry = |binding, view_dimension, filterable| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                multisampled: false,
+                view_dimension,
+                sample_type: wgpu::TextureSampleType::Float { filterable },
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+                texture_entry(1, wgpu::TextureViewDimension::D2, false),
+                texture_entry(2, wgpu::TextureViewDimension::D2, true),
+                texture_entry(3, wgpu::TextureViewDimension::Cube, true),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("ibl_bind_group_layout"),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some("IBL Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let create_pipeline = |entry_point, format: wgpu::TextureFormat| device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(entry_point),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_fullscreen"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                targets: &[Some(format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        let equirect_to_cube_pipeline = create_pipeline("fs_equirect_to_cube", CUBE_FORMAT);
+        let downsample_pipeline = create_pipeline("fs_downsample", CUBE_FORMAT);
+        let irradiance_pipeline = create_pipeline("fs_irradiance", CUBE_FORMAT);
+        let prefilter_pipeline = create_pipeline("fs_prefilter", CUBE_FORMAT);
+        let brdf_pipeline = create_pipeline("fs_brdf", wgpu::TextureFormat::Rg16Float);
+
+        let uniform_stride = (size_of::<IblUniform>() as u64)
+            .next_multiple_of(device.limits().min_uniform_buffer_offset_alignment as u64);
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("IBL Uniform Buffer"),
+            size: uniform_stride * draw_count as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let empty_equirect = Texture::from_hdr_image(device, queue, &image::Rgba32FImage::new(1, 1), Some("empty_equirect"));
+        let empty_cube = Texture::create_cube(device, "empty_cube", 1, 1, CUBE_FORMAT);
+        let empty_face = empty_cube.face_view(0, 0);
+        Self {
+            device,
+            queue,
+            sampler,
+            uniform_buffer,
+            uniform_stride,
+            next_uniform: Cell::new(0),
+            empty_equirect,
+            empty_face,
+            empty_cube,
+            bind_group_layout,
+            equirect_to_cube_pipeline,
+            downsample_pipeline,
+            irradiance_pipeline,
+            prefilter_pipeline,
+            brdf_pipeline,
+        }
+    }
+
+    // Renders `pipeline` over all of `target`, reading `source` in the slot given by `kind`.
+    fn draw(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::RenderPipeline,
+        uniform: IblUniform,
+        kind: Source,
+        source: &wgpu::TextureView,
+        target: &wgpu::TextureView,
+    ) {
+        let offset = self.next_uniform.get() * self.uniform_stride;
+        self.next_uniform.set(self.next_uniform.get() + 1);
+        self.queue.write_buffer(&self.uniform_buffer, offset, bytemuck::cast_slice(&[uniform]));
+        let (equirect, face, cube) = match kind {
+            Source::Equirect => (source, &self.empty_face, &self.empty_cube.view),
+            Source::Face => (&self.empty_equirect.view, source, &self.empty_cube.view),
+            Source::Environment => (&self.empty_equirect.view, &self.empty_face, source),
+            Source::None => (&self.empty_equirect.view, &self.empty_face, &self.empty_cube.view),
+        };
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &self.uniform_buffer,
+                        offset,
+                        size: wgpu::BufferSize::new(size_of::<IblUniform>() as u64),
+                    }),
+                },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(equirect) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(face) },
+                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(cube) },
+                wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::Sampler(&self.sampler) },
+            ],
+            label: Some("ibl_bind_group"),
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("IBL Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                }
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
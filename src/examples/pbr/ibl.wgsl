@@ -0,0 +1,204 @@
+// Precomputes image based lighting from an environment map, one full-screen triangle per face and mip:
+// equirectangular -> cubemap, cubemap mips, diffuse irradiance, GGX prefiltered specular and the BRDF LUT.
+
+const PI: f32 = 3.14159265359;
+
+struct IblUniform {
+    face: u32,
+    roughness: f32,
+    // width of the environment cubemap's first mip
+    source_size: f32,
+    sample_count: u32,
+};
+@group(0) @binding(0)
+var<uniform> ibl: IblUniform;
+@group(0) @binding(1)
+var t_equirect: texture_2d<f32>;
+@group(0) @binding(2)
+var t_face: texture_2d<f32>;
+@group(0) @binding(3)
+var t_environment: texture_cube<f32>;
+@group(0) @binding(4)
+var s_linear: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // 0..1 with v growing downwards like texture coordinates
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let position = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(position, 0.0, 1.0);
+    out.uv = vec2<f32>(position.x + 1.0, 1.0 - position.y) * 0.5;
+    return out;
+}
+
+// Direction through `uv` of cube face `face`, same layout as wgpu samples cubemaps with.
+fn cube_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    let u = uv.x * 2.0 - 1.0;
+    let v = uv.y * 2.0 - 1.0;
+    switch face {
+        case 0u: { return normalize(vec3<f32>(1.0, -v, -u)); }
+        case 1u: { return normalize(vec3<f32>(-1.0, -v, u)); }
+        case 2u: { return normalize(vec3<f32>(u, 1.0, v)); }
+        case 3u: { return normalize(vec3<f32>(u, -1.0, -v)); }
+        case 4u: { return normalize(vec3<f32>(u, -v, 1.0)); }
+        default: { return normalize(vec3<f32>(-u, -v, -1.0)); }
+    }
+}
+
+// `Rgba32Float` cannot be filtered by the sampler, interpolate by hand.
+fn load_equirect(uv: vec2<f32>) -> vec3<f32> {
+    let size = vec2<i32>(textureDimensions(t_equirect));
+    let texel = uv * vec2<f32>(size) - 0.5;
+    let base = vec2<i32>(floor(texel));
+    let fraction = texel - floor(texel);
+    var samples: array<vec3<f32>, 4>;
+    for (var i = 0; i < 4; i++) {
+        let offset = vec2<i32>(i & 1, i >> 1u);
+        // wrap around horizontally, clamp at the poles
+        let x = (base.x + offset.x + size.x) % size.x;
+        let y = clamp(base.y + offset.y, 0, size.y - 1);
+        samples[i] = textureLoad(t_equirect, vec2<i32>(x, y), 0).rgb;
+    }
+    return mix(mix(samples[0], samples[1], fraction.x), mix(samples[2], samples[3], fraction.x), fraction.y);
+}
+
+@fragment
+fn fs_equirect_to_cube(in: VertexOutput) -> @location(0) vec4<f32> {
+    let direction = cube_direction(ibl.face, in.uv);
+    let uv = vec2<f32>(atan2(direction.z, direction.x) / (2.0 * PI) + 0.5, acos(clamp(direction.y, -1.0, 1.0)) / PI);
+    return vec4<f32>(load_equirect(uv), 1.0);
+}
+
+// Bilinear sample in the middle of 2x2 texels of the previous mip, i.e. their average.
+@fragment
+fn fs_downsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSampleLevel(t_face, s_linear, in.uv, 0.0);
+}
+
+// Cosine weighted hemisphere integral divided by pi, i.e. what a white Lambert surface reflects.
+// Sampled from a low mip to keep the sample count small.
+@fragment
+fn fs_irradiance(in: VertexOutput) -> @location(0) vec4<f32> {
+    let normal = cube_direction(ibl.face, in.uv);
+    var up = vec3<f32>(0.0, 1.0, 0.0);
+    if abs(normal.y) > 0.999 {
+        up = vec3<f32>(1.0, 0.0, 0.0);
+    }
+    let right = normalize(cross(up, normal));
+    up = cross(normal, right);
+
+    let step = 0.1;
+    let level = max(log2(ibl.source_size) - 5.0, 0.0);
+    var irradiance = vec3<f32>(0.0);
+    var count = 0.0;
+    for (var phi = 0.0; phi < 2.0 * PI; phi += step) {
+        for (var theta = 0.0; theta < 0.5 * PI; theta += step) {
+            let tangent = vec3<f32>(sin(theta) * cos(phi), sin(theta) * sin(phi), cos(theta));
+            let direction = tangent.x * right + tangent.y * up + tangent.z * normal;
+            irradiance += textureSampleLevel(t_environment, s_linear, direction, level).rgb * cos(theta) * sin(theta);
+            count += 1.0;
+        }
+    }
+    return vec4<f32>(PI * irradiance / count, 1.0);
+}
+
+fn radical_inverse(bits_in: u32) -> f32 {
+    var bits = (bits_in << 16u) | (bits_in >> 16u);
+    bits = ((bits & 0x55555555u) << 1u) | ((bits & 0xAAAAAAAAu) >> 1u);
+    bits = ((bits & 0x33333333u) << 2u) | ((bits & 0xCCCCCCCCu) >> 2u);
+    bits = ((bits & 0x0F0F0F0Fu) << 4u) | ((bits & 0xF0F0F0F0u) >> 4u);
+    bits = ((bits & 0x00FF00FFu) << 8u) | ((bits & 0xFF00FF00u) >> 8u);
+    return f32(bits) * 2.3283064365386963e-10;
+}
+
+fn hammersley(i: u32, count: u32) -> vec2<f32> {
+    return vec2<f32>(f32(i) / f32(count), radical_inverse(i));
+}
+
+// Half vector around `normal` distributed like GGX with `alpha` = roughness squared.
+fn importance_sample_ggx(xi: vec2<f32>, normal: vec3<f32>, alpha: f32) -> vec3<f32> {
+    let phi = 2.0 * PI * xi.x;
+    let cos_theta = sqrt((1.0 - xi.y) / (1.0 + (alpha * alpha - 1.0) * xi.y));
+    let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+    let h = vec3<f32>(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+    var up = vec3<f32>(0.0, 0.0, 1.0);
+    if abs(normal.z) > 0.999 {
+        up = vec3<f32>(1.0, 0.0, 0.0);
+    }
+    let tangent = normalize(cross(up, normal));
+    let bitangent = cross(normal, tangent);
+    return normalize(tangent * h.x + bitangent * h.y + normal * h.z);
+}
+
+fn distribution_ggx(n_dot_h: f32, alpha: f32) -> f32 {
+    let alpha2 = alpha * alpha;
+    let d = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    return alpha2 / (PI * d * d);
+}
+
+// Split sum prefilter (Karis 2013), assumes view = normal. Samples come from the mip whose
+// texel footprint matches the sample's solid angle, which avoids the fireflies of a fixed mip.
+@fragment
+fn fs_prefilter(in: VertexOutput) -> @location(0) vec4<f32> {
+    let normal = cube_direction(ibl.face, in.uv);
+    let alpha = ibl.roughness * ibl.roughness;
+    if ibl.roughness == 0.0 {
+        return vec4<f32>(textureSampleLevel(t_environment, s_linear, normal, 0.0).rgb, 1.0);
+    }
+    let texel_solid_angle = 4.0 * PI / (6.0 * ibl.source_size * ibl.source_size);
+    var color = vec3<f32>(0.0);
+    var weight = 0.0;
+    for (var i = 0u; i < ibl.sample_count; i++) {
+        let h = importance_sample_ggx(hammersley(i, ibl.sample_count), normal, alpha);
+        let l = normalize(2.0 * dot(normal, h) * h - normal);
+        let n_dot_l = dot(normal, l);
+        if n_dot_l > 0.0 {
+            let n_dot_h = max(dot(normal, h), 0.0);
+            // with view = normal, pdf(l) = D * n.h / (4 v.h) = D / 4
+            let pdf = distribution_ggx(n_dot_h, alpha) / 4.0;
+            let sample_solid_angle = 1.0 / (f32(ibl.sample_count) * pdf + 0.0001);
+            let level = max(0.5 * log2(sample_solid_angle / texel_solid_angle) + 1.0, 0.0);
+            color += textureSampleLevel(t_environment, s_linear, l, level).rgb * n_dot_l;
+            weight += n_dot_l;
+        }
+    }
+    return vec4<f32>(color / max(weight, 0.0001), 1.0);
+}
+
+fn geometry_schlick_ggx_ibl(n_dot_x: f32, alpha: f32) -> f32 {
+    let k = alpha / 2.0;
+    return n_dot_x / (n_dot_x * (1.0 - k) + k);
+}
+
+// Scale (r) and bias (g) applied to F0 for the specular part of the split sum,
+// indexed by n.v (u) and roughness (v).
+@fragment
+fn fs_brdf(in: VertexOutput) -> @location(0) vec4<f32> {
+    let n_dot_v = max(in.uv.x, 0.001);
+    let roughness = 1.0 - in.uv.y;
+    let alpha = roughness * roughness;
+    let view = vec3<f32>(sqrt(1.0 - n_dot_v * n_dot_v), 0.0, n_dot_v);
+    let normal = vec3<f32>(0.0, 0.0, 1.0);
+    var scale = 0.0;
+    var bias = 0.0;
+    for (var i = 0u; i < ibl.sample_count; i++) {
+        let h = importance_sample_ggx(hammersley(i, ibl.sample_count), normal, alpha);
+        let l = normalize(2.0 * dot(view, h) * h - view);
+        let n_dot_l = saturate(l.z);
+        let n_dot_h = saturate(h.z);
+        let v_dot_h = saturate(dot(view, h));
+        if n_dot_l > 0.0 {
+            let geometry = geometry_schlick_ggx_ibl(n_dot_v, alpha) * geometry_schlick_ggx_ibl(n_dot_l, alpha);
+            let visibility = geometry * v_dot_h / (n_dot_h * n_dot_v);
+            let fresnel = pow(1.0 - v_dot_h, 5.0);
+            scale += (1.0 - fresnel) * visibility;
+            bias += fresnel * visibility;
+        }
+    }
+    return vec4<f32>(scale, bias, 0.0, 1.0) / f32(ibl.sample_count);
+}
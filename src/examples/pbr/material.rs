@@ -0,0 +1,256 @@
+use std::{
+    fs,
+    path::Path,
+};
+use anyhow::{
+    ensure,
+    Context,
+    Result,
+};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use crate::examples::perspective_camera::texture::Texture;
+
+// The textures of a glTF metallic-roughness material, in binding order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureSlot {
+    BaseColor,
+    // roughness in green, metallic in blue
+    MetallicRoughness,
+    // tangent space, +Y up
+    Normal,
+    // red channel
+    Occlusion,
+    Emissive,
+}
+impl TextureSlot {
+    pub const ALL: [TextureSlot; 5] = [
+        TextureSlot::BaseColor,
+        TextureSlot::MetallicRoughness,
+        TextureSlot::Normal,
+        TextureSlot::Occlusion,
+        TextureSlot::Emissive,
+    ];
+
+    // File name stem used by `load_material`.
+    pub fn name(self) -> &'static str {
+        match self {
+            TextureSlot::BaseColor => "base_color",
+            TextureSlot::MetallicRoughness => "metallic_roughness",
+            TextureSlot::Normal => "normal",
+            TextureSlot::Occlusion => "occlusion",
+            TextureSlot::Emissive => "emissive",
+        }
+    }
+
+    // Only colors are sRGB encoded, everything else is data and sampled as is.
+    pub fn format(self) -> wgpu::TextureFormat {
+        match self {
+            TextureSlot::BaseColor | TextureSlot::Emissive => wgpu::TextureFormat::Rgba8UnormSrgb,
+            _ => wgpu::TextureFormat::Rgba8Unorm,
+        }
+    }
+
+    // Used when the material has no texture in this slot, so the factor is all that counts.
+    fn default_color(self) -> [u8; 4] {
+        match self {
+            TextureSlot::Normal => [128, 128, 255, 255],
+            _ => [255, 255, 255, 255],
+        }
+    }
+
+    fn binding(self) -> u32 {
+        self as u32
+    }
+}
+
+// Factors multiplied with the textures, defaults are the ones from the glTF spec.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MaterialFactors {
+    pub base_color: [f32; 4],
+    pub metallic: f32,
+    pub roughness: f32,
+    pub normal_scale: f32,
+    pub occlusion_strength: f32,
+    pub emissive: [f32; 3],
+}
+impl Default for MaterialFactors {
+    fn default() -> Self {
+        Self {
+            base_color: [1.0; 4],
+            metallic: 1.0,
+            roughness: 1.0,
+            normal_scale: 1.0,
+            occlusion_strength: 1.0,
+            emissive: [0.0; 3],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct MaterialUniform {
+    base_color: [f32; 4],
+    emissive: [f32; 3],
+    metallic: f32,
+    roughness: f32,
+    normal_scale: f32,
+    occlusion_strength: f32,
+    _padding: f32,
+}
+impl From<MaterialFactors> for MaterialUniform {
+    fn from(factors: MaterialFactors) -> Self {
+        Self {
+            base_color: factors.base_color,
+            emissive: factors.emissive,
+            metallic: factors.metallic,
+            roughness: factors.roughness,
+            normal_scale: factors.normal_scale,
+            occlusion_strength: factors.occlusion_strength,
+            _padding: 0.0,
+        }
+    }
+}
+
+// Bind group layout generated from `TextureSlot::ALL`: one texture per slot,
+// then a shared sampler and the factors. Also owns the 1x1 fallback textures.
+pub struct MaterialLayout {
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    defaults: Vec<Texture>,
+}
+impl MaterialLayout {
+    const SAMPLER_BINDING: u32 = TextureSlot::ALL.len() as u32;
+    const FACTORS_BINDING: u32 = TextureSlot::ALL.len() as u32 + 1;
+
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let mut entries: Vec<_> = TextureSlot::ALL.iter()
+            .map(|slot| wgpu::BindGroupLayoutEntry {
+                binding: slot.binding(),
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            })
+            .collect();
+        entries.push(wgpu::BindGroupLayoutEntry {
+            binding: Self::SAMPLER_BINDING,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        });
+        entries.push(wgpu::BindGroupLayoutEntry {
+            binding: Self::FACTORS_BINDING,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None
+            },
+            count: None
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &entries,
+            label: Some("material_bind_group_layout"),
+        });
+        // Materials tile their textures, unlike the clamped sampler of `Texture`.
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Material Sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            address_mode_w: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let defaults = TextureSlot::ALL.iter()
+            .map(|slot| {
+                let image = image::RgbaImage::from_pixel(1, 1, image::Rgba(slot.default_color()));
+                Texture::from_image(device, queue, &image.into(), Some("Default Material Texture"), slot.format()).unwrap()
+            })
+            .collect();
+        Self { bind_group_layout, sampler, defaults }
+    }
+}
+
+// A glTF style metallic-roughness material, immutable once its bind group is created.
+pub struct Material {
+    pub name: String,
+    pub bind_group: wgpu::BindGroup,
+}
+impl Material {
+    // `textures` pairs slots with textures, slots left out use the fallback texture.
+    // Panics when a texture's format does not match `TextureSlot::format`.
+    pub fn new(
+        device: &wgpu::Device,
+        layout: &MaterialLayout,
+        name: &str,
+        factors: MaterialFactors,
+        textures: &[(TextureSlot, &Texture)],
+    ) -> Self {
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some(&format!("{name} Material Buffer")),
+            contents: bytemuck::cast_slice(&[MaterialUniform::from(factors)]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let mut entries: Vec<_> = TextureSlot::ALL.iter()
+            .zip(&layout.defaults)
+            .map(|(&slot, default)| {
+                let texture = textures.iter()
+                    .find(|(texture_slot, _)| *texture_slot == slot)
+                    .map_or(default, |(_, texture)| texture);
+                assert_eq!(texture.texture.format(), slot.format(), "{name}: wrong format for {slot:?}");
+                wgpu::BindGroupEntry {
+                    binding: slot.binding(),
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                }
+            })
+            .collect();
+        entries.push(wgpu::BindGroupEntry {
+            binding: MaterialLayout::SAMPLER_BINDING,
+            resource: wgpu::BindingResource::Sampler(&layout.sampler),
+        });
+        entries.push(wgpu::BindGroupEntry {
+            binding: MaterialLayout::FACTORS_BINDING,
+            resource: buffer.as_entire_binding(),
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &layout.bind_group_layout,
+            entries: &entries,
+            label: Some(&format!("{name} Material Bind Group")),
+        });
+        Self { name: name.to_string(), bind_group }
+    }
+}
+
+// Loads `<slot name>.png` (or `.jpg`) for every slot found in `dir`, e.g. `base_color.png`, `normal.jpg`.
+// Without a metallic-roughness map the surface is a rough dielectric instead of glTF's default metal.
+pub fn load_material(device: &wgpu::Device, queue: &wgpu::Queue, layout: &MaterialLayout, dir: &Path) -> Result<Material> {
+    let mut textures = Vec::new();
+    for slot in TextureSlot::ALL {
+        let Some(path) = ["png", "jpg"].iter()
+            .map(|extension| dir.join(slot.name()).with_extension(extension))
+            .find(|path| path.is_file()) else {
+            continue;
+        };
+        let bytes = fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let texture = Texture::from_bytes_with_format(device, queue, &bytes, slot.name(), slot.format())
+            .with_context(|| format!("Failed to load {}", path.display()))?;
+        textures.push((slot, texture));
+    }
+    ensure!(!textures.is_empty(), "No material textures found in {}", dir.display());
+    let has = |slot: TextureSlot| textures.iter().any(|(texture_slot, _)| *texture_slot == slot);
+    let factors = MaterialFactors {
+        metallic: if has(TextureSlot::MetallicRoughness) { 1.0 } else { 0.0 },
+        roughness: if has(TextureSlot::MetallicRoughness) { 1.0 } else { 0.5 },
+        emissive: if has(TextureSlot::Emissive) { [1.0; 3] } else { [0.0; 3] },
+        ..MaterialFactors::default()
+    };
+    let name = dir.file_name().map_or_else(|| dir.display().to_string(), |name| name.to_string_lossy().into_owned());
+    let textures: Vec<_> = textures.iter().map(|(slot, texture)| (*slot, texture)).collect();
+    Ok(Material::new(device, layout, &name, factors, &textures))
+}
@@ -0,0 +1,877 @@
+pub mod environment;
+pub mod material;
+pub mod skybox;
+
+use std::{
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::Arc,
+};
+use winit::{
+    event_loop::{
+        EventLoop,
+        ActiveEventLoop,
+        ControlFlow,
+    },
+    event::{
+        DeviceEvent,
+        DeviceId,
+        StartCause,
+        WindowEvent
+    },
+    window::WindowId,
+    dpi::PhysicalSize,
+    application::ApplicationHandler,
+};
+use async_std::task;
+use cgmath::{
+    Deg,
+    EuclideanSpace,
+    Matrix4,
+    SquareMatrix,
+    Vector3,
+};
+use crate::input::{
+    Action,
+    Input,
+};
+use crate::window::{
+    ManagedWindow,
+    WindowConfig,
+};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use crate::examples::perspective_camera::camera::{
+    Camera,
+    CameraController,
+};
+use crate::examples::perspective_camera::texture;
+use crate::examples::lighting::{
+    CameraUniform,
+};
+use crate::examples::lighting::debug_view::{
+    DebugMesh,
+    DebugView,
+};
+use crate::examples::raster_mode::{
+    RasterMode,
+    RasterPipelines,
+};
+use crate::examples::lighting::light::{
+    Light,
+    LightKind,
+    Lights,
+};
+use crate::examples::lighting::mesh::{
+    Mesh,
+    MeshData,
+    Vertex,
+};
+use crate::examples::lighting::model::Models;
+use crate::examples::lighting::shadow::ShadowDebugView;
+use crate::examples::post_process::HDR_FORMAT;
+use crate::examples::post_process::tone_mapping::ToneMapper;
+use crate::examples::post_process::bloom::Bloom;
+use crate::examples::post_process::chain::{
+    Effect,
+    PostProcessChain,
+};
+use crate::examples::pbr::environment::{
+    Environment,
+    PREFILTERED_MIPS,
+};
+use crate::examples::pbr::material::{
+    load_material,
+    Material,
+    MaterialFactors,
+    MaterialLayout,
+    TextureSlot,
+};
+use crate::examples::pbr::skybox::{
+    load_cube_faces,
+    Skybox,
+};
+
+// Where the directional light shines, the procedural sky puts its sun on the opposite side.
+const SUN_DIRECTION: Vector3<f32> = Vector3::new(-0.4, -1.0, -0.3);
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct EnvironmentUniform {
+    // 0 falls back to the flat ambient of `Lights`
+    intensity: f32,
+    prefiltered_mips: f32,
+    _padding: [f32; 2],
+}
+
+struct Object {
+    mesh: usize,
+    material: usize,
+    transform: Matrix4<f32>,
+}
+
+struct Application {
+    window_config: WindowConfig,
+    window: Option<ManagedWindow>,
+    input: Input,
+    camera: Camera,
+    camera_controller: CameraController,
+    camera_buffer: wgpu::Buffer,
+    environment_buffer: wgpu::Buffer,
+    environment_intensity: f32,
+    // camera and image based lighting, shared by every draw
+    frame_bind_group_layout: wgpu::BindGroupLayout,
+    frame_bind_group: wgpu::BindGroup,
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    // fill, wireframe and points, picked by `debug_view.raster_mode`
+    render_pipelines: Option<RasterPipelines>,
+    debug_view: Option<DebugView>,
+    tone_mapper: Option<ToneMapper>,
+    bloom: Option<Bloom>,
+    post_process: Option<PostProcessChain>,
+    // the environment's cubemap or six `--skybox` images
+    sky: texture::Texture,
+    skybox: Option<Skybox>,
+    shadow_debug: Option<ShadowDebugView>,
+    show_shadow_maps: bool,
+    depth_texture: Option<texture::Texture>,
+    // the scene is drawn into this and resolved to the tone mapper's target, `None` with 1x MSAA
+    msaa_target: Option<wgpu::TextureView>,
+    sample_count: u32,
+    material_layout: MaterialLayout,
+    meshes: Vec<Mesh>,
+    // same order as `meshes`
+    debug_meshes: Vec<DebugMesh>,
+    materials: Vec<Material>,
+    objects: Vec<Object>,
+    // index into `objects` of the small emissive sphere riding on the point light
+    lamp: usize,
+    lights: Lights,
+    models: Models,
+    time: f32,
+}
+
+impl Application {
+    fn init(&mut self, event_loop: &ActiveEventLoop) {
+        let window = Arc::new(event_loop.create_window(self.window_config.attributes(event_loop))
+            .expect("Failed to create window"));
+        let managed = ManagedWindow::new(
+            window,
+            &self.instance,
+            &self.adapter,
+            &self.device,
+            self.window_config.present_mode(),
+            None,
+        ).expect("Failed to create surface");
+        self.sample_count = self.window_config.sample_count(&self.adapter, &self.device, &[HDR_FORMAT, texture::Texture::DEPTH_FORMAT]);
+        self.create_pipeline(HDR_FORMAT);
+        let config = &managed.surface_config;
+        let tone_mapper = ToneMapper::new(&self.device, config.format, config.width, config.height);
+        self.bloom = Some(Bloom::new(&self.device, &tone_mapper.target));
+        self.tone_mapper = Some(tone_mapper);
+        self.post_process = Some(PostProcessChain::new(&self.device, &self.queue, config.format, config.width, config.height, &Effect::ALL));
+        self.depth_texture = Some(texture::Texture::create_depth_texture(&self.device, &managed.surface_config, self.sample_count, "depth_texture"));
+        self.msaa_target = WindowConfig::create_msaa_view(&self.device, &managed.surface_config, HDR_FORMAT, self.sample_count);
+        self.camera.aspect = managed.aspect();
+        self.window = Some(managed);
+    }
+
+    fn create_pipeline(&mut self, format: wgpu::TextureFormat) {
+        // SHADER, the shared light definitions go first
+        let shader = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("PBR Shader"),
+            source: wgpu::ShaderSource::Wgsl(concat!(
+                include_str!("../lighting/lights.wgsl"),
+                include_str!("../lighting/shadows.wgsl"),
+                include_str!("pbr.wgsl"),
+            ).into()),
+        });
+
+        // RENDER PIPELINE
+        let pipeline_layout = self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some("PBR Pipeline Layout"),
+            bind_group_layouts: &[
+                &self.frame_bind_group_layout,
+                &self.lights.bind_group_layout,
+                &self.material_layout.bind_group_layout,
+                &self.models.bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = wgpu::RenderPipelineDescriptor {
+            label: Some("PBR Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[
+                    Vertex::desc()
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                ..wgpu::PrimitiveState::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: texture::Texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: self.sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        };
+
+        self.render_pipelines = Some(RasterPipelines::new(&self.device, &render_pipeline));
+        self.debug_view = Some(DebugView::new(&self.device, &self.frame_bind_group_layout, &self.models, format, self.sample_count));
+        self.skybox = Some(Skybox::new(&self.device, format, self.sample_count, &self.sky));
+        self.shadow_debug = Some(ShadowDebugView::new(&self.device, &self.lights.shadows, format, self.sample_count));
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>) {
+        let Some(managed) = self.window.as_mut() else {
+            return;
+        };
+        if !managed.resize(&self.device, size) {
+            return;
+        }
+        self.depth_texture = Some(texture::Texture::create_depth_texture(&self.device, &managed.surface_config, self.sample_count, "depth_texture"));
+        self.msaa_target = WindowConfig::create_msaa_view(&self.device, &managed.surface_config, HDR_FORMAT, self.sample_count);
+        if let Some(tone_mapper) = self.tone_mapper.as_mut() {
+            tone_mapper.resize(&self.device, managed.surface_config.width, managed.surface_config.height);
+            if let Some(bloom) = self.bloom.as_mut() {
+                bloom.resize(&self.device, &tone_mapper.target);
+            }
+        }
+        if let Some(post_process) = self.post_process.as_mut() {
+            post_process.resize(&self.device, managed.surface_config.width, managed.surface_config.height);
+        }
+        self.camera.aspect = managed.aspect();
+        managed.window.request_redraw();
+    }
+
+    // Moves the point light and its lamp in a circle and uploads camera, lights and model matrices.
+    fn update(&mut self) {
+        self.time += self.input.dt();
+        let lamp_position = cgmath::Point3::new(3.0 * self.time.cos(), 1.5, 3.0 * self.time.sin());
+        if let Some(Light { kind: LightKind::Point { position, .. }, .. }) = self.lights.lights.get_mut(1) {
+            *position = lamp_position;
+        }
+        self.objects[self.lamp].transform = Matrix4::from_translation(lamp_position.to_vec())
+            * Matrix4::from_scale(0.15);
+        self.lights.update(&self.queue, &self.camera);
+        if let Some(skybox) = self.skybox.as_ref() {
+            skybox.update(&self.queue, &self.camera);
+        }
+        if let Some(tone_mapper) = self.tone_mapper.as_ref() {
+            tone_mapper.update(&self.queue);
+        }
+        if let Some(bloom) = self.bloom.as_ref() {
+            bloom.update(&self.queue);
+        }
+        if let Some(post_process) = self.post_process.as_ref() {
+            post_process.update(&self.queue);
+        }
+        self.queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[CameraUniform::new(&self.camera)]));
+        self.queue.write_buffer(&self.environment_buffer, 0, bytemuck::cast_slice(&[EnvironmentUniform {
+            intensity: self.environment_intensity,
+            prefiltered_mips: PREFILTERED_MIPS as f32,
+            _padding: [0.0; 2],
+        }]));
+        for (index, object) in self.objects.iter().enumerate() {
+            self.models.write(&self.queue, index, object.transform);
+        }
+    }
+
+    fn render(&self) {
+        let (Some(managed), Some(depth_texture), Some(tone_mapper), Some(post_process)) =
+            (self.window.as_ref(), self.depth_texture.as_ref(), self.tone_mapper.as_ref(), self.post_process.as_ref()) else {
+            return;
+        };
+        let Some(surface) = managed.surface.as_ref() else {
+            return;
+        };
+        let frame = surface.get_current_texture().unwrap();
+        let frame_view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {label: Some("Render Encoder")});
+        let size = (managed.surface_config.width, managed.surface_config.height);
+        match self.msaa_target.as_ref() {
+            Some(msaa) => self.render_scene(&mut encoder, msaa, Some(&tone_mapper.target.view), &depth_texture.view, size),
+            None => self.render_scene(&mut encoder, &tone_mapper.target.view, None, &depth_texture.view, size),
+        }
+        if let Some(bloom) = self.bloom.as_ref() {
+            bloom.render(&mut encoder, &tone_mapper.target.view);
+        }
+        tone_mapper.render(&mut encoder, post_process.input(&frame_view));
+        post_process.render(&mut encoder, &frame_view);
+
+        let command_buffer = encoder.finish();
+        self.queue.submit(Some(command_buffer));
+        frame.present();
+    }
+
+    // Renders the shadow maps, then draws every object and the sky behind them into `color_view`,
+    // an `HDR_FORMAT` target of `size`. A multisampled `color_view` is resolved into `resolve_target`.
+    fn render_scene(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        color_view: &wgpu::TextureView,
+        resolve_target: Option<&wgpu::TextureView>,
+        depth_view: &wgpu::TextureView,
+        size: (u32, u32),
+    ) {
+        let draws: Vec<_> = self.objects.iter()
+            .enumerate()
+            .map(|(index, object)| (&self.meshes[object.mesh], index))
+            .collect();
+        self.lights.shadows.render(encoder, &self.models, &draws);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: color_view,
+                resolve_target,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.0,
+                        g: 0.0,
+                        b: 0.0,
+                        a: 1.0 }),
+                    store: wgpu::StoreOp::Store,
+                }
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        let raster_mode = self.debug_view.as_ref().map_or(RasterMode::Fill, |debug_view| debug_view.raster_mode);
+        // no pipeline for a wireframe without `POLYGON_MODE_LINE`, the debug view draws it
+        if let Some(render_pipeline) = self.render_pipelines.as_ref().and_then(|pipelines| pipelines.get(raster_mode)) {
+            render_pass
+                .set_pipeline(
+                    render_pipeline);
+            render_pass
+                .set_bind_group(
+                    0,
+                    &self.frame_bind_group,
+                    &[]);
+            render_pass
+                .set_bind_group(
+                    1,
+                    &self.lights.bind_group,
+                    &[]);
+            for (index, object) in self.objects.iter().enumerate() {
+                let mesh = &self.meshes[object.mesh];
+                render_pass
+                    .set_bind_group(
+                        2,
+                        &self.materials[object.material].bind_group,
+                        &[]);
+                render_pass
+                    .set_bind_group(
+                        3,
+                        &self.models.bind_group,
+                        &[self.models.offset(index)]);
+                render_pass
+                    .set_vertex_buffer(
+                        0,
+                        mesh.vertex_buffer.slice(..));
+                render_pass
+                    .set_index_buffer(
+                        mesh.index_buffer.slice(..),
+                        wgpu::IndexFormat::Uint32);
+                render_pass
+                    .draw_indexed(
+                        0..mesh.index_count,
+                        0,
+                        0..1);
+            }
+        }
+        if let Some(debug_view) = self.debug_view.as_ref() {
+            let debug_draws: Vec<_> = self.objects.iter()
+                .enumerate()
+                .map(|(index, object)| (&self.debug_meshes[object.mesh], index))
+                .collect();
+            debug_view.render(&mut render_pass, &self.frame_bind_group, &self.models, &debug_draws);
+        }
+        if let Some(skybox) = self.skybox.as_ref() {
+            skybox.render(&mut render_pass);
+        }
+        if self.show_shadow_maps && let Some(shadow_debug) = self.shadow_debug.as_ref() {
+            shadow_debug.render(&mut render_pass, &self.lights.shadows, size);
+        }
+    }
+}
+impl ApplicationHandler for Application {
+    fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
+        let _ = (event_loop, cause);
+    }
+
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(managed) = self.window.as_mut() else {
+            self.init(event_loop);
+            return;
+        };
+        if let Err(error) = managed.resume(&self.instance, &self.device) {
+            eprintln!("{error:#}");
+            event_loop.exit();
+            return;
+        }
+        let size = managed.window.inner_size();
+        self.resize(size);
+    }
+
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, _event: ()) {}
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _window_id: WindowId, event: WindowEvent) {
+        self.input.handle_window_event(&event);
+        match event {
+            WindowEvent::CloseRequested => {
+                if let Some(managed) = self.window.as_ref() {
+                    self.window_config.store(&managed.window);
+                }
+                event_loop.exit();
+            }
+            WindowEvent::RedrawRequested => { self.render(); }
+            WindowEvent::Resized(size) => { self.resize(size); }
+            _ => {}
+        }
+    }
+
+    fn device_event(&mut self, _event_loop: &ActiveEventLoop, _device_id: DeviceId, event: DeviceEvent) {
+        self.input.handle_device_event(&event);
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.input.begin_frame();
+        let Some(window) = self.window.as_ref().map(|managed| managed.window.clone()) else {
+            self.input.end_frame();
+            return;
+        };
+        if self.window_config.handle_input(&window, &self.input) {
+            self.resize(window.inner_size());
+        }
+        if self.input.pressed(Action::CameraOrbit) {
+            window.set_cursor_visible(false);
+        }
+        if self.input.released(Action::CameraOrbit) {
+            window.set_cursor_visible(true);
+        }
+        self.camera_controller.update(&mut self.camera, &self.input);
+        if self.input.pressed(Action::ToggleEnvironment) {
+            self.environment_intensity = if self.environment_intensity > 0.0 { 0.0 } else { 1.0 };
+            println!("Image based lighting {}", if self.environment_intensity > 0.0 { "on" } else { "off" });
+        }
+        if self.input.pressed(Action::ToggleShadowDebug) {
+            self.show_shadow_maps = !self.show_shadow_maps;
+        }
+        if let Some(debug_view) = self.debug_view.as_mut() {
+            debug_view.handle_input(&self.input);
+        }
+        if let Some(tone_mapper) = self.tone_mapper.as_mut() {
+            tone_mapper.handle_input(&self.input);
+        }
+        if let Some(bloom) = self.bloom.as_mut() {
+            bloom.handle_input(&self.input);
+        }
+        if let Some(post_process) = self.post_process.as_mut() {
+            post_process.handle_input(&self.input);
+        }
+
+        // The lights are animated, redraw continuously.
+        self.update();
+        window.request_redraw();
+        event_loop.set_control_flow(ControlFlow::Poll);
+        self.input.end_frame();
+    }
+
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(managed) = self.window.as_mut() {
+            managed.suspend();
+        }
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {}
+
+    fn memory_warning(&mut self, _event_loop: &ActiveEventLoop) {}
+}
+
+// Normal and metallic-roughness maps for square tiles with beveled edges and rough grout between them.
+fn tile_images(size: u32) -> (image::RgbaImage, image::RgbaImage) {
+    let grout = size as f32 * 0.06;
+    let bevel = size as f32 * 0.1;
+    let height = |x: f32, y: f32| {
+        let edge = x.min(y).min(size as f32 - x).min(size as f32 - y);
+        ((edge - grout) / bevel).clamp(0.0, 1.0)
+    };
+    let normal = image::RgbaImage::from_fn(size, size, |x, y| {
+        let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+        let dx = height(x + 1.0, y) - height(x - 1.0, y);
+        // image rows grow downwards, tangent space +Y points up
+        let dy = height(x, y - 1.0) - height(x, y + 1.0);
+        let normal = cgmath::InnerSpace::normalize(Vector3::new(-dx, -dy, 0.5));
+        let encode = |value: f32| ((value * 0.5 + 0.5) * 255.0).round() as u8;
+        image::Rgba([encode(normal.x), encode(normal.y), encode(normal.z), 255])
+    });
+    let metallic_roughness = image::RgbaImage::from_fn(size, size, |x, y| {
+        let tile = height(x as f32 + 0.5, y as f32 + 0.5) > 0.0;
+        image::Rgba([0, if tile { 60 } else { 230 }, 0, 255])
+    });
+    (normal, metallic_roughness)
+}
+
+// Sun, a point light circling the scene and a spot light from above.
+fn default_lights(lights: &mut Lights) {
+    lights.lights = vec![
+        Light {
+            kind: LightKind::Directional { direction: SUN_DIRECTION },
+            color: [1.0, 0.95, 0.85],
+            intensity: 2.0,
+        },
+        Light {
+            kind: LightKind::Point { position: (3.0, 1.5, 0.0).into(), range: 10.0 },
+            color: [1.0, 0.6, 0.3],
+            intensity: 6.0,
+        },
+        Light {
+            kind: LightKind::Spot {
+                position: (0.0, 5.0, 0.0).into(),
+                direction: -Vector3::unit_y(),
+                range: 12.0,
+                inner: Deg(15.0),
+                outer: Deg(25.0),
+            },
+            color: [0.4, 0.7, 1.0],
+            intensity: 40.0,
+        },
+    ];
+}
+
+// Falls back to the procedural sky when the map cannot be loaded.
+fn load_environment(device: &wgpu::Device, queue: &wgpu::Queue, path: &Path) -> Option<Environment> {
+    match Environment::load(device, queue, path) {
+        Ok(environment) => {
+            println!("Loaded environment {}", path.display());
+            Some(environment)
+        }
+        Err(error) => {
+            eprintln!("{error:#}");
+            None
+        }
+    }
+}
+
+// `material` is a directory of textures shown on the center sphere and cube, see `load_material`,
+// `environment` an equirectangular `.hdr` used for image based lighting and as the sky
+// unless `skybox` is a directory of six face images, see `load_cube_faces`.
+fn new_application(
+    window_config: WindowConfig,
+    input: Input,
+    material: Option<PathBuf>,
+    environment: Option<PathBuf>,
+    skybox: Option<PathBuf>,
+) -> Application {
+    // wgpu
+    let instance= wgpu::Instance::new(&wgpu::InstanceDescriptor { backends: wgpu::Backends::all(), ..Default::default()});
+    let adapter= task::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())).unwrap();
+    let (device, queue)= task::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+        // `PolygonMode::Line` for the debug view's wireframes
+        required_features: WindowConfig::msaa_features(&adapter)
+            | (adapter.features() & wgpu::Features::POLYGON_MODE_LINE),
+        ..Default::default()
+    }, None, )).unwrap();
+
+    // CAMERA
+    let settings = &window_config.settings;
+    let camera = Camera {
+        eye: (0.0, 3.0, 7.0).into(),
+        target: (0.0, 0.5, 0.0).into(),
+        up: Vector3::unit_y(),
+        aspect: settings.width as f32 / settings.height as f32,
+        fovy: 45.0,
+        znear: 0.1,
+        zfar: 100.0
+    };
+    let camera_buffer = device.create_buffer_init(&BufferInitDescriptor {
+        label: Some("Camera Buffer"),
+        contents: bytemuck::cast_slice(&[CameraUniform::new(&camera)]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+    // ENVIRONMENT
+    let environment = environment
+        .and_then(|path| load_environment(&device, &queue, &path))
+        .unwrap_or_else(|| Environment::procedural_sky(&device, &queue, -SUN_DIRECTION));
+    let environment_buffer = device.create_buffer_init(&BufferInitDescriptor {
+        label: Some("Environment Buffer"),
+        contents: bytemuck::cast_slice(&[EnvironmentUniform {
+            intensity: 1.0,
+            prefiltered_mips: PREFILTERED_MIPS as f32,
+            _padding: [0.0; 2],
+        }]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+    let cube_entry = |binding| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::Cube,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+        },
+        count: None,
+    };
+    let frame_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None
+                },
+                count: None
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None
+                },
+                count: None
+            },
+            cube_entry(2),
+            cube_entry(3),
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 5,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+        label: Some("frame_bind_group_layout"),
+    });
+    let frame_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &frame_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding()
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: environment_buffer.as_entire_binding()
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(&environment.irradiance.view)
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::TextureView(&environment.prefiltered.view)
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: wgpu::BindingResource::TextureView(&environment.brdf_lut.view)
+            },
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: wgpu::BindingResource::Sampler(&environment.prefiltered.sampler)
+            },
+        ],
+        label: Some("frame_bind_group"),
+    });
+    let sky = skybox
+        .and_then(|dir| match load_cube_faces(&device, &queue, &dir) {
+            Ok(sky) => Some(sky),
+            Err(error) => {
+                eprintln!("{error:#}");
+                None
+            }
+        })
+        .unwrap_or(environment.cubemap);
+
+    // MATERIALS
+    let material_layout = MaterialLayout::new(&device, &queue);
+    let uv_grid = texture::Texture::from_bytes(
+        &device,
+        &queue,
+        include_bytes!("../perspective_camera/UV_Grid.png"),
+        "uv_grid"
+    ).unwrap();
+    let (tile_normal, tile_metallic_roughness) = tile_images(128);
+    let tile_normal = texture::Texture::from_image(
+        &device, &queue, &tile_normal.into(), Some("tile_normal"), TextureSlot::Normal.format()).unwrap();
+    let tile_metallic_roughness = texture::Texture::from_image(
+        &device, &queue, &tile_metallic_roughness.into(), Some("tile_metallic_roughness"), TextureSlot::MetallicRoughness.format()).unwrap();
+    let mut materials = vec![
+        Material::new(&device, &material_layout, "Tiles", MaterialFactors {
+            base_color: [0.6, 0.6, 0.65, 1.0],
+            metallic: 0.0,
+            ..MaterialFactors::default()
+        }, &[
+            (TextureSlot::Normal, &tile_normal),
+            (TextureSlot::MetallicRoughness, &tile_metallic_roughness),
+        ]),
+        Material::new(&device, &material_layout, "UV Grid", MaterialFactors {
+            metallic: 0.0,
+            roughness: 0.4,
+            ..MaterialFactors::default()
+        }, &[(TextureSlot::BaseColor, &uv_grid)]),
+        Material::new(&device, &material_layout, "Lamp", MaterialFactors {
+            base_color: [0.0, 0.0, 0.0, 1.0],
+            emissive: [1.0, 0.6, 0.3],
+            ..MaterialFactors::default()
+        }, &[]),
+    ];
+    if let Some(dir) = material {
+        match load_material(&device, &queue, &material_layout, &dir) {
+            Ok(loaded) => {
+                println!("Loaded material {}", loaded.name);
+                materials[1] = loaded;
+            }
+            Err(error) => eprintln!("{error:#}"),
+        }
+    }
+    // Roughness grows to the right, the back row is metal.
+    let swatches = materials.len();
+    for metallic in [0.0, 1.0] {
+        for step in 0..5 {
+            let roughness = 0.1 + step as f32 * 0.225;
+            let base_color = if metallic > 0.0 { [1.0, 0.78, 0.34, 1.0] } else { [0.8, 0.1, 0.1, 1.0] };
+            materials.push(Material::new(&device, &material_layout, &format!("Swatch {metallic} {roughness}"), MaterialFactors {
+                base_color,
+                metallic,
+                roughness,
+                ..MaterialFactors::default()
+            }, &[]));
+        }
+    }
+
+    // SCENE
+    let mesh_data = [
+        ("Plane", MeshData::plane(12.0, 6.0)),
+        ("Cube", MeshData::cube()),
+        ("Sphere", MeshData::sphere(48, 24)),
+    ];
+    let meshes = mesh_data.iter().map(|(label, data)| Mesh::new(&device, label, data)).collect();
+    let debug_meshes = mesh_data.iter().map(|(label, data)| DebugMesh::new(&device, label, data)).collect();
+    let mut objects = vec![
+        Object { mesh: 0, material: 0, transform: Matrix4::identity() },
+        Object { mesh: 2, material: 1, transform: Matrix4::from_translation((0.0, 1.0, 0.0).into()) * Matrix4::from_scale(2.0) },
+        Object {
+            mesh: 1,
+            material: 1,
+            transform: Matrix4::from_translation((-2.5, 0.5, 0.5).into()) * Matrix4::from_angle_y(Deg(30.0)),
+        },
+    ];
+    for (index, material) in (swatches..materials.len()).enumerate() {
+        let (row, column) = (index / 5, index % 5);
+        objects.push(Object {
+            mesh: 2,
+            material,
+            transform: Matrix4::from_translation((column as f32 - 2.0, 0.35, 2.5 - row as f32 * 5.0).into())
+                * Matrix4::from_scale(0.7),
+        });
+    }
+    let lamp = objects.len();
+    objects.push(Object { mesh: 2, material: 2, transform: Matrix4::identity() });
+    let models = Models::new(&device, objects.len() as u32);
+    let mut lights = Lights::new(&device, &models.bind_group_layout);
+    default_lights(&mut lights);
+
+    Application{
+        window_config,
+        window: None,
+        input,
+        camera,
+        camera_controller: CameraController::new(4.0, 1.5, 0.005),
+        camera_buffer,
+        environment_buffer,
+        environment_intensity: 1.0,
+        frame_bind_group_layout,
+        frame_bind_group,
+        instance,
+        adapter,
+        device,
+        queue,
+        render_pipelines: None,
+        debug_view: None,
+        tone_mapper: None,
+        bloom: None,
+        post_process: None,
+        sky,
+        skybox: None,
+        shadow_debug: None,
+        show_shadow_maps: false,
+        depth_texture: None,
+        msaa_target: None,
+        sample_count: 1,
+        material_layout,
+        meshes,
+        debug_meshes,
+        materials,
+        objects,
+        lamp,
+        lights,
+        models,
+        time: 0.0,
+    }
+}
+
+pub fn run(
+    window_config: WindowConfig,
+    input: Input,
+    material: Option<PathBuf>,
+    environment: Option<PathBuf>,
+    skybox: Option<PathBuf>,
+) {
+    // winit
+    let event_loop = EventLoop::new().unwrap();
+
+    let mut application = new_application(window_config, input, material, environment, skybox);
+
+    event_loop.run_app(&mut application).expect("Failed to start event_loop");
+    println!("pbr example");
+}
@@ -0,0 +1,200 @@
+// VERTEX SHADER, lighting/lights.wgsl and lighting/shadows.wgsl are prepended
+
+const PI: f32 = 3.14159265359;
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    view_position: vec4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+// image based lighting, see environment.rs
+struct EnvironmentUniform {
+    intensity: f32,
+    prefiltered_mips: f32,
+};
+@group(0) @binding(1)
+var<uniform> environment: EnvironmentUniform;
+@group(0) @binding(2)
+var t_irradiance: texture_cube<f32>;
+@group(0) @binding(3)
+var t_prefiltered: texture_cube<f32>;
+@group(0) @binding(4)
+var t_brdf_lut: texture_2d<f32>;
+@group(0) @binding(5)
+var s_environment: sampler;
+
+@group(1) @binding(0)
+var<uniform> lights: Lights;
+
+struct ModelUniform {
+    model: mat4x4<f32>,
+    normal: mat3x3<f32>,
+};
+@group(3) @binding(0) // dynamic offset selects the object
+var<uniform> model: ModelUniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) tex_coords: vec2<f32>,
+    @location(3) tangent: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) world_tangent: vec4<f32>,
+    @location(3) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    vertex: VertexInput,
+) -> VertexOutput {
+    let world_position = model.model * vec4<f32>(vertex.position, 1.0);
+    var out: VertexOutput;
+    out.world_position = world_position.xyz;
+    out.world_normal = model.normal * vertex.normal;
+    // tangents follow the surface, they are transformed like positions
+    out.world_tangent = vec4<f32>((model.model * vec4<f32>(vertex.tangent.xyz, 0.0)).xyz, vertex.tangent.w);
+    out.tex_coords = vertex.tex_coords;
+    out.clip_position = camera.view_proj * world_position;
+    return out;
+}
+
+// FRAGMENT SHADER
+
+// glTF metallic-roughness, bindings generated from `TextureSlot::ALL`
+struct MaterialUniform {
+    base_color: vec4<f32>,
+    emissive: vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+    normal_scale: f32,
+    occlusion_strength: f32,
+};
+@group(2) @binding(0)
+var t_base_color: texture_2d<f32>;
+@group(2) @binding(1)
+var t_metallic_roughness: texture_2d<f32>;
+@group(2) @binding(2)
+var t_normal: texture_2d<f32>;
+@group(2) @binding(3)
+var t_occlusion: texture_2d<f32>;
+@group(2) @binding(4)
+var t_emissive: texture_2d<f32>;
+@group(2) @binding(5)
+var s_material: sampler;
+@group(2) @binding(6)
+var<uniform> material: MaterialUniform;
+
+// Trowbridge-Reitz GGX normal distribution, `alpha` is roughness squared.
+fn distribution_ggx(n_dot_h: f32, alpha: f32) -> f32 {
+    let alpha2 = alpha * alpha;
+    let d = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    return alpha2 / (PI * d * d);
+}
+
+// Smith height-correlated visibility, includes the 1 / (4 n.l n.v) of Cook-Torrance.
+fn visibility_smith_ggx(n_dot_v: f32, n_dot_l: f32, alpha: f32) -> f32 {
+    let alpha2 = alpha * alpha;
+    let ggx_v = n_dot_l * sqrt(n_dot_v * n_dot_v * (1.0 - alpha2) + alpha2);
+    let ggx_l = n_dot_v * sqrt(n_dot_l * n_dot_l * (1.0 - alpha2) + alpha2);
+    return 0.5 / max(ggx_v + ggx_l, 0.0001);
+}
+
+fn fresnel_schlick(cos_theta: f32, f0: vec3<f32>) -> vec3<f32> {
+    return f0 + (1.0 - f0) * pow(1.0 - cos_theta, 5.0);
+}
+
+struct Surface {
+    base_color: vec4<f32>,
+    metallic: f32,
+    roughness: f32,
+    normal: vec3<f32>,
+    occlusion: f32,
+    emissive: vec3<f32>,
+};
+
+fn sample_surface(in: VertexOutput, front_facing: bool) -> Surface {
+    var surface: Surface;
+    surface.base_color = textureSample(t_base_color, s_material, in.tex_coords) * material.base_color;
+    let metallic_roughness = textureSample(t_metallic_roughness, s_material, in.tex_coords);
+    surface.metallic = saturate(material.metallic * metallic_roughness.b);
+    // perfectly smooth surfaces turn point lights into invisible specks
+    surface.roughness = clamp(material.roughness * metallic_roughness.g, 0.045, 1.0);
+    let occlusion = textureSample(t_occlusion, s_material, in.tex_coords).r;
+    surface.occlusion = 1.0 + material.occlusion_strength * (occlusion - 1.0);
+    surface.emissive = textureSample(t_emissive, s_material, in.tex_coords).rgb * material.emissive;
+
+    var normal = normalize(in.world_normal);
+    if !front_facing {
+        normal = -normal;
+    }
+    let tangent = normalize(in.world_tangent.xyz - normal * dot(normal, in.world_tangent.xyz));
+    let bitangent = cross(normal, tangent) * in.world_tangent.w;
+    var tangent_normal = textureSample(t_normal, s_material, in.tex_coords).xyz * 2.0 - 1.0;
+    tangent_normal = vec3<f32>(tangent_normal.xy * material.normal_scale, tangent_normal.z);
+    surface.normal = normalize(mat3x3<f32>(tangent, bitangent, normal) * tangent_normal);
+    return surface;
+}
+
+// Cook-Torrance specular plus Lambert diffuse for a single light.
+fn shade(surface: Surface, view_direction: vec3<f32>, light_direction: vec3<f32>, radiance: vec3<f32>) -> vec3<f32> {
+    let n_dot_l = dot(surface.normal, light_direction);
+    if n_dot_l <= 0.0 {
+        return vec3<f32>(0.0);
+    }
+    let half_direction = normalize(light_direction + view_direction);
+    let n_dot_v = max(dot(surface.normal, view_direction), 0.0001);
+    let n_dot_h = max(dot(surface.normal, half_direction), 0.0);
+    let v_dot_h = max(dot(view_direction, half_direction), 0.0);
+    let alpha = surface.roughness * surface.roughness;
+
+    let f0 = mix(vec3<f32>(0.04), surface.base_color.rgb, surface.metallic);
+    let fresnel = fresnel_schlick(v_dot_h, f0);
+    let specular = fresnel * distribution_ggx(n_dot_h, alpha) * visibility_smith_ggx(n_dot_v, n_dot_l, alpha);
+    let diffuse = (1.0 - fresnel) * (1.0 - surface.metallic) * surface.base_color.rgb / PI;
+    return (diffuse + specular) * radiance * n_dot_l;
+}
+
+// Split sum approximation: diffuse from the irradiance map, specular from the prefiltered mip
+// matching the roughness, scaled and biased by the BRDF lookup table.
+fn ambient(surface: Surface, view_direction: vec3<f32>) -> vec3<f32> {
+    if environment.intensity <= 0.0 {
+        return lights.ambient * surface.base_color.rgb * surface.occlusion;
+    }
+    let n_dot_v = max(dot(surface.normal, view_direction), 0.0001);
+    let f0 = mix(vec3<f32>(0.04), surface.base_color.rgb, surface.metallic);
+    // Schlick with roughness, rough surfaces reflect less at grazing angles
+    let fresnel = f0 + (max(vec3<f32>(1.0 - surface.roughness), f0) - f0) * pow(1.0 - n_dot_v, 5.0);
+
+    let irradiance = textureSample(t_irradiance, s_environment, surface.normal).rgb;
+    let diffuse = (1.0 - fresnel) * (1.0 - surface.metallic) * surface.base_color.rgb * irradiance;
+
+    let reflection = reflect(-view_direction, surface.normal);
+    let level = surface.roughness * (environment.prefiltered_mips - 1.0);
+    let prefiltered = textureSampleLevel(t_prefiltered, s_environment, reflection, level).rgb;
+    let brdf = textureSample(t_brdf_lut, s_environment, vec2<f32>(n_dot_v, 1.0 - surface.roughness)).rg;
+    let specular = prefiltered * (f0 * brdf.x + brdf.y);
+
+    return (diffuse + specular) * surface.occlusion * environment.intensity;
+}
+
+@fragment
+fn fs_main(in: VertexOutput, @builtin(front_facing) front_facing: bool) -> @location(0) vec4<f32> {
+    let surface = sample_surface(in, front_facing);
+    let view_direction = normalize(camera.view_position.xyz - in.world_position);
+
+    var color = ambient(surface, view_direction);
+    for (var i = 0u; i < min(lights.count, MAX_LIGHTS); i++) {
+        let light = sample_light(lights.lights[i], in.world_position);
+        let shadow = sample_shadow(lights.lights[i], in.world_position, surface.normal);
+        color += shade(surface, view_direction, light.direction, light.radiance * shadow);
+    }
+    color += surface.emissive;
+    return vec4<f32>(color, surface.base_color.a);
+}
@@ -0,0 +1,160 @@
+use std::{
+    fs,
+    path::Path,
+};
+use anyhow::{
+    Context,
+    Result,
+};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use crate::examples::perspective_camera::camera::Camera;
+use crate::examples::perspective_camera::texture::Texture;
+
+// File name stems of the faces in `Texture::create_cube` order.
+const FACE_NAMES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct SkyboxUniform {
+    inverse_view_proj: [[f32; 4]; 4],
+}
+
+// Draws a cubemap behind the scene. Rendered after the opaque geometry at depth 1,
+// so it only covers pixels nothing else was drawn to.
+pub struct Skybox {
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    render_pipeline: wgpu::RenderPipeline,
+}
+impl Skybox {
+    // `format` and `sample_count` match the pass it is drawn in.
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, sample_count: u32, cubemap: &Texture) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Skybox Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("skybox.wgsl").into()),
+        });
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Skybox Buffer"),
+            contents: bytemuck::cast_slice(&[SkyboxUniform { inverse_view_proj: [[0.0; 4]; 4] }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::Cube,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("skybox_bind_group_layout"),
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding()
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&cubemap.view)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&cubemap.sampler)
+                },
+            ],
+            label: Some("skybox_bind_group"),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some("Skybox Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Skybox Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            // depth 1 passes `LessEqual` only where the depth buffer still holds its clear value
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        Self { buffer, bind_group, render_pipeline }
+    }
+
+    pub fn update(&self, queue: &wgpu::Queue, camera: &Camera) {
+        let uniform = SkyboxUniform { inverse_view_proj: camera.build_inverse_view_rotation_projection_matrix().into() };
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    // Call last in a pass that already drew the scene into its depth attachment.
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+// Loads `px`, `nx`, `py`, `ny`, `pz` and `nz` (`.png` or `.jpg`) from `dir` into a cubemap.
+pub fn load_cube_faces(device: &wgpu::Device, queue: &wgpu::Queue, dir: &Path) -> Result<Texture> {
+    let mut faces = Vec::new();
+    for name in FACE_NAMES {
+        let path = ["png", "jpg"].iter()
+            .map(|extension| dir.join(name).with_extension(extension))
+            .find(|path| path.is_file())
+            .with_context(|| format!("No {name}.png or {name}.jpg in {}", dir.display()))?;
+        let bytes = fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        faces.push(image::load_from_memory(&bytes).with_context(|| format!("Failed to load {}", path.display()))?);
+    }
+    let faces: [image::DynamicImage; 6] = faces.try_into().unwrap();
+    Texture::from_cube_images(device, queue, &faces, "skybox")
+}
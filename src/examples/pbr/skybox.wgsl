@@ -0,0 +1,33 @@
+// Full-screen triangle at the far plane, each pixel looks up the cubemap along its view direction.
+
+struct SkyboxUniform {
+    // clip space to world space directions, without the camera's translation
+    inverse_view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> skybox: SkyboxUniform;
+@group(0) @binding(1)
+var t_sky: texture_cube<f32>;
+@group(0) @binding(2)
+var s_sky: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) direction: vec3<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let position = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+    var out: VertexOutput;
+    // z = w puts the sky at depth 1, behind everything drawn before
+    out.clip_position = vec4<f32>(position, 1.0, 1.0);
+    // w is positive and only scales the direction, leaving it out keeps the interpolation linear
+    out.direction = (skybox.inverse_view_proj * vec4<f32>(position, 1.0, 1.0)).xyz;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(textureSample(t_sky, s_sky, normalize(in.direction)).rgb, 1.0);
+}
@@ -1,9 +1,21 @@
+use cgmath::{
+    InnerSpace,
+    SquareMatrix,
+};
+use crate::examples::perspective_camera::picking::Ray;
+use crate::input::{
+    Action,
+    Input,
+};
+
+// Maps OpenGL's -1..1 clip space depth to wgpu's 0..1: z' = 0.5 * z + 0.5 * w.
+// cgmath matrices are column major, so the 0.5 offset lives in the last column.
 #[rustfmt::skip]
 pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
     1.0, 0.0, 0.0, 0.0,
     0.0, 1.0, 0.0, 0.0,
-    0.0, 0.0, 0.5, 0.5,
-    0.0, 0.0, 0.0, 1.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
 );
 pub struct Camera {
     pub eye: cgmath::Point3<f32>,
@@ -19,6 +31,121 @@ impl Camera {
         let view = cgmath::Matrix4::look_at_rh(self.eye, self.target, self.up);
         let proj = cgmath::perspective(cgmath::Deg(self.fovy), self.aspect, self.znear, self.zfar);
 
-        return OPENGL_TO_WGPU_MATRIX * proj * view;
+        OPENGL_TO_WGPU_MATRIX * proj * view
+    }
+
+    // Inverse of the view projection with the eye moved to the origin, turns clip space positions
+    // into view directions. Translation would move the sky with the camera, so it is left out.
+    pub fn build_inverse_view_rotation_projection_matrix(&self) -> cgmath::Matrix4<f32> {
+        let origin = cgmath::Point3::new(0.0, 0.0, 0.0);
+        let view = cgmath::Matrix4::look_at_rh(origin, origin + (self.target - self.eye), self.up);
+        let proj = cgmath::perspective(cgmath::Deg(self.fovy), self.aspect, self.znear, self.zfar);
+
+        (OPENGL_TO_WGPU_MATRIX * proj * view).invert()
+            .expect("view projection matrix is not invertible")
+    }
+
+    // World space ray through a cursor position given in physical pixels, (0, 0) is the top left corner.
+    // Un-projects the cursor at wgpu's near (z = 0) and far (z = 1) clip planes, the inverse of
+    // `build_view_projection_matrix` already undoes `OPENGL_TO_WGPU_MATRIX`.
+    pub fn screen_ray(&self, cursor: (f32, f32), screen: (f32, f32)) -> Ray {
+        let ndc_x = 2.0 * cursor.0 / screen.0 - 1.0;
+        let ndc_y = 1.0 - 2.0 * cursor.1 / screen.1;
+        let inverse = self.build_view_projection_matrix().invert()
+            .expect("view projection matrix is not invertible");
+        let unproject = |z: f32| {
+            let point = inverse * cgmath::Vector4::new(ndc_x, ndc_y, z, 1.0);
+            cgmath::Point3::from_homogeneous(point)
+        };
+        let near = unproject(0.0);
+        let far = unproject(1.0);
+        Ray::new(near, far - near)
+    }
+}
+// Orbits the eye around `target`: WASD/arrows rotate and dolly, Space/Shift move up and down,
+// dragging with the orbit button rotates by raw mouse motion and the wheel zooms.
+pub struct CameraController {
+    pub speed: f32,
+    pub rotate_speed: f32,
+    pub sensitivity: f32,
+}
+impl CameraController {
+    pub fn new(speed: f32, rotate_speed: f32, sensitivity: f32) -> Self {
+        Self { speed, rotate_speed, sensitivity }
+    }
+
+    // Returns true when the camera moved.
+    pub fn update(&self, camera: &mut Camera, input: &Input) -> bool {
+        let dt = input.dt();
+        let held = |action: Action| if input.held(action) { 1.0 } else { 0.0 };
+        let axis = |positive: Action, negative: Action| held(positive) - held(negative);
+        let mut yaw = axis(Action::CameraRight, Action::CameraLeft) * self.rotate_speed * dt;
+        let mut pitch = axis(Action::CameraUp, Action::CameraDown) * self.rotate_speed * dt;
+        let dolly = axis(Action::CameraForward, Action::CameraBackward) * self.speed * dt + input.scroll() * self.speed * 0.1;
+        if input.held(Action::CameraOrbit) {
+            let (dx, dy) = input.mouse_motion();
+            yaw -= dx as f32 * self.sensitivity;
+            pitch += dy as f32 * self.sensitivity;
+        }
+        if yaw == 0.0 && pitch == 0.0 && dolly == 0.0 {
+            return false;
+        }
+
+        let offset = camera.eye - camera.target;
+        let distance = (offset.magnitude() - dolly).max(camera.znear * 2.0);
+        let yaw = offset.x.atan2(offset.z) + yaw;
+        let limit = std::f32::consts::FRAC_PI_2 - 0.01;
+        let pitch = ((offset.y / offset.magnitude()).asin() + pitch).clamp(-limit, limit);
+        camera.eye = camera.target + cgmath::Vector3::new(
+            pitch.cos() * yaw.sin(),
+            pitch.sin(),
+            pitch.cos() * yaw.cos(),
+        ) * distance;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{
+        EuclideanSpace,
+        Point3,
+        Transform,
+    };
+
+    fn camera() -> Camera {
+        Camera {
+            eye: (0.0, 1.0, 2.0).into(),
+            target: (0.0, 0.0, 0.0).into(),
+            up: cgmath::Vector3::unit_y(),
+            aspect: 1.5,
+            fovy: 45.0,
+            znear: 0.1,
+            zfar: 100.0
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn center_ray_points_at_target() {
+        let camera = camera();
+        let ray = camera.screen_ray((400.0, 300.0), (800.0, 600.0));
+        let expected = (camera.target - camera.eye).normalize();
+        assert!((ray.direction - expected).magnitude() < 1e-4);
+        // The ray starts on the near plane in front of the eye.
+        assert!(((ray.origin - camera.eye).magnitude() - camera.znear).abs() < 1e-3);
+    }
+
+    #[test]
+    fn ray_passes_through_projected_point() {
+        let camera = camera();
+        let point = Point3::new(0.3, -0.2, 0.1);
+        let clip = camera.build_view_projection_matrix().transform_point(point);
+        let cursor = ((clip.x + 1.0) * 0.5 * 800.0, (1.0 - clip.y) * 0.5 * 600.0);
+        let ray = camera.screen_ray(cursor, (800.0, 600.0));
+        let to_point = point - ray.origin;
+        let distance = (to_point - ray.direction * to_point.dot(ray.direction)).magnitude();
+        assert!(distance < 1e-4, "ray misses the point by {distance}");
+        assert!(point.to_vec().dot(ray.direction) > ray.origin.to_vec().dot(ray.direction));
+    }
+}
@@ -0,0 +1,168 @@
+use std::fmt;
+use cgmath::{
+    EuclideanSpace,
+    InnerSpace,
+    Matrix,
+    Matrix4,
+    Point3,
+    Vector3,
+    Vector4,
+};
+use crate::examples::perspective_camera::picking::Aabb;
+
+// Points with `normal · p + distance >= 0` are on the inside.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Plane {
+    pub normal: Vector3<f32>,
+    pub distance: f32,
+}
+impl Plane {
+    // From the coefficients of `ax + by + cz + d = 0`, scaled so `distance_to` is in world units.
+    fn from_coefficients(coefficients: Vector4<f32>) -> Self {
+        let length = coefficients.truncate().magnitude();
+        Self {
+            normal: coefficients.truncate() / length,
+            distance: coefficients.w / length,
+        }
+    }
+
+    pub fn distance_to(&self, point: Point3<f32>) -> f32 {
+        self.normal.dot(point.to_vec()) + self.distance
+    }
+}
+
+// The volume a camera sees, bounded by six inward facing planes: left, right, bottom, top, near, far.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    pub planes: [Plane; 6],
+}
+impl Frustum {
+    // Gribb/Hartmann: a point is inside when -w <= x, y <= w and 0 <= z <= w in clip space, every
+    // inequality is a plane made of the matrix rows. Takes wgpu's 0..1 depth range, as produced by
+    // `Camera::build_view_projection_matrix`.
+    pub fn from_view_projection(view_projection: Matrix4<f32>) -> Self {
+        let row = |index: usize| view_projection.row(index);
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        Self {
+            planes: [
+                Plane::from_coefficients(w + x),
+                Plane::from_coefficients(w - x),
+                Plane::from_coefficients(w + y),
+                Plane::from_coefficients(w - y),
+                Plane::from_coefficients(z),
+                Plane::from_coefficients(w - z),
+            ],
+        }
+    }
+
+    // Conservative, a sphere near a corner can pass although it is outside.
+    pub fn intersects_sphere(&self, center: Point3<f32>, radius: f32) -> bool {
+        self.planes.iter().all(|plane| plane.distance_to(center) >= -radius)
+    }
+
+    // Tests the corner furthest along each plane's normal, conservative like `intersects_sphere`.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            let corner = Point3::new(
+                if plane.normal.x >= 0.0 { aabb.max.x } else { aabb.min.x },
+                if plane.normal.y >= 0.0 { aabb.max.y } else { aabb.min.y },
+                if plane.normal.z >= 0.0 { aabb.max.z } else { aabb.min.z },
+            );
+            plane.distance_to(corner) >= 0.0
+        })
+    }
+}
+
+// How many objects a view drew and how many it skipped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CullStats {
+    pub visible: u32,
+    pub culled: u32,
+}
+impl CullStats {
+    pub fn count(&mut self, visible: bool) {
+        if visible {
+            self.visible += 1;
+        } else {
+            self.culled += 1;
+        }
+    }
+}
+impl fmt::Display for CullStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} visible, {} culled", self.visible, self.culled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples::perspective_camera::camera::Camera;
+
+    // At the origin looking down -z, 90° vertically so the side planes are at 45°.
+    fn frustum() -> Frustum {
+        let camera = Camera {
+            eye: (0.0, 0.0, 0.0).into(),
+            target: (0.0, 0.0, -1.0).into(),
+            up: Vector3::unit_y(),
+            aspect: 1.0,
+            fovy: 90.0,
+            znear: 1.0,
+            zfar: 10.0
+        };
+        Frustum::from_view_projection(camera.build_view_projection_matrix())
+    }
+
+    #[test]
+    fn planes_are_normalized_and_face_inwards() {
+        let frustum = frustum();
+        let inside = Point3::new(0.0, 0.0, -5.0);
+        for plane in frustum.planes {
+            assert!((plane.normal.magnitude() - 1.0).abs() < 1e-5);
+            assert!(plane.distance_to(inside) > 0.0);
+        }
+        // Near and far sit at the camera's clip distances.
+        let [.., near, far] = frustum.planes;
+        assert!((near.distance_to(Point3::new(0.0, 0.0, -1.0))).abs() < 1e-4);
+        assert!((far.distance_to(Point3::new(0.0, 0.0, -10.0))).abs() < 1e-3);
+        // The left plane goes through the eye at 45°.
+        let left = frustum.planes[0];
+        assert!((left.normal - Vector3::new(1.0, 0.0, -1.0).normalize()).magnitude() < 1e-5);
+        assert!(left.distance_to(Point3::new(-3.0, 0.0, -3.0)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn spheres_outside_any_plane_are_culled() {
+        let frustum = frustum();
+        assert!(frustum.intersects_sphere(Point3::new(0.0, 0.0, -5.0), 0.5));
+        // behind the eye, beyond the far plane, off to the right
+        assert!(!frustum.intersects_sphere(Point3::new(0.0, 0.0, 2.0), 0.5));
+        assert!(!frustum.intersects_sphere(Point3::new(0.0, 0.0, -12.0), 1.0));
+        assert!(!frustum.intersects_sphere(Point3::new(8.0, 0.0, -5.0), 1.0));
+        // straddling the right plane
+        assert!(frustum.intersects_sphere(Point3::new(5.5, 0.0, -5.0), 1.0));
+    }
+
+    #[test]
+    fn boxes_outside_any_plane_are_culled() {
+        let frustum = frustum();
+        let aabb = |min: [f32; 3], max: [f32; 3]| Aabb { min: min.into(), max: max.into() };
+        assert!(frustum.intersects_aabb(&aabb([-1.0, -1.0, -6.0], [1.0, 1.0, -4.0])));
+        assert!(!frustum.intersects_aabb(&aabb([-1.0, -1.0, 1.0], [1.0, 1.0, 3.0])));
+        assert!(!frustum.intersects_aabb(&aabb([-1.0, 7.0, -6.0], [1.0, 9.0, -4.0])));
+        // reaches into the frustum from below
+        assert!(frustum.intersects_aabb(&aabb([-1.0, -9.0, -6.0], [1.0, -4.0, -4.0])));
+        // surrounds the whole frustum
+        assert!(frustum.intersects_aabb(&aabb([-20.0, -20.0, -20.0], [20.0, 20.0, 20.0])));
+    }
+
+    #[test]
+    fn stats_count_both_outcomes() {
+        let mut stats = CullStats::default();
+        for visible in [true, false, true] {
+            stats.count(visible);
+        }
+        assert_eq!(stats, CullStats { visible: 2, culled: 1 });
+        assert_eq!(stats.to_string(), "2 visible, 1 culled");
+    }
+}
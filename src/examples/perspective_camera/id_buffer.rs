@@ -0,0 +1,346 @@
+use crate::examples::perspective_camera::{
+    Mesh,
+    Vertex,
+};
+
+pub const ID_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+// Offscreen pass that renders object ids into an `R32Uint` target, so the object under
+// a pixel can be read back exactly. Slot `n` of `render` is written as id `n + 1`, 0 stays "nothing".
+// Needs no window, only a device and a queue.
+pub struct IdBuffer {
+    width: u32,
+    height: u32,
+    view: wgpu::TextureView,
+    texture: wgpu::Texture,
+    depth_view: wgpu::TextureView,
+    pipeline: wgpu::RenderPipeline,
+    object_bind_group: wgpu::BindGroup,
+    object_stride: u32,
+    max_objects: u32,
+    readback_buffer: wgpu::Buffer,
+}
+impl IdBuffer {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        model_bind_group_layout: &wgpu::BindGroupLayout,
+        width: u32,
+        height: u32,
+        max_objects: u32,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Id Buffer Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("id_buffer.wgsl").into()),
+        });
+
+        // OBJECT IDS, one aligned uniform per slot selected with a dynamic offset
+        let object_stride = device.limits().min_uniform_buffer_offset_alignment;
+        let object_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Object Id Buffer"),
+            size: (object_stride * max_objects) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        for slot in 0..max_objects {
+            queue.write_buffer(
+                &object_buffer,
+                (slot * object_stride) as wgpu::BufferAddress,
+                bytemuck::cast_slice(&[slot + 1]));
+        }
+        let object_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(size_of::<u32>() as u64),
+                    },
+                    count: None
+                }
+            ],
+            label: Some("object_bind_group_layout"),
+        });
+        let object_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &object_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &object_buffer,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(size_of::<u32>() as u64),
+                    }),
+                }
+            ],
+            label: Some("object_bind_group"),
+        });
+
+        // RENDER PIPELINE
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some("Id Buffer Pipeline Layout"),
+            bind_group_layouts: &[
+                camera_bind_group_layout,
+                &object_bind_group_layout,
+                model_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Id Buffer Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[
+                    Vertex::desc()
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: ID_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                front_face: wgpu::FrontFace::Ccw,
+                ..wgpu::PrimitiveState::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        // A single texel is copied per pick, rows of a copy are always 256 byte aligned.
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Id Readback Buffer"),
+            size: wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let (texture, view, depth_view) = Self::create_targets(device, width, height);
+        Self {
+            width,
+            height,
+            view,
+            texture,
+            depth_view,
+            pipeline,
+            object_bind_group,
+            object_stride,
+            max_objects,
+            readback_buffer,
+        }
+    }
+
+    // Sized like the surface, so cursor coordinates map 1:1 to texels.
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        if (width, height) == (self.width, self.height) || width == 0 || height == 0 {
+            return;
+        }
+        (self.texture, self.view, self.depth_view) = Self::create_targets(device, width, height);
+        self.width = width;
+        self.height = height;
+    }
+
+    // Each mesh comes with the dynamic offset of its world matrix in `model_bind_group`.
+    pub fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        camera_bind_group: &wgpu::BindGroup,
+        model_bind_group: &wgpu::BindGroup,
+        meshes: &[(&Mesh, u32)],
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Id Buffer Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                }
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        for (slot, (mesh, model_offset)) in meshes.iter().take(self.max_objects as usize).enumerate() {
+            render_pass.set_bind_group(1, &self.object_bind_group, &[slot as u32 * self.object_stride]);
+            render_pass.set_bind_group(2, model_bind_group, &[*model_offset]);
+            render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..mesh.index_count, 0, 0..1);
+        }
+    }
+
+    // Blocks until the texel at (x, y) is read back, returns the slot drawn there.
+    pub fn read(&self, device: &wgpu::Device, queue: &wgpu::Queue, x: u32, y: u32) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {label: Some("Id Readback Encoder")});
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                aspect: wgpu::TextureAspect::All,
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.readback_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+        );
+        queue.submit(Some(encoder.finish()));
+
+        let slice = self.readback_buffer.slice(..size_of::<u32>() as wgpu::BufferAddress);
+        slice.map_async(wgpu::MapMode::Read, |_| {});
+        device.poll(wgpu::Maintain::Wait);
+        let id = bytemuck::pod_read_unaligned::<u32>(&slice.get_mapped_range());
+        self.readback_buffer.unmap();
+        id.checked_sub(1).map(|slot| slot as usize)
+    }
+
+    fn create_targets(device: &wgpu::Device, width: u32, height: u32) -> (wgpu::Texture, wgpu::TextureView, wgpu::TextureView) {
+        let size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Id Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: ID_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Id Depth Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (texture, view, depth_view)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_std::task;
+    use wgpu::util::{BufferInitDescriptor, DeviceExt};
+    use crate::examples::perspective_camera::{
+        create_model_bind_group_layout,
+        main_camera,
+        CameraUniform,
+        ModelUniform,
+        QUAD_INDICES,
+        QUAD_VERTICES,
+    };
+
+    #[test]
+    fn reads_back_the_mesh_under_a_pixel() {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor { backends: wgpu::Backends::all(), ..Default::default()});
+        let adapter = task::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .expect("the id buffer test needs an adapter");
+        let (device, queue) = task::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).unwrap();
+
+        let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                }
+            ],
+            label: Some("camera_bind_group_layout"),
+        });
+        let mut camera_uniform = CameraUniform::new();
+        camera_uniform.update_view_proj(&main_camera(1.0));
+        let camera_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Camera Buffer"),
+            contents: bytemuck::cast_slice(&[camera_uniform]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &camera_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: camera_buffer.as_entire_binding()
+                }
+            ],
+            label: Some("camera_bind_group"),
+        });
+        let model_bind_group_layout = create_model_bind_group_layout(&device);
+        let model_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Model Buffer"),
+            contents: bytemuck::cast_slice(&[ModelUniform::new(cgmath::Matrix4::from_scale(1.0), false)]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let model_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &model_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: model_buffer.as_entire_binding()
+                }
+            ],
+            label: Some("model_bind_group"),
+        });
+        let quad = Mesh::new(&device, "Quad", QUAD_VERTICES, QUAD_INDICES);
+
+        let mut id_buffer = IdBuffer::new(&device, &queue, &camera_bind_group_layout, &model_bind_group_layout, 32, 32, 2);
+        id_buffer.resize(&device, 64, 64);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {label: None});
+        id_buffer.render(&mut encoder, &camera_bind_group, &model_bind_group, &[(&quad, 0), (&quad, 0)]);
+        queue.submit(Some(encoder.finish()));
+
+        // The second draw is at the same depth and loses the depth test.
+        assert_eq!(id_buffer.read(&device, &queue, 32, 32), Some(0));
+        assert_eq!(id_buffer.read(&device, &queue, 0, 0), None);
+        assert_eq!(id_buffer.read(&device, &queue, 64, 64), None);
+    }
+}
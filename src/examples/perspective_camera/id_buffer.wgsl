@@ -0,0 +1,29 @@
+// Writes the id of the object being drawn instead of a color, 0 means "nothing".
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct ObjectUniform {
+    id: u32,
+};
+@group(1) @binding(0) // dynamic offset selects the object
+var<uniform> object: ObjectUniform;
+
+struct ModelUniform {
+    model: mat4x4<f32>,
+};
+@group(2) @binding(0) // dynamic offset selects the scene node
+var<uniform> node: ModelUniform;
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>) -> @builtin(position) vec4<f32> {
+    return camera.view_proj * node.model * vec4<f32>(position, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) u32 {
+    return object.id;
+}
@@ -1,13 +1,19 @@
-mod camera;
-mod texture;
+pub mod camera;
+pub mod frustum;
+mod id_buffer;
+pub mod picking;
+pub mod scene;
+pub mod texture;
 
 use std::{
+    collections::HashMap,
     sync::Arc,
 };
 use winit::{
     event_loop::{
         EventLoop,
-        ActiveEventLoop
+        ActiveEventLoop,
+        ControlFlow,
     },
     event::{
         DeviceEvent,
@@ -16,27 +22,66 @@ use winit::{
         WindowEvent
     },
     window::{
-        Window,
+        WindowAttributes,
         WindowId,
-        WindowAttributes
     },
     dpi::{
         LogicalSize,
-        Position,
-        PhysicalPosition,
-        PhysicalSize
+        PhysicalSize,
     },
     application::ApplicationHandler,
 };
 use async_std::task;
+use cgmath::{
+    InnerSpace,
+    Quaternion,
+    Rad,
+    Rotation3,
+    SquareMatrix,
+    Transform as _,
+    Vector3,
+};
+use crate::input::{
+    Action,
+    Input,
+};
+use crate::window::{
+    ManagedWindow,
+    WindowConfig,
+    WindowManager,
+};
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
-use crate::examples::perspective_camera::camera::Camera;
+use crate::examples::perspective_camera::camera::{
+    Camera,
+    CameraController,
+};
+use crate::examples::perspective_camera::frustum::{
+    CullStats,
+    Frustum,
+};
+use crate::examples::perspective_camera::id_buffer::IdBuffer;
+use crate::examples::raster_mode::{
+    RasterMode,
+    RasterPipelines,
+};
+use crate::examples::perspective_camera::picking::{
+    Aabb,
+    Ray,
+};
+use crate::examples::perspective_camera::scene::{
+    Attachment,
+    NodeId,
+    PointLight,
+    Projection,
+    Scene,
+    Transform,
+};
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct Vertex {
     position: [f32; 3],
-    color: [f32; 3],
+    tex_coords: [f32; 2],
 }
 impl Vertex {
     fn desc() -> wgpu::VertexBufferLayout<'static> {
@@ -77,15 +122,86 @@ impl CameraUniform {
     }
 }
 
-struct CameraController {}
-impl CameraController {}
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ModelUniform {
+    model: [[f32; 4]; 4],
+    // multiplies the texture color, highlights the selected node
+    tint: [f32; 4],
+}
+impl ModelUniform {
+    fn new(model: cgmath::Matrix4<f32>, selected: bool) -> Self {
+        let tint = if selected { SELECTED_TINT } else { [1.0; 4] };
+        Self { model: model.into(), tint }
+    }
+}
+
+const SELECTED_TINT: [f32; 4] = [1.0, 0.55, 0.2, 1.0];
+
+// How bright the scene is away from its light.
+const AMBIENT: f32 = 0.35;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct LightUniform {
+    position: [f32; 3],
+    range: f32,
+    color: [f32; 3],
+    ambient: f32,
+}
+impl LightUniform {
+    // Without a light the texture is shown as it is, which is what the texture viewer wants.
+    fn new(light: Option<(cgmath::Point3<f32>, PointLight)>) -> Self {
+        match light {
+            Some((position, light)) => Self {
+                position: position.into(),
+                range: light.range,
+                color: light.color,
+                ambient: AMBIENT,
+            },
+            None => Self {
+                position: [0.0; 3],
+                range: 1.0,
+                color: [0.0; 3],
+                ambient: 1.0,
+            },
+        }
+    }
+}
+
+// One world matrix and tint per scene node, the node's index picks it with a dynamic offset.
+fn create_model_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(size_of::<ModelUniform>() as u64),
+                },
+                count: None
+            }
+        ],
+        label: Some("model_bind_group_layout"),
+    })
+}
+
+// Spins a scene node around one of its local axes.
+struct Spin {
+    node: NodeId,
+    axis: Vector3<f32>,
+    // radians per second
+    speed: f32,
+}
 
 const VERTICES: &[Vertex] = &[
-    Vertex { position: [-0.0868241, 0.49240386, 0.0], color: [0.5, 0.0, 0.0] }, // A
-    Vertex { position: [-0.49513406, 0.06958647, 0.0], color: [0.0, 0.5, 0.0] }, // B
-    Vertex { position: [-0.21918549, -0.44939706, 0.0], color: [0.0, 0.0, 0.5] }, // C
-    Vertex { position: [0.35966998, -0.3473291, 0.0], color: [0.5, 0.0, 0.0] }, // D
-    Vertex { position: [0.44147372, 0.2347359, 0.0], color: [0.0, 0.5, 0.0] }, // E
+    Vertex { position: [-0.0868241, 0.49240386, 0.0], tex_coords: [0.4131759, 0.00759614], }, // A
+    Vertex { position: [-0.49513406, 0.06958647, 0.0], tex_coords: [0.0048659444, 0.43041354], }, // B
+    Vertex { position: [-0.21918549, -0.44939706, 0.0], tex_coords: [0.28081453, 0.949397], }, // C
+    Vertex { position: [0.35966998, -0.3473291, 0.0], tex_coords: [0.85967, 0.84732914], }, // D
+    Vertex { position: [0.44147372, 0.2347359, 0.0], tex_coords: [0.9414737, 0.2652641], }, // E
 ];
 const INDICES: &[u16] = &[
     0, 1, 4,
@@ -93,47 +209,153 @@ const INDICES: &[u16] = &[
     2, 3, 4,
 ];
 
-struct Application<'window> {
-    window: Option<Arc<Window>>,
+// Whole texture on a unit quad, shown by the texture viewer window.
+const QUAD_VERTICES: &[Vertex] = &[
+    Vertex { position: [-0.5, 0.5, 0.0], tex_coords: [0.0, 0.0], },
+    Vertex { position: [-0.5, -0.5, 0.0], tex_coords: [0.0, 1.0], },
+    Vertex { position: [0.5, -0.5, 0.0], tex_coords: [1.0, 1.0], },
+    Vertex { position: [0.5, 0.5, 0.0], tex_coords: [1.0, 0.0], },
+];
+const QUAD_INDICES: &[u16] = &[
+    0, 1, 2,
+    0, 2, 3,
+];
+
+// GPU buffers plus a CPU copy of the geometry for picking.
+struct Mesh {
+    name: String,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    index_count: u32,
+    positions: Vec<cgmath::Point3<f32>>,
+    indices: Vec<u16>,
+    bounds: Aabb,
+}
+impl Mesh {
+    fn new(device: &wgpu::Device, label: &str, vertices: &[Vertex], indices: &[u16]) -> Self {
+        let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some(&format!("{label} Vertex Buffer")),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some(&format!("{label} Index Buffer")),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let positions: Vec<_> = vertices.iter().map(|vertex| vertex.position.into()).collect();
+        let bounds = Aabb::from_points(&positions);
+        Self {
+            name: label.to_string(),
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+            positions,
+            indices: indices.to_vec(),
+            bounds,
+        }
+    }
+}
+
+// Everything a single window needs to draw the scene: its own camera and the part of the scene it looks at.
+struct View {
+    camera: Camera,
+    camera_uniform: CameraUniform,
+    camera_buffer: wgpu::Buffer,
+    light_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    // drawn with everything below it
+    root: NodeId,
+    // meshes below `root` inside the camera's frustum, refreshed every frame
+    visible: Vec<(NodeId, usize)>,
+    // drawn into and resolved to the frame, `None` with 1x MSAA
+    msaa: Option<wgpu::TextureView>,
+    depth_texture: texture::Texture,
+}
+
+struct Application {
+    window_config: WindowConfig,
+    windows: WindowManager,
+    views: HashMap<WindowId, View>,
+    main_window: Option<WindowId>,
+    texture_viewer: Option<WindowId>,
+    input: Input,
+    camera_controller: CameraController,
+    // of the main window, printed when they change
+    cull_stats: CullStats,
+    selected: Option<NodeId>,
+    gpu_picking: bool,
+    id_buffer: Option<IdBuffer>,
     instance: wgpu::Instance,
     adapter: wgpu::Adapter,
     device: wgpu::Device,
     queue: wgpu::Queue,
-    surface: Option<wgpu::Surface<'window>>,
-    surface_config: Option<wgpu::SurfaceConfiguration>,
     shader: Option<wgpu::ShaderModule>,
     pipeline_layout: Option<wgpu::PipelineLayout>,
-    render_pipeline: Option<wgpu::RenderPipeline>,
-    buffers: Vec<wgpu::Buffer>,
+    // fill, wireframe and points, picked by `raster_mode`
+    render_pipelines: Option<RasterPipelines>,
+    raster_mode: RasterMode,
+    sample_count: u32,
+    meshes: Vec<Mesh>,
+    scene: Scene,
+    // what the main window shows
+    world_root: NodeId,
+    // what the texture viewer shows, including its camera
+    viewer_root: NodeId,
+    spins: Vec<Spin>,
+    model_buffer: Option<wgpu::Buffer>,
+    model_bind_group: Option<wgpu::BindGroup>,
+    model_stride: u32,
     diffuse_bind_group: Option<wgpu::BindGroup>,
     diffuse_texture: Option<texture::Texture>,
-    camera: Option<Camera>,
-    camera_uniform: Option<CameraUniform>,
-    camera_buffer: Option<wgpu::Buffer>,
-    camera_bind_group: Option<wgpu::BindGroup>,
+    camera_bind_group_layout: Option<wgpu::BindGroupLayout>,
 }
 
-impl<'window> Application<'window> {
+fn main_camera(aspect: f32) -> Camera {
+    camera::Camera {
+        eye: (0.0, 1.0, 2.0).into(),
+        target: (0.0, 0.0, 0.0).into(),
+        up: cgmath::Vector3::unit_y(),
+        aspect,
+        fovy: 45.0,
+        znear: 0.1,
+        zfar: 100.0
+    }
+}
+
+fn default_camera_controller() -> CameraController {
+    CameraController::new(2.0, 1.5, 0.005)
+}
+
+// The meshes below `root` whose world space bounds are inside the camera's frustum.
+fn cull(scene: &Scene, meshes: &[Mesh], root: NodeId, camera: &Camera) -> (Vec<(NodeId, usize)>, CullStats) {
+    let frustum = Frustum::from_view_projection(camera.build_view_projection_matrix());
+    let mut stats = CullStats::default();
+    let visible = scene.meshes(root)
+        .into_iter()
+        .filter(|&(node, mesh)| {
+            let visible = frustum.intersects_aabb(&meshes[mesh].bounds.transformed(scene.world(node)));
+            stats.count(visible);
+            visible
+        })
+        .collect();
+    (visible, stats)
+}
+
+impl Application {
     fn init(&mut self, event_loop: &ActiveEventLoop) {
-        let window = Arc::new(event_loop.create_window(WindowAttributes::default()
-            .with_title("My Window")
-            .with_inner_size(LogicalSize::new(512, 512))
-            .with_position(Position::Physical(PhysicalPosition::new(1800,500))))
+        let window = Arc::new(event_loop.create_window(self.window_config.attributes(event_loop))
             .expect("Failed to create window"));
-
-        let surface = self.instance.create_surface(window.clone()).unwrap();
-        let surface_format= surface.get_capabilities(&self.adapter).formats[0];
-        let surface_config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
-            width: window.inner_size().width,
-            height: window.inner_size().height,
-            present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: wgpu::CompositeAlphaMode::Auto,
-            view_formats: vec![],
-            desired_maximum_frame_latency: 2,
-        };
-        surface.configure(&self.device, &surface_config);
+        let main_window = ManagedWindow::new(
+            window,
+            &self.instance,
+            &self.adapter,
+            &self.device,
+            self.window_config.present_mode(),
+            None,
+        ).expect("Failed to create surface");
+        let surface_format = main_window.surface_config.format;
+        self.sample_count = self.window_config.sample_count(&self.adapter, &self.device, &[surface_format]);
 
         // SHADER
         let shader = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -190,22 +412,6 @@ impl<'window> Application<'window> {
         );
 
         // CAMERA
-        let camera = camera::Camera {
-            eye: (0.0, 1.0, 2.0).into(),
-            target: (0.0, 0.0, 0.0).into(),
-            up: cgmath::Vector3::unit_y(),
-            aspect: surface_config.width as f32 / surface_config.height as f32,
-            fovy: 45.0,
-            znear: 0.1,
-            zfar: 100.0
-        };
-        let mut camera_uniform = CameraUniform::new();
-        camera_uniform.update_view_proj(&camera);
-        let camera_buffer = self.device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("Camera Buffer"),
-            contents: bytemuck::cast_slice(&[camera_uniform]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
         let camera_bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[
                 wgpu::BindGroupLayoutEntry {
@@ -217,32 +423,57 @@ impl<'window> Application<'window> {
                         min_binding_size: None
                     },
                     count: None
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
                 }
             ],
             label: Some("camera_bind_group_layout"),
         });
-        let camera_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &camera_bind_group_layout,
+
+        // SCENE, world matrices of all nodes in one buffer
+        let model_bind_group_layout = create_model_bind_group_layout(&self.device);
+        let model_stride = self.device.limits().min_uniform_buffer_offset_alignment;
+        let model_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Model Buffer"),
+            size: (model_stride as usize * self.scene.node_count()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let model_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &model_bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: camera_buffer.as_entire_binding()
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &model_buffer,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(size_of::<ModelUniform>() as u64),
+                    }),
                 }
             ],
-            label: Some("camera_bind_group"),
+            label: Some("model_bind_group"),
         });
 
         // RENDER PIPELINE
-        let pipeline_layout = Some(self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+        let pipeline_layout = self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
             label: Some("Pipeline Layout"),
             bind_group_layouts: &[
                 &texture_bind_group_layout,
                 &camera_bind_group_layout,
+                &model_bind_group_layout,
             ],
             push_constant_ranges: &[],
-        })).expect("Failed to create pipeline layout");
+        });
 
-        let render_pipeline = self.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        let render_pipeline = wgpu::RenderPipelineDescriptor {
             label: Some("Render Pipeline"),
             layout: Some(&pipeline_layout),
             vertex: wgpu::VertexState {
@@ -273,168 +504,554 @@ impl<'window> Application<'window> {
                 front_face: wgpu::FrontFace::Ccw,
                 ..wgpu::PrimitiveState::default()
             },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
+            // the same test as the id buffer, so what gets picked is what is shown
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: texture::Texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: self.sample_count,
+                ..Default::default()
+            },
             multiview: None,
             cache: None,
-        });
+        };
 
-        self.window = Some(window);
-        self.surface = Some(surface);
+        self.render_pipelines = Some(RasterPipelines::new(&self.device, &render_pipeline));
         self.shader = Some(shader);
         self.pipeline_layout = Some(pipeline_layout);
-        self.render_pipeline = Some(render_pipeline);
-        self.surface_config = Some(surface_config);
         self.diffuse_texture = Some(diffuse_texture);
         self.diffuse_bind_group = Some(diffuse_bind_group);
-        self.camera = Some(camera);
-        self.camera_uniform = Some(camera_uniform);
-        self.camera_buffer = Some(camera_buffer);
-        self.camera_bind_group = Some(camera_bind_group);
+        self.id_buffer = Some(IdBuffer::new(
+            &self.device,
+            &self.queue,
+            &camera_bind_group_layout,
+            &model_bind_group_layout,
+            main_window.surface_config.width,
+            main_window.surface_config.height,
+            self.scene.node_count() as u32,
+        ));
+        self.camera_bind_group_layout = Some(camera_bind_group_layout);
+        self.model_buffer = Some(model_buffer);
+        self.model_bind_group = Some(model_bind_group);
+        self.model_stride = model_stride;
+        let changed = self.scene.update();
+        self.upload_models(&changed);
+
+        let view = self.create_view(main_camera(main_window.aspect()), self.world_root, &main_window.surface_config);
+        let id = self.windows.insert(main_window);
+        self.views.insert(id, view);
+        self.main_window = Some(id);
+    }
+
+    fn create_view(&self, camera: Camera, root: NodeId, config: &wgpu::SurfaceConfiguration) -> View {
+        let mut camera_uniform = CameraUniform::new();
+        camera_uniform.update_view_proj(&camera);
+        let camera_buffer = self.device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Camera Buffer"),
+            contents: bytemuck::cast_slice(&[camera_uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let light_buffer = self.device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Light Buffer"),
+            contents: bytemuck::cast_slice(&[LightUniform::new(self.scene.light(root))]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let camera_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: self.camera_bind_group_layout.as_ref().unwrap(),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: camera_buffer.as_entire_binding()
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: light_buffer.as_entire_binding()
+                }
+            ],
+            label: Some("camera_bind_group"),
+        });
+        let msaa = WindowConfig::create_msaa_view(&self.device, config, config.format, self.sample_count);
+        let depth_texture = texture::Texture::create_depth_texture(&self.device, config, self.sample_count, "depth_texture");
+        let (visible, _) = cull(&self.scene, &self.meshes, root, &camera);
+        View { camera, camera_uniform, camera_buffer, light_buffer, camera_bind_group, root, visible, msaa, depth_texture }
+    }
+
+    // Writes the world matrices of `nodes` into their slots of the model buffer.
+    fn upload_models(&self, nodes: &[NodeId]) {
+        let Some(model_buffer) = self.model_buffer.as_ref() else {
+            return;
+        };
+        for &node in nodes {
+            self.queue.write_buffer(
+                model_buffer,
+                (node.index() as u32 * self.model_stride) as wgpu::BufferAddress,
+                bytemuck::cast_slice(&[ModelUniform::new(self.scene.world(node), self.selected == Some(node))]));
+        }
+    }
+
+    // Second window looking straight at the texture, sharing device, pipeline and bind groups.
+    fn toggle_texture_viewer(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(id) = self.texture_viewer.take() {
+            self.views.remove(&id);
+            self.windows.remove(id);
+            return;
+        }
+        let attributes = self.window_config.platform.apply(WindowAttributes::default()
+            .with_title("Texture Viewer")
+            .with_inner_size(LogicalSize::new(256, 256)));
+        let window = Arc::new(event_loop.create_window(attributes)
+            .expect("Failed to create window"));
+        let main_format = self.main_window
+            .and_then(|id| self.windows.get(id))
+            .map(|main_window| main_window.surface_config.format);
+        // the pipelines are built for the main window's format, the viewer cannot use another one
+        let texture_viewer = match ManagedWindow::new(
+            window,
+            &self.instance,
+            &self.adapter,
+            &self.device,
+            self.window_config.present_mode(),
+            main_format,
+        ) {
+            Ok(texture_viewer) => texture_viewer,
+            Err(error) => {
+                eprintln!("Cannot open the texture viewer: {error:#}");
+                return;
+            }
+        };
+        let camera = self.scene.camera(self.viewer_root, texture_viewer.aspect())
+            .expect("the texture viewer scene has no camera");
+        let view = self.create_view(camera, self.viewer_root, &texture_viewer.surface_config);
+        let id = self.windows.insert(texture_viewer);
+        self.views.insert(id, view);
+        self.texture_viewer = Some(id);
+    }
+
+    fn resize(&mut self, window_id: WindowId, size: PhysicalSize<u32>) {
+        let (Some(managed), Some(view)) = (self.windows.get_mut(window_id), self.views.get_mut(&window_id)) else {
+            return;
+        };
+        if !managed.resize(&self.device, size) {
+            return;
+        }
+        view.msaa = WindowConfig::create_msaa_view(&self.device, &managed.surface_config, managed.surface_config.format, self.sample_count);
+        view.depth_texture = texture::Texture::create_depth_texture(&self.device, &managed.surface_config, self.sample_count, "depth_texture");
+        view.camera.aspect = managed.aspect();
+        view.camera_uniform.update_view_proj(&view.camera);
+        self.queue.write_buffer(&view.camera_buffer, 0, bytemuck::cast_slice(&[view.camera_uniform]));
+        managed.window.request_redraw();
+        println!("Window resized: {:?}", size);
+    }
+
+    // Selects the closest scene node under the cursor, either with a CPU ray cast or the GPU id buffer.
+    fn pick(&mut self, window_id: WindowId, cursor: (f32, f32)) -> Option<NodeId> {
+        let (Some(managed), Some(view)) = (self.windows.get(window_id), self.views.get(&window_id)) else {
+            return None;
+        };
+        let objects = self.scene.meshes(view.root);
+        let (width, height) = (managed.surface_config.width, managed.surface_config.height);
+        if self.gpu_picking {
+            // left of or above the window while dragging, casting would clamp it onto the first texel
+            if cursor.0 < 0.0 || cursor.1 < 0.0 {
+                println!("Selected nothing");
+                return None;
+            }
+            let (Some(id_buffer), Some(model_bind_group)) = (self.id_buffer.as_mut(), self.model_bind_group.as_ref()) else {
+                return None;
+            };
+            id_buffer.resize(&self.device, width, height);
+            let meshes: Vec<(&Mesh, u32)> = objects.iter()
+                .map(|&(node, mesh)| (&self.meshes[mesh], node.index() as u32 * self.model_stride))
+                .collect();
+            let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {label: Some("Id Buffer Encoder")});
+            id_buffer.render(&mut encoder, &view.camera_bind_group, model_bind_group, &meshes);
+            self.queue.submit(Some(encoder.finish()));
+            let slot = id_buffer.read(&self.device, &self.queue, cursor.0 as u32, cursor.1 as u32);
+            let hit = slot.map(|slot| objects[slot]);
+            match hit {
+                Some((node, mesh)) => println!("Selected {} ({}) at pixel {:?}", self.scene.node(node).name, self.meshes[mesh].name, cursor),
+                None => println!("Selected nothing"),
+            }
+            return hit.map(|(node, _)| node);
+        }
+        // Meshes are tested in their own space, hits are compared by world distance.
+        let ray = view.camera.screen_ray(cursor, (width as f32, height as f32));
+        let hit = objects.into_iter()
+            .filter_map(|(node, index)| {
+                let mesh = &self.meshes[index];
+                let world = self.scene.world(node);
+                let inverse = world.invert()?;
+                let local_ray = Ray::new(inverse.transform_point(ray.origin), inverse.transform_vector(ray.direction));
+                local_ray.intersect_mesh(&mesh.positions, &mesh.indices, &mesh.bounds)
+                    .map(|t| world.transform_point(local_ray.at(t)))
+                    .map(|point| (node, index, point, (point - ray.origin).magnitude()))
+            })
+            .min_by(|a, b| a.3.total_cmp(&b.3));
+        match hit {
+            Some((node, mesh, point, _)) => println!("Selected {} ({}) at {:?}", self.scene.node(node).name, self.meshes[mesh].name, point),
+            None => println!("Selected nothing"),
+        }
+        hit.map(|(node, ..)| node)
+    }
+
+    // Moves the highlight from the previously selected node to `node`.
+    fn select(&mut self, node: Option<NodeId>) {
+        let previous = std::mem::replace(&mut self.selected, node);
+        let changed: Vec<NodeId> = previous.into_iter().chain(node).collect();
+        self.upload_models(&changed);
+    }
+
+    fn render(&self, window_id: WindowId) {
+        let (Some(managed), Some(view)) = (self.windows.get(window_id), self.views.get(&window_id)) else {
+            return;
+        };
+        let Some(surface) = managed.surface.as_ref() else {
+            return;
+        };
+        let frame = surface.get_current_texture().unwrap();
+        let frame_view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {label: Some("Render Encoder")});
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: view.msaa.as_ref().unwrap_or(&frame_view),
+                resolve_target: view.msaa.as_ref().map(|_| &frame_view),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.0,
+                        g: 0.0,
+                        b: 0.0,
+                        a: 1.0 }),
+                    store: wgpu::StoreOp::Store,
+                }
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &view.depth_texture.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass
+            .set_pipeline(
+                self.render_pipelines
+                    .as_ref()
+                    .unwrap()
+                    .get_or_fill(self.raster_mode));
+        render_pass
+            .set_bind_group(
+                0,
+                &self.diffuse_bind_group,
+                &[]);
+        render_pass
+            .set_bind_group(
+                1,
+                &view.camera_bind_group,
+                &[]);
+        for &(node, mesh) in &view.visible {
+            let mesh = &self.meshes[mesh];
+            render_pass
+                .set_bind_group(
+                    2,
+                    &self.model_bind_group,
+                    &[node.index() as u32 * self.model_stride]);
+            render_pass
+                .set_vertex_buffer(
+                    0,
+                    mesh.vertex_buffer.slice(..));
+            render_pass
+                .set_index_buffer(
+                    mesh.index_buffer.slice(..),
+                    wgpu::IndexFormat::Uint16);
+            render_pass
+                .draw_indexed(
+                    0..mesh.index_count,
+                    0,
+                    0..1);
+        }
+        drop(render_pass);
+
+        let command_buffer = encoder.finish();
+        self.queue.submit(Some(command_buffer));
+        frame.present();
     }
 }
-impl ApplicationHandler for Application<'_> {
+impl ApplicationHandler for Application {
     fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
         let _ = (event_loop, cause);
     }
 
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        if self.window.is_none() { &self.init(event_loop); }
+        if self.main_window.is_none() {
+            self.init(event_loop);
+            return;
+        }
+        let resumed = match self.windows.resume(&self.instance, &self.device) {
+            Ok(resumed) => resumed,
+            Err(error) => {
+                eprintln!("{error:#}");
+                event_loop.exit();
+                return;
+            }
+        };
+        for window_id in resumed {
+            let size = self.windows.get(window_id).unwrap().window.inner_size();
+            self.resize(window_id, size);
+        }
     }
 
-    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: ()) {}
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, _event: ()) {}
 
     fn window_event(&mut self, event_loop: &ActiveEventLoop, window_id: WindowId, event: WindowEvent) {
+        self.input.handle_window_event(&event);
         match event {
-            WindowEvent::CloseRequested => { event_loop.exit(); }
-            WindowEvent::RedrawRequested => {
-                if let Some(window) = self.window.as_ref() {
-                    let frame = self.surface.as_ref().unwrap().get_current_texture().unwrap();
-                    let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
-                    let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {label: Some("Render Encoder")});
-                    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                        label: Some("Render Pass"),
-                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                            view: &view,
-                            resolve_target: None,
-                            ops: wgpu::Operations {
-                                load: wgpu::LoadOp::Clear(wgpu::Color {
-                                    r: 0.0,
-                                    g: 0.0,
-                                    b: 0.0,
-                                    a: 1.0 }),
-                                store: wgpu::StoreOp::Store,
-                            }
-                        })],
-                        depth_stencil_attachment: None,
-                        timestamp_writes: None,
-                        occlusion_query_set: None,
-                    });
-
-                    render_pass
-                        .set_pipeline(
-                            self.render_pipeline
-                                .as_ref()
-                                .unwrap());
-                    render_pass
-                        .set_bind_group(
-                            0,
-                            &self.diffuse_bind_group,
-                            &[]);
-                    render_pass
-                        .set_bind_group(
-                            1,
-                            &self.camera_bind_group,
-                            &[]);
-                    render_pass
-                        .set_vertex_buffer(
-                            0,
-                            self.buffers[0].slice(..));
-                    render_pass
-                        .set_index_buffer(
-                            self.buffers[1].slice(..),
-                            wgpu::IndexFormat::Uint16);
-                    render_pass
-                        .draw_indexed(
-                            0..INDICES.len() as u32,
-                            0,
-                            0..1);
-                    render_pass
-                        .draw(
-                            0..VERTICES.len() as u32,
-                            0..1);
-                    drop(render_pass);
-
-                    let command_buffer = encoder.finish();
-                    self.queue.submit(Some(command_buffer));
-                    frame.present();
-
-                    // window.request_redraw();
-                    // println!("Redraw Requested");
+            WindowEvent::CloseRequested => {
+                if Some(window_id) == self.main_window {
+                    if let Some(managed) = self.windows.get(window_id) {
+                        self.window_config.store(&managed.window);
+                    }
+                    event_loop.exit();
+                } else if Some(window_id) == self.texture_viewer {
+                    self.toggle_texture_viewer(event_loop);
                 }
             }
-            WindowEvent::Resized(size) => {
-                self.surface_config.as_mut().unwrap().width = size.width;
-                self.surface_config.as_mut().unwrap().height = size.height;
-                self.surface.as_ref().unwrap().configure(&self.device, &self.surface_config.as_ref().unwrap());
-                println!("Window resized: {:?}", size);
-            }
+            WindowEvent::RedrawRequested => { self.render(window_id); }
+            WindowEvent::Resized(size) => { self.resize(window_id, size); }
             _ => {}
         }
     }
 
-    fn device_event(&mut self, event_loop: &ActiveEventLoop, device_id: DeviceId, event: DeviceEvent) {}
+    fn device_event(&mut self, _event_loop: &ActiveEventLoop, _device_id: DeviceId, event: DeviceEvent) {
+        self.input.handle_device_event(&event);
+    }
 
-    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {}
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.input.begin_frame();
+        if self.input.pressed(Action::ToggleTextureViewer) {
+            self.toggle_texture_viewer(event_loop);
+        }
+        let Some(main_window) = self.main_window else {
+            self.input.end_frame();
+            return;
+        };
+        let window = self.windows.get(main_window).unwrap().window.clone();
+        if self.window_config.handle_input(&window, &self.input) {
+            self.resize(main_window, window.inner_size());
+        }
+        if self.input.pressed(Action::CameraOrbit) {
+            window.set_cursor_visible(false);
+        }
+        if self.input.released(Action::CameraOrbit) {
+            window.set_cursor_visible(true);
+        }
+        if let Some(render_pipelines) = self.render_pipelines.as_ref() {
+            render_pipelines.handle_input(&mut self.raster_mode, &self.input);
+        }
+        if self.input.pressed(Action::TogglePickingMode) {
+            self.gpu_picking = !self.gpu_picking;
+            println!("Picking with {}", if self.gpu_picking { "GPU id buffer" } else { "CPU ray casts" });
+        }
+        if self.input.pressed(Action::Select)
+            && window.has_focus()
+            && let Some(cursor) = self.input.cursor_position() {
+            let node = self.pick(main_window, (cursor.x as f32, cursor.y as f32));
+            self.select(node);
+        }
 
-    fn suspended(&mut self, event_loop: &ActiveEventLoop) {}
+        // Move the scene, only nodes below a spinning one get new world matrices.
+        let dt = self.input.dt();
+        for spin in &self.spins {
+            let transform = self.scene.transform_mut(spin.node);
+            transform.rotation = (Quaternion::from_axis_angle(spin.axis, Rad(spin.speed * dt)) * transform.rotation).normalize();
+        }
+        let changed = self.scene.update();
+        if !changed.is_empty() {
+            self.upload_models(&changed);
+            for (&window_id, view) in &self.views {
+                self.queue.write_buffer(&view.light_buffer, 0, bytemuck::cast_slice(&[LightUniform::new(self.scene.light(view.root))]));
+                if let Some(managed) = self.windows.get(window_id) {
+                    managed.window.request_redraw();
+                }
+            }
+        }
 
-    fn exiting(&mut self, event_loop: &ActiveEventLoop) {}
+        // Keep polling while the scene or the camera moves or a replay runs, none of them produces events on its own.
+        let view = self.views.get_mut(&main_window).unwrap();
+        let moved = self.camera_controller.update(&mut view.camera, &self.input);
+        if moved {
+            view.camera_uniform.update_view_proj(&view.camera);
+            self.queue.write_buffer(&view.camera_buffer, 0, bytemuck::cast_slice(&[view.camera_uniform]));
+            window.request_redraw();
+        }
+        for (&window_id, view) in self.views.iter_mut() {
+            let (visible, stats) = cull(&self.scene, &self.meshes, view.root, &view.camera);
+            view.visible = visible;
+            if Some(window_id) == self.main_window && stats != self.cull_stats {
+                self.cull_stats = stats;
+                println!("Meshes: {stats}");
+            }
+        }
+        let animating = !changed.is_empty();
+        event_loop.set_control_flow(if moved || animating || self.input.replaying() { ControlFlow::Poll } else { ControlFlow::Wait });
+        self.input.end_frame();
+    }
+
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        self.windows.suspend();
+    }
 
-    fn memory_warning(&mut self, event_loop: &ActiveEventLoop) {}
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {}
+
+    fn memory_warning(&mut self, _event_loop: &ActiveEventLoop) {}
 }
 
-pub fn run() {
+pub fn run(window_config: WindowConfig, input: Input) {
     // winit
     let event_loop = EventLoop::new().unwrap();
 
     // wgpu
     let instance= wgpu::Instance::new(&wgpu::InstanceDescriptor { backends: wgpu::Backends::all(), ..Default::default()});
     let adapter= task::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())).unwrap();
-    let (device, queue)= task::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None, )).unwrap();
+    let (device, queue)= task::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+        // `PolygonMode::Line` for the wireframe raster mode
+        required_features: WindowConfig::msaa_features(&adapter)
+            | (adapter.features() & wgpu::Features::POLYGON_MODE_LINE),
+        ..Default::default()
+    }, None, )).unwrap();
 
     // buffers
-    let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Vertex Buffer"),
-        contents: bytemuck::cast_slice(VERTICES),
-        usage: wgpu::BufferUsages::VERTEX,
+    let meshes = vec![
+        Mesh::new(&device, "Pentagon", VERTICES, INDICES),
+        Mesh::new(&device, "Quad", QUAD_VERTICES, QUAD_INDICES),
+    ];
+
+    // SCENE
+    // A pentagon sun with a planet and its moon going around it, a lamp on the opposite side.
+    // Children sit slightly in front of their parents so they win the depth test.
+    let mut scene = Scene::default();
+    let world_root = scene.add(None, "World", Transform::default());
+    let sun = scene.add(Some(world_root), "Sun", Transform::default());
+    scene.attach(sun, Attachment::Mesh(0));
+    let orbit = scene.add(Some(world_root), "Orbit", Transform::default());
+    let planet = scene.add(Some(orbit), "Planet", Transform {
+        translation: Vector3::new(0.7, 0.0, 0.05),
+        scale: Vector3::new(0.3, 0.3, 0.3),
+        ..Default::default()
+    });
+    scene.attach(planet, Attachment::Mesh(0));
+    let moon = scene.add(Some(planet), "Moon", Transform {
+        translation: Vector3::new(0.8, 0.0, 0.05),
+        scale: Vector3::new(0.4, 0.4, 0.4),
+        ..Default::default()
     });
-    let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor{
-        label: Some("Index Buffer"),
-        contents: bytemuck::cast_slice(INDICES),
-        usage: wgpu::BufferUsages::INDEX,
+    scene.attach(moon, Attachment::Mesh(0));
+    let lamp = scene.add(Some(orbit), "Lamp", Transform {
+        translation: Vector3::new(-0.6, 0.0, 0.3),
+        ..Default::default()
+    });
+    scene.attach(lamp, Attachment::Light(PointLight { color: [1.0, 0.85, 0.6], range: 1.2 }));
+    let spins = vec![
+        Spin { node: sun, axis: Vector3::unit_z(), speed: 0.2 },
+        Spin { node: orbit, axis: Vector3::unit_z(), speed: 0.5 },
+        Spin { node: planet, axis: Vector3::unit_z(), speed: 2.0 },
+    ];
+
+    // The texture viewer looks straight at the whole texture.
+    let viewer_root = scene.add(None, "Texture Viewer", Transform::default());
+    let quad = scene.add(Some(viewer_root), "Quad", Transform::default());
+    scene.attach(quad, Attachment::Mesh(1));
+    let viewer_camera = scene.add(Some(viewer_root), "Viewer Camera", Transform {
+        translation: Vector3::new(0.0, 0.0, 1.3),
+        ..Default::default()
     });
+    scene.attach(viewer_camera, Attachment::Camera(Projection { fovy: 45.0, znear: 0.1, zfar: 100.0 }));
 
     let mut application = Application{
-        window: None,
-        surface: None,
-        surface_config: None,
+        window_config,
+        windows: WindowManager::default(),
+        views: HashMap::new(),
+        main_window: None,
+        texture_viewer: None,
+        input,
+        camera_controller: default_camera_controller(),
+        cull_stats: CullStats::default(),
+        selected: None,
+        gpu_picking: false,
+        id_buffer: None,
         instance,
         adapter,
         device,
         queue,
         shader: None,
         pipeline_layout: None,
-        render_pipeline: None,
-        buffers: vec![vertex_buffer, index_buffer],
+        render_pipelines: None,
+        raster_mode: RasterMode::Fill,
+        sample_count: 1,
+        meshes,
+        scene,
+        world_root,
+        viewer_root,
+        spins,
+        model_buffer: None,
+        model_bind_group: None,
+        model_stride: 0,
         diffuse_texture: None,
         diffuse_bind_group: None,
-        camera: None,
-        camera_uniform: None,
-        camera_buffer: None,
-        camera_bind_group: None,
+        camera_bind_group_layout: None,
     };
 
     event_loop.run_app(&mut application).expect("Failed to start event_loop");
     println!("base example");
-}
\ No newline at end of file
+}
+
+// Runs a recorded camera flight without a window or GPU and returns where the main camera ended up.
+// Every frame goes through the same `CameraController` as `about_to_wait`, so the same recording
+// always gives the same camera and camera bugs become reproducible.
+pub fn replay_headless(window_config: &WindowConfig, mut input: Input) -> Camera {
+    let settings = &window_config.settings;
+    let mut camera = main_camera(settings.width as f32 / settings.height as f32);
+    let camera_controller = default_camera_controller();
+    loop {
+        input.begin_frame();
+        if !input.replaying() {
+            break;
+        }
+        camera_controller.update(&mut camera, &input);
+        input.end_frame();
+    }
+    camera
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use cgmath::{
+        assert_abs_diff_eq,
+        InnerSpace,
+        Point3,
+    };
+    use crate::input::ActionMap;
+
+    #[test]
+    fn replaying_a_recording_moves_the_camera_the_same_way_every_time() {
+        let mut input = Input::new(ActionMap::default());
+        input.replay(&Path::new(env!("CARGO_MANIFEST_DIR")).join("src/examples/perspective_camera/flight.jsonl")).unwrap();
+        let camera = replay_headless(&WindowConfig::default(), input);
+
+        // half a second of KeyW dollies 1 unit closer, the mouse motion is ignored without
+        // orbiting and ArrowRight held for pi/3 seconds turns it a quarter around the target
+        let distance = 5.0f32.sqrt() - 1.0;
+        assert_abs_diff_eq!((camera.eye - camera.target).magnitude(), distance, epsilon = 1e-4);
+        assert_abs_diff_eq!(camera.eye, Point3::new(distance * 2.0 / 5.0f32.sqrt(), distance / 5.0f32.sqrt(), 0.0), epsilon = 1e-4);
+    }
+}
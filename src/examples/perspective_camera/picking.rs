@@ -0,0 +1,178 @@
+use cgmath::{
+    InnerSpace,
+    Matrix4,
+    Point3,
+    Transform,
+    Vector3,
+};
+
+// Below this a ray is considered parallel to a triangle.
+const EPSILON: f32 = 1e-6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Point3<f32>,
+    pub direction: Vector3<f32>,
+}
+impl Ray {
+    pub fn new(origin: Point3<f32>, direction: Vector3<f32>) -> Self {
+        Self { origin, direction: direction.normalize() }
+    }
+
+    pub fn at(&self, t: f32) -> Point3<f32> {
+        self.origin + self.direction * t
+    }
+
+    // Möller–Trumbore, returns the distance along the ray. Both windings are hit.
+    pub fn intersect_triangle(&self, a: Point3<f32>, b: Point3<f32>, c: Point3<f32>) -> Option<f32> {
+        let edge1 = b - a;
+        let edge2 = c - a;
+        let p = self.direction.cross(edge2);
+        let determinant = edge1.dot(p);
+        if determinant.abs() < EPSILON {
+            return None;
+        }
+        let inverse = 1.0 / determinant;
+        let s = self.origin - a;
+        let u = s.dot(p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(edge1);
+        let v = self.direction.dot(q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = edge2.dot(q) * inverse;
+        (t > EPSILON).then_some(t)
+    }
+
+    // Slab test, returns the entry distance or 0 when the origin is inside the box.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut t_min = 0.0f32;
+        let mut t_max = f32::INFINITY;
+        for axis in 0..3 {
+            let inverse = 1.0 / self.direction[axis];
+            let mut t0 = (aabb.min[axis] - self.origin[axis]) * inverse;
+            let mut t1 = (aabb.max[axis] - self.origin[axis]) * inverse;
+            if inverse < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // NaN (origin on a slab plane of a parallel ray) must not narrow the interval.
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some(t_min)
+    }
+
+    // Closest hit against an indexed triangle list, the bounds are checked first.
+    pub fn intersect_mesh(&self, positions: &[Point3<f32>], indices: &[u16], bounds: &Aabb) -> Option<f32> {
+        self.intersect_aabb(bounds)?;
+        indices.chunks_exact(3)
+            .filter_map(|triangle| self.intersect_triangle(
+                positions[triangle[0] as usize],
+                positions[triangle[1] as usize],
+                positions[triangle[2] as usize],
+            ))
+            .min_by(f32::total_cmp)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+impl Aabb {
+    pub fn from_points(points: &[Point3<f32>]) -> Self {
+        let infinity = f32::INFINITY;
+        let initial = Self {
+            min: Point3::new(infinity, infinity, infinity),
+            max: Point3::new(-infinity, -infinity, -infinity),
+        };
+        points.iter().fold(initial, |aabb, point| Self {
+            min: Point3::new(aabb.min.x.min(point.x), aabb.min.y.min(point.y), aabb.min.z.min(point.z)),
+            max: Point3::new(aabb.max.x.max(point.x), aabb.max.y.max(point.y), aabb.max.z.max(point.z)),
+        })
+    }
+
+    // Box around all eight corners moved by `matrix`, e.g. a mesh's bounds in world space.
+    pub fn transformed(&self, matrix: Matrix4<f32>) -> Self {
+        let corners: Vec<Point3<f32>> = (0..8)
+            .map(|corner| matrix.transform_point(Point3::new(
+                if corner & 1 == 0 { self.min.x } else { self.max.x },
+                if corner & 2 == 0 { self.min.y } else { self.max.y },
+                if corner & 4 == 0 { self.min.z } else { self.max.z },
+            )))
+            .collect();
+        Self::from_points(&corners)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle() -> [Point3<f32>; 3] {
+        [Point3::new(-1.0, -1.0, 0.0), Point3::new(1.0, -1.0, 0.0), Point3::new(0.0, 1.0, 0.0)]
+    }
+
+    #[test]
+    fn ray_hits_triangle_in_front() {
+        let [a, b, c] = triangle();
+        let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        let t = ray.intersect_triangle(a, b, c).unwrap();
+        assert!((t - 5.0).abs() < 1e-5);
+        assert!((ray.at(t) - Point3::new(0.0, 0.0, 0.0)).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn ray_misses_triangle() {
+        let [a, b, c] = triangle();
+        let outside = Ray::new(Point3::new(2.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        let behind = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, 1.0));
+        let parallel = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(outside.intersect_triangle(a, b, c), None);
+        assert_eq!(behind.intersect_triangle(a, b, c), None);
+        assert_eq!(parallel.intersect_triangle(a, b, c), None);
+    }
+
+    #[test]
+    fn ray_hits_and_misses_aabb() {
+        let aabb = Aabb::from_points(&[Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0)]);
+        let hit = Ray::new(Point3::new(0.5, 0.5, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        let miss = Ray::new(Point3::new(2.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        let inside = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 0.0));
+        let away = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, 1.0));
+        assert!((hit.intersect_aabb(&aabb).unwrap() - 4.0).abs() < 1e-5);
+        assert_eq!(miss.intersect_aabb(&aabb), None);
+        assert_eq!(inside.intersect_aabb(&aabb), Some(0.0));
+        assert_eq!(away.intersect_aabb(&aabb), None);
+    }
+
+    #[test]
+    fn transformed_aabb_contains_the_moved_corners() {
+        let aabb = Aabb::from_points(&[Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0)]);
+        let matrix = Matrix4::from_translation(Vector3::new(5.0, 0.0, 0.0))
+            * Matrix4::from_angle_y(cgmath::Deg(45.0));
+        let moved = aabb.transformed(matrix);
+        let half = 2.0f32.sqrt();
+        assert!((moved.min - Point3::new(5.0 - half, -1.0, -half)).magnitude() < 1e-5);
+        assert!((moved.max - Point3::new(5.0 + half, 1.0, half)).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn mesh_hit_is_the_closest_triangle() {
+        let positions = [
+            Point3::new(-1.0, -1.0, 0.0), Point3::new(1.0, -1.0, 0.0), Point3::new(0.0, 1.0, 0.0),
+            Point3::new(-1.0, -1.0, 1.0), Point3::new(1.0, -1.0, 1.0), Point3::new(0.0, 1.0, 1.0),
+        ];
+        let indices = [0, 1, 2, 3, 4, 5];
+        let bounds = Aabb::from_points(&positions);
+        let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        assert!((ray.intersect_mesh(&positions, &indices, &bounds).unwrap() - 4.0).abs() < 1e-5);
+    }
+}
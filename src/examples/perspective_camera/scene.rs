@@ -0,0 +1,272 @@
+use cgmath::{
+    EuclideanSpace,
+    InnerSpace,
+    Matrix4,
+    One,
+    Point3,
+    Quaternion,
+    SquareMatrix,
+    Transform as _,
+    Vector3,
+    Zero,
+};
+use crate::examples::perspective_camera::camera::Camera;
+
+// Local placement of a node relative to its parent, applied as scale, then rotation, then translation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: Vector3::zero(),
+            rotation: Quaternion::one(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+impl Transform {
+    pub fn matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.translation)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+impl NodeId {
+    // Nodes are never removed, the index is stable and dense, e.g. for a slot in a GPU buffer.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+// Light sitting at the node's world position, reaching `range` world units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointLight {
+    pub color: [f32; 3],
+    pub range: f32,
+}
+
+// Looks down the node's -Z axis with +Y up, the aspect comes from the window it is shown in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Projection {
+    pub fovy: f32,
+    pub znear: f32,
+    pub zfar: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Attachment {
+    // index into the application's meshes
+    Mesh(usize),
+    Light(PointLight),
+    Camera(Projection),
+}
+
+pub struct Node {
+    pub name: String,
+    pub attachment: Option<Attachment>,
+    local: Transform,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    world: Matrix4<f32>,
+    // local transform changed since the last `update`
+    dirty: bool,
+}
+
+// Hierarchy of nodes, each placed relative to its parent. World matrices are cached and only
+// recomputed by `update` for nodes whose transform, or an ancestor's transform, changed.
+#[derive(Default)]
+pub struct Scene {
+    nodes: Vec<Node>,
+    roots: Vec<NodeId>,
+}
+impl Scene {
+    pub fn add(&mut self, parent: Option<NodeId>, name: &str, local: Transform) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Node {
+            name: name.to_string(),
+            attachment: None,
+            local,
+            parent,
+            children: Vec::new(),
+            world: Matrix4::identity(),
+            dirty: true,
+        });
+        match parent {
+            Some(parent) => self.nodes[parent.0].children.push(id),
+            None => self.roots.push(id),
+        }
+        id
+    }
+
+    pub fn attach(&mut self, id: NodeId, attachment: Attachment) {
+        self.nodes[id.0].attachment = Some(attachment);
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    // Marks the node dirty, its subtree is moved on the next `update`.
+    pub fn transform_mut(&mut self, id: NodeId) -> &mut Transform {
+        let node = &mut self.nodes[id.0];
+        node.dirty = true;
+        &mut node.local
+    }
+
+    // As of the last `update`.
+    pub fn world(&self, id: NodeId) -> Matrix4<f32> {
+        self.nodes[id.0].world
+    }
+
+    // Recomputes the world matrices of dirty nodes and everything below them, parents before
+    // children. Returns the nodes that changed so only their data has to be uploaded.
+    pub fn update(&mut self) -> Vec<NodeId> {
+        let mut changed = Vec::new();
+        let mut stack: Vec<(NodeId, bool)> = self.roots.iter().rev().map(|&id| (id, false)).collect();
+        while let Some((id, parent_changed)) = stack.pop() {
+            let node = &self.nodes[id.0];
+            let recompute = node.dirty || parent_changed;
+            if recompute {
+                let parent_world = node.parent.map_or(Matrix4::identity(), |parent| self.nodes[parent.0].world);
+                let node = &mut self.nodes[id.0];
+                node.world = parent_world * node.local.matrix();
+                node.dirty = false;
+                changed.push(id);
+            }
+            stack.extend(self.nodes[id.0].children.iter().rev().map(|&child| (child, recompute)));
+        }
+        changed
+    }
+
+    // `root` and everything below it, depth first with parents before their children.
+    pub fn subtree(&self, root: NodeId) -> Vec<NodeId> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            nodes.push(id);
+            stack.extend(self.nodes[id.0].children.iter().rev());
+        }
+        nodes
+    }
+
+    // Every mesh drawn for `root`, in draw order.
+    pub fn meshes(&self, root: NodeId) -> Vec<(NodeId, usize)> {
+        self.subtree(root)
+            .into_iter()
+            .filter_map(|id| match self.nodes[id.0].attachment {
+                Some(Attachment::Mesh(mesh)) => Some((id, mesh)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // The first light below `root` and where it is in the world.
+    pub fn light(&self, root: NodeId) -> Option<(Point3<f32>, PointLight)> {
+        self.subtree(root)
+            .into_iter()
+            .find_map(|id| match self.nodes[id.0].attachment {
+                Some(Attachment::Light(light)) => Some((self.nodes[id.0].world.transform_point(Point3::origin()), light)),
+                _ => None,
+            })
+    }
+
+    // A camera for the first camera node below `root`, following the node's world matrix.
+    pub fn camera(&self, root: NodeId, aspect: f32) -> Option<Camera> {
+        self.subtree(root)
+            .into_iter()
+            .find_map(|id| match self.nodes[id.0].attachment {
+                Some(Attachment::Camera(projection)) => {
+                    let world = self.nodes[id.0].world;
+                    let eye = world.transform_point(Point3::origin());
+                    Some(Camera {
+                        eye,
+                        target: eye + world.transform_vector(-Vector3::unit_z()).normalize(),
+                        up: world.transform_vector(Vector3::unit_y()).normalize(),
+                        aspect,
+                        fovy: projection.fovy,
+                        znear: projection.znear,
+                        zfar: projection.zfar,
+                    })
+                }
+                _ => None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{
+        Deg,
+        Rotation3,
+    };
+
+    fn translation(x: f32, y: f32, z: f32) -> Transform {
+        Transform {
+            translation: Vector3::new(x, y, z),
+            ..Default::default()
+        }
+    }
+
+    fn position(scene: &Scene, id: NodeId) -> Point3<f32> {
+        scene.world(id).transform_point(Point3::origin())
+    }
+
+    #[test]
+    fn children_follow_their_parents() {
+        let mut scene = Scene::default();
+        let parent = scene.add(None, "Parent", Transform {
+            rotation: Quaternion::from_angle_y(Deg(90.0)),
+            scale: Vector3::new(2.0, 2.0, 2.0),
+            ..translation(1.0, 0.0, 0.0)
+        });
+        let child = scene.add(Some(parent), "Child", translation(1.0, 0.0, 0.0));
+        scene.update();
+        // One unit along the parent's x, rotated onto -z and scaled by two.
+        assert!((position(&scene, child) - Point3::new(1.0, 0.0, -2.0)).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn update_only_recomputes_dirty_subtrees() {
+        let mut scene = Scene::default();
+        let a = scene.add(None, "A", Transform::default());
+        let b = scene.add(Some(a), "B", translation(0.0, 1.0, 0.0));
+        let c = scene.add(Some(b), "C", translation(0.0, 1.0, 0.0));
+        let d = scene.add(None, "D", Transform::default());
+        assert_eq!(scene.update(), vec![a, b, c, d]);
+        assert!(scene.update().is_empty());
+
+        scene.transform_mut(b).translation.y = 5.0;
+        // Cached until the next update.
+        assert!((position(&scene, c) - Point3::new(0.0, 2.0, 0.0)).magnitude() < 1e-5);
+        assert_eq!(scene.update(), vec![b, c]);
+        assert!((position(&scene, c) - Point3::new(0.0, 6.0, 0.0)).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn attached_camera_looks_down_the_node_z_axis() {
+        let mut scene = Scene::default();
+        let root = scene.add(None, "Root", translation(0.0, 0.0, 3.0));
+        let eye = scene.add(Some(root), "Eye", Transform {
+            rotation: Quaternion::from_angle_y(Deg(90.0)),
+            ..Default::default()
+        });
+        scene.attach(eye, Attachment::Camera(Projection { fovy: 45.0, znear: 0.1, zfar: 100.0 }));
+        scene.update();
+        let camera = scene.camera(root, 1.0).unwrap();
+        assert!((camera.eye - Point3::new(0.0, 0.0, 3.0)).magnitude() < 1e-5);
+        assert!(((camera.target - camera.eye) - Vector3::new(-1.0, 0.0, 0.0)).magnitude() < 1e-5);
+        assert!(scene.light(root).is_none());
+    }
+}
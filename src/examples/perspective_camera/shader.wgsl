@@ -6,6 +6,14 @@ struct CameraUniform {
 @group(1) @binding(0) // 1.
 var<uniform> camera: CameraUniform;
 
+// world matrix of the scene node being drawn, tinted when it is selected
+struct ModelUniform {
+    model: mat4x4<f32>,
+    tint: vec4<f32>,
+};
+@group(2) @binding(0) // dynamic offset selects the node
+var<uniform> node: ModelUniform;
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) tex_coords: vec2<f32>,
@@ -14,6 +22,8 @@ struct VertexInput {
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
+    @location(1) world_position: vec3<f32>,
+    @location(2) tint: vec3<f32>,
 }
 
 @vertex
@@ -21,8 +31,11 @@ fn vs_main(
     model: VertexInput,
 ) -> VertexOutput {
     var out: VertexOutput;
+    let world_position = node.model * vec4<f32>(model.position, 1.0);
     out.tex_coords = model.tex_coords;
-    out.clip_position = camera.view_proj * vec4<f32>(model.position, 1.0); // 2.
+    out.world_position = world_position.xyz;
+    out.tint = node.tint.rgb;
+    out.clip_position = camera.view_proj * world_position; // 2.
     return out;
 }
 
@@ -32,7 +45,19 @@ var t_diffuse: texture_2d<f32>;
 @group(0) @binding(1)
 var s_diffuse: sampler;
 
+// the first light of the scene the view shows, no light is `ambient` 1 and black
+struct LightUniform {
+    position: vec3<f32>,
+    range: f32,
+    color: vec3<f32>,
+    ambient: f32,
+};
+@group(1) @binding(1)
+var<uniform> light: LightUniform;
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
-}
\ No newline at end of file
+    let color = textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    let falloff = clamp(1.0 - distance(light.position, in.world_position) / light.range, 0.0, 1.0);
+    return vec4<f32>(color.rgb * in.tint * (light.ambient + light.color * falloff * falloff), color.a);
+}
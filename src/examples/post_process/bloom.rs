@@ -0,0 +1,297 @@
+use crate::examples::perspective_camera::texture::Texture;
+use crate::input::{
+    Action,
+    Input,
+};
+use crate::examples::post_process::HDR_FORMAT;
+
+// Levels below the half resolution first mip, fewer when the window is small.
+const MAX_MIPS: u32 = 6;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct BloomUniform {
+    threshold: f32,
+    knee: f32,
+    intensity: f32,
+    radius: f32,
+}
+
+// Adds a glow around everything brighter than `threshold` to an `HDR_FORMAT` target, before it
+// is tone mapped. The target is read into a half resolution mip chain, blurred down and back up,
+// and the result is blended additively onto the target. Pass the new target to `resize`.
+pub struct Bloom {
+    pub enabled: bool,
+    // scale of the blurred light added to the scene
+    pub intensity: f32,
+    // brightness where the bright pass starts, in linear scene units
+    pub threshold: f32,
+    // spread of each upsample step, in fractions of the screen's height
+    pub radius: f32,
+    mips: Vec<wgpu::TextureView>,
+    buffer: wgpu::Buffer,
+    sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    // reads the target
+    target_bind_group: wgpu::BindGroup,
+    // `mip_bind_groups[i]` reads `mips[i]`
+    mip_bind_groups: Vec<wgpu::BindGroup>,
+    prefilter_pipeline: wgpu::RenderPipeline,
+    downsample_pipeline: wgpu::RenderPipeline,
+    upsample_pipeline: wgpu::RenderPipeline,
+    composite_pipeline: wgpu::RenderPipeline,
+}
+impl Bloom {
+    pub fn new(device: &wgpu::Device, target: &Texture) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Bloom Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("bloom.wgsl").into()),
+        });
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Bloom Buffer"),
+            size: size_of::<BloomUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let sampler = device.create_sampler(
+            &wgpu::SamplerDescriptor {
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }
+        );
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+            ],
+            label: Some("bloom_bind_group_layout"),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some("Bloom Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let additive = wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            // the target's alpha is left alone
+            alpha: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::Zero,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+        };
+        let create_pipeline = |entry_point: &str, blend: Option<wgpu::BlendState>| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(&format!("Bloom {entry_point} Pipeline")),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    compilation_options: Default::default(),
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some(entry_point),
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: HDR_FORMAT,
+                        blend,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            })
+        };
+        let prefilter_pipeline = create_pipeline("fs_prefilter", None);
+        let downsample_pipeline = create_pipeline("fs_downsample", None);
+        let upsample_pipeline = create_pipeline("fs_upsample", Some(additive));
+        let composite_pipeline = create_pipeline("fs_composite", Some(additive));
+        let target_bind_group = Self::create_bind_group(device, &bind_group_layout, &buffer, &sampler, &target.view);
+        let (mips, mip_bind_groups) = Self::create_mips(device, &bind_group_layout, &buffer, &sampler, target);
+
+        Self {
+            enabled: true,
+            intensity: 0.1,
+            threshold: 1.0,
+            radius: 0.004,
+            mips,
+            buffer,
+            sampler,
+            bind_group_layout,
+            target_bind_group,
+            mip_bind_groups,
+            prefilter_pipeline,
+            downsample_pipeline,
+            upsample_pipeline,
+            composite_pipeline,
+        }
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        buffer: &wgpu::Buffer,
+        sampler: &wgpu::Sampler,
+        source: &wgpu::TextureView,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(source)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: buffer.as_entire_binding()
+                },
+            ],
+            label: Some("bloom_bind_group"),
+        })
+    }
+
+    fn create_mips(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        buffer: &wgpu::Buffer,
+        sampler: &wgpu::Sampler,
+        target: &Texture,
+    ) -> (Vec<wgpu::TextureView>, Vec<wgpu::BindGroup>) {
+        let size = target.texture.size();
+        let width = (size.width / 2).max(1);
+        let height = (size.height / 2).max(1);
+        // stop before the smallest level gets under a few pixels
+        let mip_count = (width.min(height).max(1).ilog2()).saturating_sub(2).clamp(1, MAX_MIPS);
+        let texture = device.create_texture(
+            &wgpu::TextureDescriptor {
+                label: Some("bloom_mips"),
+                size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+                mip_level_count: mip_count,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: HDR_FORMAT,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[]
+            }
+        );
+        let mips: Vec<wgpu::TextureView> = (0..mip_count)
+            .map(|mip| texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("bloom_mip_view"),
+                base_mip_level: mip,
+                mip_level_count: Some(1),
+                ..Default::default()
+            }))
+            .collect();
+        let bind_groups = mips.iter()
+            .map(|mip| Self::create_bind_group(device, layout, buffer, sampler, mip))
+            .collect();
+        (mips, bind_groups)
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, target: &Texture) {
+        self.target_bind_group = Self::create_bind_group(device, &self.bind_group_layout, &self.buffer, &self.sampler, &target.view);
+        (self.mips, self.mip_bind_groups) = Self::create_mips(device, &self.bind_group_layout, &self.buffer, &self.sampler, target);
+    }
+
+    // F8 switches bloom off and on.
+    pub fn handle_input(&mut self, input: &Input) {
+        if input.pressed(Action::ToggleBloom) {
+            self.enabled = !self.enabled;
+            println!("Bloom {}", if self.enabled { "on" } else { "off" });
+        }
+    }
+
+    pub fn update(&self, queue: &wgpu::Queue) {
+        let uniform = BloomUniform {
+            threshold: self.threshold,
+            knee: 0.5 * self.threshold,
+            intensity: self.intensity,
+            radius: self.radius,
+        };
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    fn draw(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::RenderPipeline,
+        source: &wgpu::BindGroup,
+        destination: &wgpu::TextureView,
+        load: wgpu::LoadOp<wgpu::Color>,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Bloom Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: destination,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                }
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, source, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    // Run after the scene is drawn into `target`, before tone mapping.
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView) {
+        if !self.enabled {
+            return;
+        }
+        let clear = wgpu::LoadOp::Clear(wgpu::Color::BLACK);
+        self.draw(encoder, &self.prefilter_pipeline, &self.target_bind_group, &self.mips[0], clear);
+        for mip in 1..self.mips.len() {
+            self.draw(encoder, &self.downsample_pipeline, &self.mip_bind_groups[mip - 1], &self.mips[mip], clear);
+        }
+        for mip in (1..self.mips.len()).rev() {
+            self.draw(encoder, &self.upsample_pipeline, &self.mip_bind_groups[mip], &self.mips[mip - 1], wgpu::LoadOp::Load);
+        }
+        self.draw(encoder, &self.composite_pipeline, &self.mip_bind_groups[0], target, wgpu::LoadOp::Load);
+    }
+}
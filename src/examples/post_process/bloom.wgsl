@@ -0,0 +1,120 @@
+// Bloom after Jimenez, "Next Generation Post Processing in Call of Duty: Advanced Warfare":
+// a 13-tap downsample down the mip chain, then a 3x3 tent upsample added back up each level.
+
+struct BloomUniform {
+    threshold: f32,
+    // width of the soft transition below the threshold
+    knee: f32,
+    intensity: f32,
+    // spread of the upsample filter in uv units of the screen's height
+    radius: f32,
+};
+@group(0) @binding(0)
+var t_source: texture_2d<f32>;
+@group(0) @binding(1)
+var s_source: sampler;
+@group(0) @binding(2)
+var<uniform> bloom: BloomUniform;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let position = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(position, 0.0, 1.0);
+    out.uv = vec2<f32>(position.x + 1.0, 1.0 - position.y) * 0.5;
+    return out;
+}
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// Weights a group of taps down by its brightness so single very bright pixels don't flicker.
+fn karis_average(color: vec3<f32>) -> f32 {
+    return 1.0 / (1.0 + luminance(color));
+}
+
+fn tap(uv: vec2<f32>, texel: vec2<f32>, x: f32, y: f32) -> vec3<f32> {
+    return textureSampleLevel(t_source, s_source, uv + vec2<f32>(x, y) * texel, 0.0).rgb;
+}
+
+// The 13 taps as five overlapping 2x2 boxes, the center one weighted 0.5, the corners 0.125.
+fn downsample(uv: vec2<f32>, karis: bool) -> vec3<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(t_source));
+    let a = tap(uv, texel, -2.0, -2.0);
+    let b = tap(uv, texel, 0.0, -2.0);
+    let c = tap(uv, texel, 2.0, -2.0);
+    let d = tap(uv, texel, -2.0, 0.0);
+    let e = tap(uv, texel, 0.0, 0.0);
+    let f = tap(uv, texel, 2.0, 0.0);
+    let g = tap(uv, texel, -2.0, 2.0);
+    let h = tap(uv, texel, 0.0, 2.0);
+    let i = tap(uv, texel, 2.0, 2.0);
+    let j = tap(uv, texel, -1.0, -1.0);
+    let k = tap(uv, texel, 1.0, -1.0);
+    let l = tap(uv, texel, -1.0, 1.0);
+    let m = tap(uv, texel, 1.0, 1.0);
+
+    let boxes = array<vec3<f32>, 5>(
+        (j + k + l + m) * 0.25,
+        (a + b + d + e) * 0.25,
+        (b + c + e + f) * 0.25,
+        (d + e + g + h) * 0.25,
+        (e + f + h + i) * 0.25,
+    );
+    let weights = array<f32, 5>(0.5, 0.125, 0.125, 0.125, 0.125);
+    var color = vec3<f32>(0.0);
+    var total = 0.0;
+    for (var index = 0u; index < 5u; index++) {
+        var weight = weights[index];
+        if karis {
+            weight *= karis_average(boxes[index]);
+        }
+        color += boxes[index] * weight;
+        total += weight;
+    }
+    return color / total;
+}
+
+@fragment
+fn fs_prefilter(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = downsample(in.uv, true);
+    let brightness = max(color.r, max(color.g, color.b));
+    var soft = clamp(brightness - bloom.threshold + bloom.knee, 0.0, 2.0 * bloom.knee);
+    soft = soft * soft / (4.0 * bloom.knee + 0.00001);
+    let contribution = max(soft, brightness - bloom.threshold) / max(brightness, 0.00001);
+    return vec4<f32>(color * contribution, 1.0);
+}
+
+@fragment
+fn fs_downsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(downsample(in.uv, false), 1.0);
+}
+
+fn upsample(uv: vec2<f32>) -> vec3<f32> {
+    let size = vec2<f32>(textureDimensions(t_source));
+    let offset = vec2<f32>(bloom.radius * size.y / size.x, bloom.radius);
+    var color = tap(uv, offset, 0.0, 0.0) * 4.0;
+    color += (tap(uv, offset, 0.0, -1.0) + tap(uv, offset, -1.0, 0.0)
+        + tap(uv, offset, 1.0, 0.0) + tap(uv, offset, 0.0, 1.0)) * 2.0;
+    color += tap(uv, offset, -1.0, -1.0) + tap(uv, offset, 1.0, -1.0)
+        + tap(uv, offset, -1.0, 1.0) + tap(uv, offset, 1.0, 1.0);
+    return color / 16.0;
+}
+
+// Reads the smaller mip and is blended additively onto the next larger one, over its downsampled content.
+@fragment
+fn fs_upsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(upsample(in.uv), 1.0);
+}
+
+// Blended additively onto the scene.
+@fragment
+fn fs_composite(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(upsample(in.uv) * bloom.intensity, 1.0);
+}
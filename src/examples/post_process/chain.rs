@@ -0,0 +1,421 @@
+use anyhow::{
+    bail,
+    Result,
+};
+use image::RgbaImage;
+use crate::examples::perspective_camera::texture::Texture;
+use crate::input::{
+    Action,
+    Input,
+};
+
+// Entries per axis of the generated color grading LUT.
+const LUT_SIZE: u32 = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    Fxaa,
+    ChromaticAberration,
+    ColorGrading,
+    Vignette,
+    Grayscale,
+}
+impl Effect {
+    // Chain order, FXAA first so it smooths the tone mapped image before anything shifts colors.
+    pub const ALL: [Effect; 5] = [
+        Effect::Fxaa,
+        Effect::ChromaticAberration,
+        Effect::ColorGrading,
+        Effect::Vignette,
+        Effect::Grayscale,
+    ];
+
+    fn source(self) -> &'static str {
+        match self {
+            Effect::Fxaa => concat!(include_str!("effect.wgsl"), include_str!("effects/fxaa.wgsl")),
+            Effect::ChromaticAberration => concat!(include_str!("effect.wgsl"), include_str!("effects/chromatic_aberration.wgsl")),
+            Effect::ColorGrading => concat!(include_str!("effect.wgsl"), include_str!("effects/color_grading.wgsl")),
+            Effect::Vignette => concat!(include_str!("effect.wgsl"), include_str!("effects/vignette.wgsl")),
+            Effect::Grayscale => concat!(include_str!("effect.wgsl"), include_str!("effects/grayscale.wgsl")),
+        }
+    }
+
+    // See the header of each effect's shader for what the values mean.
+    fn default_params(self) -> [f32; 4] {
+        match self {
+            Effect::Fxaa => [8.0, 0.0, 0.0, 0.0],
+            Effect::ChromaticAberration => [0.012, 0.0, 0.0, 0.0],
+            Effect::ColorGrading => [1.0, 0.0, 0.0, 0.0],
+            Effect::Vignette => [0.45, 0.6, 0.9, 0.0],
+            Effect::Grayscale => [1.0, 0.0, 0.0, 0.0],
+        }
+    }
+
+    fn enabled_by_default(self) -> bool {
+        matches!(self, Effect::Fxaa | Effect::Vignette)
+    }
+
+    fn toggle_action(self) -> Action {
+        match self {
+            Effect::Fxaa => Action::ToggleFxaa,
+            Effect::ChromaticAberration => Action::ToggleChromaticAberration,
+            Effect::ColorGrading => Action::ToggleColorGrading,
+            Effect::Vignette => Action::ToggleVignette,
+            Effect::Grayscale => Action::ToggleGrayscale,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct EffectUniform {
+    params: [f32; 4],
+    linear_input: u32,
+    _padding: [u32; 3],
+}
+
+pub struct Pass {
+    pub effect: Effect,
+    pub enabled: bool,
+    pub params: [f32; 4],
+    buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    // one per ping-pong target, `bind_groups[i]` reads `targets[i]`
+    bind_groups: [wgpu::BindGroup; 2],
+    render_pipeline: wgpu::RenderPipeline,
+}
+
+// An ordered stack of full-screen passes. The previous stage renders into `input`, every
+// enabled pass reads the last result and writes the other target, the last one writes the
+// output. Targets are the output's size and format, recreate them with `resize`.
+pub struct PostProcessChain {
+    pub passes: Vec<Pass>,
+    targets: [Texture; 2],
+    format: wgpu::TextureFormat,
+    lut: Texture,
+}
+impl PostProcessChain {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+        effects: &[Effect],
+    ) -> Self {
+        let targets = Self::create_targets(device, format, width, height);
+        let lut = create_lut(device, queue, &graded_strip()).unwrap();
+        let passes = effects.iter()
+            .map(|effect| Self::create_pass(device, *effect, format, &targets, &lut))
+            .collect();
+
+        Self {
+            passes,
+            targets,
+            format,
+            lut,
+        }
+    }
+
+    fn create_targets(device: &wgpu::Device, format: wgpu::TextureFormat, width: u32, height: u32) -> [Texture; 2] {
+        [
+            Texture::create_render_target(device, "post_process_target_0", width, height, format),
+            Texture::create_render_target(device, "post_process_target_1", width, height, format),
+        ]
+    }
+
+    fn create_pass(
+        device: &wgpu::Device,
+        effect: Effect,
+        format: wgpu::TextureFormat,
+        targets: &[Texture; 2],
+        lut: &Texture,
+    ) -> Pass {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(&format!("{effect:?} Shader")),
+            source: wgpu::ShaderSource::Wgsl(effect.source().into()),
+        });
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{effect:?} Buffer")),
+            size: size_of::<EffectUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut entries = vec![
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None
+                },
+                count: None
+            },
+        ];
+        if effect == Effect::ColorGrading {
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D3,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            });
+        }
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &entries,
+            label: Some("post_process_bind_group_layout"),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some(&format!("{effect:?} Pipeline Layout")),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(&format!("{effect:?} Pipeline")),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        let bind_groups = Self::create_bind_groups(device, effect, &bind_group_layout, &buffer, targets, lut);
+
+        Pass {
+            effect,
+            enabled: effect.enabled_by_default(),
+            params: effect.default_params(),
+            buffer,
+            bind_group_layout,
+            bind_groups,
+            render_pipeline,
+        }
+    }
+
+    fn create_bind_groups(
+        device: &wgpu::Device,
+        effect: Effect,
+        layout: &wgpu::BindGroupLayout,
+        buffer: &wgpu::Buffer,
+        targets: &[Texture; 2],
+        lut: &Texture,
+    ) -> [wgpu::BindGroup; 2] {
+        targets.each_ref().map(|target| {
+            let mut entries = vec![
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&target.view)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&target.sampler)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: buffer.as_entire_binding()
+                },
+            ];
+            if effect == Effect::ColorGrading {
+                entries.push(wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&lut.view)
+                });
+            }
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout,
+                entries: &entries,
+                label: Some("post_process_bind_group"),
+            })
+        })
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.targets = Self::create_targets(device, self.format, width, height);
+        for pass in &mut self.passes {
+            pass.bind_groups = Self::create_bind_groups(
+                device,
+                pass.effect,
+                &pass.bind_group_layout,
+                &pass.buffer,
+                &self.targets,
+                &self.lut,
+            );
+        }
+    }
+
+    pub fn toggle(&mut self, effect: Effect) {
+        for pass in self.passes.iter_mut().filter(|pass| pass.effect == effect) {
+            pass.enabled = !pass.enabled;
+            println!("{:?}: {}", effect, if pass.enabled { "on" } else { "off" });
+        }
+    }
+
+    // 1 to 5 toggle the effects, in chain order.
+    pub fn handle_input(&mut self, input: &Input) {
+        for effect in Effect::ALL {
+            if input.pressed(effect.toggle_action()) {
+                self.toggle(effect);
+            }
+        }
+    }
+
+    pub fn update(&self, queue: &wgpu::Queue) {
+        for pass in &self.passes {
+            let uniform = EffectUniform {
+                params: pass.params,
+                linear_input: self.format.is_srgb() as u32,
+                _padding: [0; 3],
+            };
+            queue.write_buffer(&pass.buffer, 0, bytemuck::cast_slice(&[uniform]));
+        }
+    }
+
+    // Where the previous stage renders: the chain's first target, or straight into
+    // `output` when every effect is off.
+    pub fn input<'a>(&'a self, output: &'a wgpu::TextureView) -> &'a wgpu::TextureView {
+        if self.passes.iter().any(|pass| pass.enabled) {
+            &self.targets[0].view
+        } else {
+            output
+        }
+    }
+
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+        let enabled: Vec<&Pass> = self.passes.iter().filter(|pass| pass.enabled).collect();
+        for (i, pass) in enabled.iter().enumerate() {
+            let view = if i + 1 == enabled.len() {
+                output
+            } else {
+                &self.targets[(i + 1) % 2].view
+            };
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(&format!("{:?} Pass", pass.effect)),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    }
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(&pass.render_pipeline);
+            render_pass.set_bind_group(0, &pass.bind_groups[i % 2], &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+}
+
+// Uploads a LUT in the common strip layout: `size` tiles of `size`x`size` side by side,
+// red along x inside a tile, green along y, blue selects the tile. Colors are sRGB encoded.
+pub fn create_lut(device: &wgpu::Device, queue: &wgpu::Queue, strip: &RgbaImage) -> Result<Texture> {
+    let size = strip.height();
+    if strip.width() != size * size {
+        bail!("LUT strip is {}x{}, expected {}x{size}", strip.width(), size, size * size);
+    }
+    let texture = device.create_texture(
+        &wgpu::TextureDescriptor {
+            label: Some("color_grading_lut"),
+            size: wgpu::Extent3d { width: size, height: size, depth_or_array_layers: size },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D3,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[]
+        }
+    );
+    // the strip's rows hold a row of every tile, the texture wants the tiles one after another
+    let mut data = Vec::with_capacity(strip.as_raw().len());
+    for blue in 0..size {
+        for green in 0..size {
+            let start = ((green * size * size + blue * size) * 4) as usize;
+            data.extend_from_slice(&strip.as_raw()[start..start + (size * 4) as usize]);
+        }
+    }
+    queue.write_texture(
+        wgpu::TexelCopyTextureInfo {
+            aspect: wgpu::TextureAspect::All,
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+        },
+        &data,
+        wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * size),
+            rows_per_image: Some(size),
+        },
+        texture.size(),
+    );
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let sampler = device.create_sampler(
+        &wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        }
+    );
+
+    Ok(Texture { texture, view, sampler })
+}
+
+// A warm, slightly more contrasted and saturated grade, standing in for a LUT made in an
+// image editor.
+fn graded_strip() -> RgbaImage {
+    RgbaImage::from_fn(LUT_SIZE * LUT_SIZE, LUT_SIZE, |x, y| {
+        let scale = (LUT_SIZE - 1) as f32;
+        let color = [(x % LUT_SIZE) as f32 / scale, y as f32 / scale, (x / LUT_SIZE) as f32 / scale];
+        let luma = 0.2126 * color[0] + 0.7152 * color[1] + 0.0722 * color[2];
+        let warmth = [1.06, 1.0, 0.9];
+        let graded = color.iter().zip(warmth).map(|(channel, warmth)| {
+            let saturated = luma + (channel - luma) * 1.15;
+            // smoothstep-like S-curve around mid gray
+            let contrasted = saturated + 0.15 * saturated * (1.0 - saturated) * (2.0 * saturated - 1.0);
+            ((contrasted * warmth).clamp(0.0, 1.0) * 255.0).round() as u8
+        });
+        let mut pixel = [255; 4];
+        for (channel, value) in pixel.iter_mut().zip(graded) {
+            *channel = value;
+        }
+        image::Rgba(pixel)
+    })
+}
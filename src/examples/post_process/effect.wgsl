@@ -0,0 +1,29 @@
+// Shared by every effect of the post-processing chain, the effect's fragment shader is appended.
+// `t_input` is the previous pass's result, `effect.params` is `Pass::params` in chain.rs.
+
+struct EffectUniform {
+    params: vec4<f32>,
+    // 1 when the chain's format is sRGB, so sampled colors are linear
+    linear_input: u32,
+};
+@group(0) @binding(0)
+var t_input: texture_2d<f32>;
+@group(0) @binding(1)
+var s_input: sampler;
+@group(0) @binding(2)
+var<uniform> effect: EffectUniform;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let position = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(position, 0.0, 1.0);
+    out.uv = vec2<f32>(position.x + 1.0, 1.0 - position.y) * 0.5;
+    return out;
+}
+
@@ -0,0 +1,11 @@
+// Lens fringes: red and blue are sampled pushed outwards and inwards, growing towards the edges.
+// params.x: offset at the corners in uv units
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let offset = (in.uv - 0.5) * effect.params.x;
+    let color = textureSample(t_input, s_input, in.uv);
+    let red = textureSample(t_input, s_input, in.uv + offset).r;
+    let blue = textureSample(t_input, s_input, in.uv - offset).b;
+    return vec4<f32>(red, color.g, blue, color.a);
+}
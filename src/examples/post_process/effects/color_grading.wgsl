@@ -0,0 +1,34 @@
+// Looks colors up in a 3D LUT indexed by sRGB encoded color, see `create_lut` in chain.rs.
+// params.x: blend between the input (0) and the graded color (1)
+
+@group(0) @binding(3)
+var t_lut: texture_3d<f32>;
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(t_input, s_input, in.uv);
+    var encoded = saturate(color.rgb);
+    if effect.linear_input == 1u {
+        encoded = linear_to_srgb(encoded);
+    }
+    // texel centers, so 0 and 1 hit the first and last entry instead of blending with the border
+    let size = f32(textureDimensions(t_lut).x);
+    let coordinates = encoded * (size - 1.0) / size + 0.5 / size;
+    var graded = textureSampleLevel(t_lut, s_input, coordinates, 0.0).rgb;
+    if effect.linear_input == 1u {
+        graded = srgb_to_linear(graded);
+    }
+    return vec4<f32>(mix(color.rgb, graded, effect.params.x), color.a);
+}
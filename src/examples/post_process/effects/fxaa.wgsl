@@ -0,0 +1,39 @@
+// FXAA, the compact variant of Lottes' algorithm: blur along the edge direction found from
+// the luma of the four diagonal neighbours, keep the narrower blur if the wider one overshoots.
+// params.x: longest blur in pixels
+
+fn fxaa_luma(color: vec3<f32>) -> f32 {
+    // edges are judged perceptually, not on linear values
+    return sqrt(dot(color, vec3<f32>(0.299, 0.587, 0.114)));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(t_input));
+    let color = textureSample(t_input, s_input, in.uv);
+    let luma_nw = fxaa_luma(textureSample(t_input, s_input, in.uv + vec2<f32>(-1.0, -1.0) * texel).rgb);
+    let luma_ne = fxaa_luma(textureSample(t_input, s_input, in.uv + vec2<f32>(1.0, -1.0) * texel).rgb);
+    let luma_sw = fxaa_luma(textureSample(t_input, s_input, in.uv + vec2<f32>(-1.0, 1.0) * texel).rgb);
+    let luma_se = fxaa_luma(textureSample(t_input, s_input, in.uv + vec2<f32>(1.0, 1.0) * texel).rgb);
+    let luma_m = fxaa_luma(color.rgb);
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    var direction = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * (1.0 / 8.0), 1.0 / 128.0);
+    let scale = 1.0 / (min(abs(direction.x), abs(direction.y)) + reduce);
+    direction = clamp(direction * scale, vec2<f32>(-effect.params.x), vec2<f32>(effect.params.x)) * texel;
+
+    let narrow = 0.5 * (
+        textureSample(t_input, s_input, in.uv + direction * (1.0 / 3.0 - 0.5)).rgb
+        + textureSample(t_input, s_input, in.uv + direction * (2.0 / 3.0 - 0.5)).rgb);
+    let wide = narrow * 0.5 + 0.25 * (
+        textureSample(t_input, s_input, in.uv - direction * 0.5).rgb
+        + textureSample(t_input, s_input, in.uv + direction * 0.5).rgb);
+    let luma_wide = fxaa_luma(wide);
+    let overshoots = luma_wide < luma_min || luma_wide > luma_max;
+    return vec4<f32>(select(wide, narrow, overshoots), color.a);
+}
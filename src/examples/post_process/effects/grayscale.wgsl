@@ -0,0 +1,8 @@
+// params.x: 0 keeps the colors, 1 is fully gray
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(t_input, s_input, in.uv);
+    let gray = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+    return vec4<f32>(mix(color.rgb, vec3<f32>(gray), effect.params.x), color.a);
+}
@@ -0,0 +1,12 @@
+// Darkens towards the corners.
+// params.x: strength, y: radius where darkening starts, z: width of the falloff
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(t_input, s_input, in.uv);
+    let size = vec2<f32>(textureDimensions(t_input));
+    // round on screen, whatever the aspect
+    let offset = (in.uv - 0.5) * size / min(size.x, size.y);
+    let falloff = smoothstep(effect.params.y, effect.params.y + effect.params.z, length(offset) * 2.0);
+    return vec4<f32>(color.rgb * (1.0 - effect.params.x * falloff), color.a);
+}
@@ -0,0 +1,6 @@
+pub mod bloom;
+pub mod chain;
+pub mod tone_mapping;
+
+// Scenes render into this before post-processing, wide enough that lighting is not clamped to 8 bits.
+pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
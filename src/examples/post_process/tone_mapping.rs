@@ -0,0 +1,203 @@
+use crate::examples::perspective_camera::texture::Texture;
+use crate::input::{
+    Action,
+    Input,
+};
+use crate::examples::post_process::HDR_FORMAT;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToneMapping {
+    Reinhard,
+    Aces,
+    AgX,
+}
+impl ToneMapping {
+    pub fn next(self) -> Self {
+        match self {
+            ToneMapping::Reinhard => ToneMapping::Aces,
+            ToneMapping::Aces => ToneMapping::AgX,
+            ToneMapping::AgX => ToneMapping::Reinhard,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ToneMappingUniform {
+    exposure: f32,
+    curve: u32,
+    encode_srgb: u32,
+    _padding: u32,
+}
+
+// Owns the `HDR_FORMAT` scene target and resolves it to the output format with a full-screen pass.
+// Scenes draw into `view`, `render` writes the tone mapped result. Recreate the target with `resize`.
+pub struct ToneMapper {
+    pub target: Texture,
+    pub tone_mapping: ToneMapping,
+    // in stops, 0 leaves the scene as is
+    pub exposure: f32,
+    output_format: wgpu::TextureFormat,
+    buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    render_pipeline: wgpu::RenderPipeline,
+}
+impl ToneMapper {
+    pub fn new(device: &wgpu::Device, output_format: wgpu::TextureFormat, width: u32, height: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Tone Mapping Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("tone_mapping.wgsl").into()),
+        });
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Tone Mapping Buffer"),
+            size: size_of::<ToneMappingUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("tone_mapping_bind_group_layout"),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor{
+            label: Some("Tone Mapping Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Tone Mapping Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(output_format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        let target = Texture::create_render_target(device, "hdr_target", width, height, HDR_FORMAT);
+        let bind_group = Self::create_bind_group(device, &bind_group_layout, &buffer, &target);
+
+        Self {
+            target,
+            tone_mapping: ToneMapping::AgX,
+            exposure: 0.0,
+            output_format,
+            buffer,
+            bind_group_layout,
+            bind_group,
+            render_pipeline,
+        }
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        buffer: &wgpu::Buffer,
+        target: &Texture,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding()
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&target.view)
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&target.sampler)
+                },
+            ],
+            label: Some("tone_mapping_bind_group"),
+        })
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.target = Texture::create_render_target(device, "hdr_target", width, height, HDR_FORMAT);
+        self.bind_group = Self::create_bind_group(device, &self.bind_group_layout, &self.buffer, &self.target);
+    }
+
+    // F7 cycles the operators, +/- change the exposure by half a stop.
+    pub fn handle_input(&mut self, input: &Input) {
+        if input.pressed(Action::CycleToneMapping) {
+            self.tone_mapping = self.tone_mapping.next();
+            println!("Tone mapping: {:?}", self.tone_mapping);
+        }
+        let stops = input.pressed(Action::ExposureUp) as i32 - input.pressed(Action::ExposureDown) as i32;
+        if stops != 0 {
+            self.exposure += 0.5 * stops as f32;
+            println!("Exposure: {:+.1} EV", self.exposure);
+        }
+    }
+
+    pub fn update(&self, queue: &wgpu::Queue) {
+        let uniform = ToneMappingUniform {
+            exposure: self.exposure.exp2(),
+            curve: self.tone_mapping as u32,
+            encode_srgb: !self.output_format.is_srgb() as u32,
+            _padding: 0,
+        };
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Tone Mapping Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                }
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,96 @@
+// Maps the linear HDR scene to display range. Operators follow the usual published fits:
+// Reinhard on luminance, Narkowicz's ACES filmic curve and the minimal AgX of Wrensch.
+
+const TONE_MAPPING_REINHARD: u32 = 0u;
+const TONE_MAPPING_ACES: u32 = 1u;
+const TONE_MAPPING_AGX: u32 = 2u;
+
+struct ToneMappingUniform {
+    // linear multiplier, 2^EV
+    exposure: f32,
+    curve: u32,
+    // 1 when the output format is not sRGB and the shader has to encode
+    encode_srgb: u32,
+};
+@group(0) @binding(0)
+var<uniform> tone_mapping: ToneMappingUniform;
+@group(0) @binding(1)
+var t_scene: texture_2d<f32>;
+@group(0) @binding(2)
+var s_scene: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let position = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(position, 0.0, 1.0);
+    out.uv = vec2<f32>(position.x + 1.0, 1.0 - position.y) * 0.5;
+    return out;
+}
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+fn reinhard(color: vec3<f32>) -> vec3<f32> {
+    return color / (1.0 + luminance(color));
+}
+
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    // the fit expects the exposure ACES uses, 0.6 of ours
+    let x = color * 0.6;
+    return saturate((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14));
+}
+
+fn agx_contrast(x: vec3<f32>) -> vec3<f32> {
+    let x2 = x * x;
+    let x4 = x2 * x2;
+    return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232;
+}
+
+fn agx(color: vec3<f32>) -> vec3<f32> {
+    let inset = mat3x3<f32>(
+        0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+        0.0784335999999992, 0.878468636469772, 0.0784336,
+        0.0792237451477643, 0.0791661274605434, 0.879142973793104,
+    );
+    let outset = mat3x3<f32>(
+        1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+        -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+        -0.0990297440797205, -0.0989611768448433, 1.15107367264116,
+    );
+    let min_ev = -12.47393;
+    let max_ev = 4.026069;
+    var x = inset * max(color, vec3<f32>(1e-10));
+    x = clamp((log2(x) - min_ev) / (max_ev - min_ev), vec3<f32>(0.0), vec3<f32>(1.0));
+    x = outset * agx_contrast(x);
+    // the curve ends in display encoding, undo it for the sRGB target
+    return pow(max(x, vec3<f32>(0.0)), vec3<f32>(2.2));
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let hdr = textureSample(t_scene, s_scene, in.uv).rgb * tone_mapping.exposure;
+    var color: vec3<f32>;
+    switch tone_mapping.curve {
+        case TONE_MAPPING_REINHARD: { color = reinhard(hdr); }
+        case TONE_MAPPING_ACES: { color = aces(hdr); }
+        case TONE_MAPPING_AGX, default: { color = agx(hdr); }
+    }
+    color = saturate(color);
+    if tone_mapping.encode_srgb == 1u {
+        color = linear_to_srgb(color);
+    }
+    return vec4<f32>(color, 1.0);
+}
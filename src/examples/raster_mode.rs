@@ -0,0 +1,91 @@
+use crate::input::{
+    Action,
+    Input,
+};
+
+// How triangles are rasterized, for every pipeline made with `RasterPipelines`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RasterMode {
+    Fill,
+    Wireframe,
+    Points,
+}
+impl RasterMode {
+    pub fn next(self) -> Self {
+        match self {
+            RasterMode::Fill => RasterMode::Wireframe,
+            RasterMode::Wireframe => RasterMode::Points,
+            RasterMode::Points => RasterMode::Fill,
+        }
+    }
+
+    // Cycles on `Action::CycleRasterMode` (F10 by default), returns true when it did.
+    pub fn handle_input(&mut self, input: &Input) -> bool {
+        if !input.pressed(Action::CycleRasterMode) {
+            return false;
+        }
+        *self = self.next();
+        println!("Raster mode: {self:?}");
+        true
+    }
+}
+
+// One variant of a render pipeline per `RasterMode`, all made from the same descriptor. Back faces
+// are drawn in wireframe and points so the whole mesh shows. The wireframe needs `POLYGON_MODE_LINE`,
+// without it `get` returns `None`. lighting and pbr then draw it with the barycentric shader of their
+// `DebugView`, the other examples draw filled with `get_or_fill` and say so in `handle_input`.
+pub struct RasterPipelines {
+    fill: wgpu::RenderPipeline,
+    wireframe: Option<wgpu::RenderPipeline>,
+    points: wgpu::RenderPipeline,
+}
+impl RasterPipelines {
+    pub fn new(device: &wgpu::Device, descriptor: &wgpu::RenderPipelineDescriptor) -> Self {
+        let variant = |mode: RasterMode, primitive: wgpu::PrimitiveState| {
+            let label = format!("{} ({mode:?})", descriptor.label.unwrap_or("Render Pipeline"));
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(&label),
+                primitive,
+                ..descriptor.clone()
+            })
+        };
+        let wireframe = device.features().contains(wgpu::Features::POLYGON_MODE_LINE).then(|| {
+            variant(RasterMode::Wireframe, wgpu::PrimitiveState {
+                polygon_mode: wgpu::PolygonMode::Line,
+                cull_mode: None,
+                ..descriptor.primitive
+            })
+        });
+        // A point list draws every index as a point, no `POLYGON_MODE_POINT` needed.
+        let points = variant(RasterMode::Points, wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::PointList,
+            strip_index_format: None,
+            cull_mode: None,
+            ..descriptor.primitive
+        });
+        Self {
+            fill: device.create_render_pipeline(descriptor),
+            wireframe,
+            points,
+        }
+    }
+
+    pub fn get(&self, mode: RasterMode) -> Option<&wgpu::RenderPipeline> {
+        match mode {
+            RasterMode::Fill => Some(&self.fill),
+            RasterMode::Wireframe => self.wireframe.as_ref(),
+            RasterMode::Points => Some(&self.points),
+        }
+    }
+
+    pub fn get_or_fill(&self, mode: RasterMode) -> &wgpu::RenderPipeline {
+        self.get(mode).unwrap_or(&self.fill)
+    }
+
+    // `RasterMode::handle_input` for examples drawing with `get_or_fill`.
+    pub fn handle_input(&self, mode: &mut RasterMode, input: &Input) {
+        if mode.handle_input(input) && self.get(*mode).is_none() {
+            println!("{mode:?} needs POLYGON_MODE_LINE, which this adapter does not have, drawing filled");
+        }
+    }
+}
@@ -6,7 +6,8 @@ use std::{
 use winit::{
     event_loop::{
         EventLoop,
-        ActiveEventLoop
+        ActiveEventLoop,
+        ControlFlow,
     },
     event::{
         DeviceEvent,
@@ -17,17 +18,13 @@ use winit::{
     window::{
         Window,
         WindowId,
-        WindowAttributes
-    },
-    dpi::{
-        LogicalSize,
-        Position,
-        PhysicalPosition,
     },
+    dpi::PhysicalSize,
     application::ApplicationHandler,
 };
 use async_std::task;
-use image::GenericImageView;
+use crate::input::Input;
+use crate::window::WindowConfig;
 use wgpu::util::DeviceExt;
 
 #[repr(C)]
@@ -73,13 +70,18 @@ const INDICES: &[u16] = &[
 ];
 
 struct Application<'window> {
+    window_config: WindowConfig,
     window: Option<Arc<Window>>,
+    input: Input,
     instance: wgpu::Instance,
     adapter: wgpu::Adapter,
     device: wgpu::Device,
     queue: wgpu::Queue,
     surface: Option<wgpu::Surface<'window>>,
     surface_config: Option<wgpu::SurfaceConfiguration>,
+    // drawn into and resolved to the frame, `None` with 1x MSAA
+    msaa_view: Option<wgpu::TextureView>,
+    sample_count: u32,
     shader: Option<wgpu::ShaderModule>,
     pipeline_layout: Option<wgpu::PipelineLayout>,
     render_pipeline: Option<wgpu::RenderPipeline>,
@@ -90,10 +92,7 @@ struct Application<'window> {
 
 impl<'window> Application<'window> {
     fn init(&mut self, event_loop: &ActiveEventLoop) {
-        let window = Arc::new(event_loop.create_window(WindowAttributes::default()
-            .with_title("My Window")
-            .with_inner_size(LogicalSize::new(512, 512))
-            .with_position(Position::Physical(PhysicalPosition::new(1800,500))))
+        let window = Arc::new(event_loop.create_window(self.window_config.attributes(event_loop))
             .expect("Failed to create window"));
 
         let surface = self.instance.create_surface(window.clone()).unwrap();
@@ -103,12 +102,13 @@ impl<'window> Application<'window> {
             format: surface_format,
             width: window.inner_size().width,
             height: window.inner_size().height,
-            present_mode: wgpu::PresentMode::Fifo,
+            present_mode: self.window_config.present_mode(),
             alpha_mode: wgpu::CompositeAlphaMode::Auto,
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
         };
         surface.configure(&self.device, &surface_config);
+        let sample_count = self.window_config.sample_count(&self.adapter, &self.device, &[surface_format]);
 
         // SHADER
         let shader = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -169,12 +169,12 @@ impl<'window> Application<'window> {
         );
 
         // RENDER PIPELINE
-        let pipeline_layout = Some(self.device.create_pipeline_layout(
+        let pipeline_layout = self.device.create_pipeline_layout(
             &wgpu::PipelineLayoutDescriptor {
                 label: Some("Pipeline Layout"),
                 bind_group_layouts: &[&texture_bind_group_layout],
                 push_constant_ranges: &[],
-            })).expect("Failed to create pipeline layout");
+            });
         let render_pipeline = self.device.create_render_pipeline(
             &wgpu::RenderPipelineDescriptor {
                 label: Some("Render Pipeline"),
@@ -208,7 +208,10 @@ impl<'window> Application<'window> {
                     ..wgpu::PrimitiveState::default()
                 },
                 depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    ..Default::default()
+                },
                 multiview: None,
                 cache: None,
         });
@@ -220,9 +223,41 @@ impl<'window> Application<'window> {
         self.pipeline_layout = Some(pipeline_layout);
         self.render_pipeline = Some(render_pipeline);
         self.surface_config = Some(surface_config);
+        self.sample_count = sample_count;
+        let surface_config = self.surface_config.as_ref().unwrap();
+        self.msaa_view = WindowConfig::create_msaa_view(&self.device, surface_config, surface_config.format, self.sample_count);
         self.diffuse_texture = Some(diffuse_texture);
         self.diffuse_bind_group = Some(diffuse_bind_group);
     }
+
+    // Surfaces are destroyed while the application is suspended, pipelines and buffers are kept.
+    fn create_surface(&mut self) {
+        let window = self.window.clone().unwrap();
+        let surface = self.instance.create_surface(window.clone()).unwrap();
+        let surface_config = self.surface_config.as_mut().unwrap();
+        surface_config.width = window.inner_size().width.max(1);
+        surface_config.height = window.inner_size().height.max(1);
+        surface.configure(&self.device, surface_config);
+        self.surface = Some(surface);
+        let surface_config = self.surface_config.as_ref().unwrap();
+        self.msaa_view = WindowConfig::create_msaa_view(&self.device, surface_config, surface_config.format, self.sample_count);
+        window.request_redraw();
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>) {
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+        let surface_config = self.surface_config.as_mut().unwrap();
+        surface_config.width = size.width;
+        surface_config.height = size.height;
+        if let Some(surface) = self.surface.as_ref() {
+            surface.configure(&self.device, surface_config);
+        }
+        let surface_config = self.surface_config.as_ref().unwrap();
+        self.msaa_view = WindowConfig::create_msaa_view(&self.device, surface_config, surface_config.format, self.sample_count);
+        println!("Window resized: {:?}", size);
+    }
 }
 impl ApplicationHandler for Application<'_> {
     fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
@@ -230,24 +265,30 @@ impl ApplicationHandler for Application<'_> {
     }
 
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        if self.window.is_none() { &self.init(event_loop); }
+        if self.window.is_none() { self.init(event_loop); } else { self.create_surface(); }
     }
 
-    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: ()) {}
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, _event: ()) {}
 
-    fn window_event(&mut self, event_loop: &ActiveEventLoop, window_id: WindowId, event: WindowEvent) {
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _window_id: WindowId, event: WindowEvent) {
+        self.input.handle_window_event(&event);
         match event {
-            WindowEvent::CloseRequested => { event_loop.exit(); }
-            WindowEvent::RedrawRequested => {
+            WindowEvent::CloseRequested => {
                 if let Some(window) = self.window.as_ref() {
-                    let frame = self.surface.as_ref().unwrap().get_current_texture().unwrap();
+                    self.window_config.store(window);
+                }
+                event_loop.exit();
+            }
+            WindowEvent::RedrawRequested => {
+                if let Some(surface) = self.surface.as_ref() {
+                    let frame = surface.get_current_texture().unwrap();
                     let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
                     let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {label: Some("Render Encoder")});
                     let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                         label: Some("Render Pass"),
                         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                            view: &view,
-                            resolve_target: None,
+                            view: self.msaa_view.as_ref().unwrap_or(&view),
+                            resolve_target: self.msaa_view.as_ref().map(|_| &view),
                             ops: wgpu::Operations {
                                 load: wgpu::LoadOp::Clear(wgpu::Color {
                                     r: 0.0,
@@ -299,35 +340,46 @@ impl ApplicationHandler for Application<'_> {
                     println!("Redraw Requested");
                 }
             }
-            WindowEvent::Resized(size) => {
-                self.surface_config.as_mut().unwrap().width = size.width;
-                self.surface_config.as_mut().unwrap().height = size.height;
-                self.surface.as_ref().unwrap().configure(&self.device, &self.surface_config.as_ref().unwrap());
-                println!("Window resized: {:?}", size);
-            }
+            WindowEvent::Resized(size) => { self.resize(size); }
             _ => {}
         }
     }
 
-    fn device_event(&mut self, event_loop: &ActiveEventLoop, device_id: DeviceId, event: DeviceEvent) {}
+    fn device_event(&mut self, _event_loop: &ActiveEventLoop, _device_id: DeviceId, event: DeviceEvent) {
+        self.input.handle_device_event(&event);
+    }
 
-    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {}
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.input.begin_frame();
+        if let Some(window) = self.window.clone()
+            && self.window_config.handle_input(&window, &self.input) {
+            self.resize(window.inner_size());
+        }
+        // A replay has no events to wake the loop up.
+        event_loop.set_control_flow(if self.input.replaying() { ControlFlow::Poll } else { ControlFlow::Wait });
+        self.input.end_frame();
+    }
 
-    fn suspended(&mut self, event_loop: &ActiveEventLoop) {}
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        self.surface = None;
+    }
 
-    fn exiting(&mut self, event_loop: &ActiveEventLoop) {}
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {}
 
-    fn memory_warning(&mut self, event_loop: &ActiveEventLoop) {}
+    fn memory_warning(&mut self, _event_loop: &ActiveEventLoop) {}
 }
 
-pub fn run() {
+pub fn run(window_config: WindowConfig, input: Input) {
     // winit
     let event_loop = EventLoop::new().unwrap();
 
     // wgpu
     let instance= wgpu::Instance::new(&wgpu::InstanceDescriptor { backends: wgpu::Backends::all(), ..Default::default()});
     let adapter= task::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())).unwrap();
-    let (device, queue)= task::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None, )).unwrap();
+    let (device, queue)= task::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+        required_features: WindowConfig::msaa_features(&adapter),
+        ..Default::default()
+    }, None, )).unwrap();
 
     // buffers
     let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -342,9 +394,13 @@ pub fn run() {
     });
 
     let mut application = Application{
+        window_config,
         window: None,
+        input,
         surface: None,
         surface_config: None,
+        msaa_view: None,
+        sample_count: 1,
         instance,
         adapter,
         device,
@@ -0,0 +1,232 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+};
+use anyhow::{
+    bail,
+    Context,
+    Error,
+    Result,
+};
+use serde::{
+    de::{
+        value::StrDeserializer,
+        IntoDeserializer,
+    },
+    Deserialize,
+};
+use winit::{
+    event::MouseButton,
+    keyboard::{
+        KeyCode,
+        ModifiersState,
+    },
+};
+
+// Everything the examples react to. Bindings are looked up by action,
+// so rebinding a key never touches example code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    CameraForward,
+    CameraBackward,
+    CameraLeft,
+    CameraRight,
+    CameraUp,
+    CameraDown,
+    CameraOrbit,
+    Select,
+    ToggleFullscreen,
+    ToggleExclusiveFullscreen,
+    ToggleTextureViewer,
+    TogglePickingMode,
+    CycleShading,
+    ToggleEnvironment,
+    ToggleShadowDebug,
+    CycleToneMapping,
+    ExposureUp,
+    ExposureDown,
+    ToggleFxaa,
+    ToggleChromaticAberration,
+    ToggleColorGrading,
+    ToggleVignette,
+    ToggleGrayscale,
+    ToggleBloom,
+    ToggleGpuCulling,
+    CycleRasterMode,
+    CycleDebugVectors,
+    ParticleCountUp,
+    ParticleCountDown,
+    NextEmitterParameter,
+    EmitterParameterUp,
+    EmitterParameterDown,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+// A key or mouse button plus the modifiers that have to be held with it,
+// written as `Alt+Enter`, `KeyW` or `MouseRight` in the config file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Binding {
+    pub modifiers: ModifiersState,
+    pub trigger: Trigger,
+}
+impl std::str::FromStr for Binding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let trigger = parts.pop().filter(|part| !part.is_empty())
+            .with_context(|| format!("Empty binding `{s}`"))?;
+        let mut modifiers = ModifiersState::empty();
+        for part in parts {
+            modifiers |= match part {
+                "Shift" => ModifiersState::SHIFT,
+                "Ctrl" | "Control" => ModifiersState::CONTROL,
+                "Alt" => ModifiersState::ALT,
+                "Super" => ModifiersState::SUPER,
+                _ => bail!("Unknown modifier `{part}` in `{s}`"),
+            };
+        }
+        let trigger = match trigger {
+            "MouseLeft" => Trigger::Mouse(MouseButton::Left),
+            "MouseRight" => Trigger::Mouse(MouseButton::Right),
+            "MouseMiddle" => Trigger::Mouse(MouseButton::Middle),
+            "MouseBack" => Trigger::Mouse(MouseButton::Back),
+            "MouseForward" => Trigger::Mouse(MouseButton::Forward),
+            // Key names are winit's `KeyCode` variants: KeyW, ArrowUp, F11, Enter, ...
+            key => {
+                let deserializer: StrDeserializer<serde::de::value::Error> = key.into_deserializer();
+                Trigger::Key(KeyCode::deserialize(deserializer)
+                    .with_context(|| format!("Unknown key `{key}` in `{s}`"))?)
+            }
+        };
+        Ok(Self { modifiers, trigger })
+    }
+}
+
+// Parsed when an `ActionMap` is created, every one of them has to be a valid `Binding`.
+const DEFAULT_BINDINGS: &[(Action, &[&str])] = &[
+    (Action::CameraForward, &["KeyW", "ArrowUp"]),
+    (Action::CameraBackward, &["KeyS", "ArrowDown"]),
+    (Action::CameraLeft, &["KeyA", "ArrowLeft"]),
+    (Action::CameraRight, &["KeyD", "ArrowRight"]),
+    (Action::CameraUp, &["Space"]),
+    (Action::CameraDown, &["ShiftLeft"]),
+    (Action::CameraOrbit, &["MouseRight"]),
+    (Action::Select, &["MouseLeft"]),
+    (Action::ToggleFullscreen, &["F11"]),
+    (Action::ToggleExclusiveFullscreen, &["Alt+Enter"]),
+    (Action::ToggleTextureViewer, &["F2"]),
+    (Action::TogglePickingMode, &["F3"]),
+    (Action::CycleShading, &["F4"]),
+    (Action::ToggleEnvironment, &["F5"]),
+    (Action::ToggleShadowDebug, &["F6"]),
+    (Action::CycleToneMapping, &["F7"]),
+    (Action::ExposureUp, &["Equal", "NumpadAdd"]),
+    (Action::ExposureDown, &["Minus", "NumpadSubtract"]),
+    (Action::ToggleFxaa, &["Digit1"]),
+    (Action::ToggleChromaticAberration, &["Digit2"]),
+    (Action::ToggleColorGrading, &["Digit3"]),
+    (Action::ToggleVignette, &["Digit4"]),
+    (Action::ToggleGrayscale, &["Digit5"]),
+    (Action::ToggleBloom, &["F8"]),
+    (Action::ToggleGpuCulling, &["F9"]),
+    (Action::CycleRasterMode, &["F10"]),
+    (Action::CycleDebugVectors, &["F12"]),
+    (Action::ParticleCountUp, &["BracketRight"]),
+    (Action::ParticleCountDown, &["BracketLeft"]),
+    (Action::NextEmitterParameter, &["Tab"]),
+    (Action::EmitterParameterUp, &["Period"]),
+    (Action::EmitterParameterDown, &["Comma"]),
+];
+
+#[derive(Clone, Debug)]
+pub struct ActionMap {
+    bindings: HashMap<Action, Vec<Binding>>,
+}
+impl Default for ActionMap {
+    fn default() -> Self {
+        let bindings = DEFAULT_BINDINGS.iter()
+            .map(|(action, bindings)| {
+                (*action, bindings.iter().map(|binding| binding.parse().unwrap()).collect())
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+impl ActionMap {
+    // Actions listed in the file replace their default bindings, the others keep them.
+    //
+    // [bindings]
+    // camera_forward = ["KeyW", "ArrowUp"]
+    // toggle_exclusive_fullscreen = ["Alt+Enter"]
+    pub fn load(path: &Path) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct File {
+            bindings: HashMap<Action, Vec<String>>,
+        }
+
+        let mut action_map = Self::default();
+        if !path.exists() {
+            return Ok(action_map);
+        }
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let file: File = toml::from_str(&text)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        for (action, bindings) in file.bindings {
+            let bindings = bindings.iter()
+                .map(|binding| binding.parse())
+                .collect::<Result<Vec<Binding>>>()?;
+            action_map.bindings.insert(action, bindings);
+        }
+        Ok(action_map)
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_default_binding_parses() {
+        for (action, bindings) in DEFAULT_BINDINGS {
+            for binding in *bindings {
+                assert!(binding.parse::<Binding>().is_ok(), "default binding `{binding}` of {action:?} does not parse");
+            }
+        }
+        let action_map = ActionMap::default();
+        assert_eq!(action_map.bindings(Action::ToggleExclusiveFullscreen), &[Binding {
+            modifiers: ModifiersState::ALT,
+            trigger: Trigger::Key(KeyCode::Enter),
+        }]);
+        assert_eq!(action_map.bindings(Action::CameraOrbit), &[Binding {
+            modifiers: ModifiersState::empty(),
+            trigger: Trigger::Mouse(MouseButton::Right),
+        }]);
+    }
+
+    #[test]
+    fn bindings_combine_modifiers() {
+        let binding: Binding = "Ctrl + Shift+KeyS".parse().unwrap();
+        assert_eq!(binding.modifiers, ModifiersState::CONTROL | ModifiersState::SHIFT);
+        assert_eq!(binding.trigger, Trigger::Key(KeyCode::KeyS));
+    }
+
+    #[test]
+    fn unknown_keys_and_modifiers_are_rejected() {
+        for bad in ["", "Alt+", "KeyWW", "W", "Hyper+KeyW", "MouseSide"] {
+            assert!(bad.parse::<Binding>().is_err(), "`{bad}` was accepted");
+        }
+    }
+}
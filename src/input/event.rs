@@ -0,0 +1,63 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use winit::{
+    event::{
+        DeviceEvent,
+        ElementState,
+        MouseButton,
+        MouseScrollDelta,
+        WindowEvent,
+    },
+    keyboard::{
+        KeyCode,
+        ModifiersState,
+        PhysicalKey,
+    },
+};
+
+// Lines per scroll "click" when the platform reports pixels instead.
+const PIXELS_PER_LINE: f32 = 38.0;
+
+// The part of winit's `WindowEvent`/`DeviceEvent` that `Input` cares about.
+// Unlike the winit events it can be serialized, which is what recordings are made of.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InputEvent {
+    Key { key: KeyCode, state: ElementState, repeat: bool },
+    MouseButton { button: MouseButton, state: ElementState },
+    Modifiers(ModifiersState),
+    CursorMoved { x: f64, y: f64 },
+    CursorLeft,
+    Scroll(f32),
+    Focused(bool),
+    MouseMotion { dx: f64, dy: f64 },
+}
+impl InputEvent {
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => match event.physical_key {
+                PhysicalKey::Code(key) => Some(Self::Key { key, state: event.state, repeat: event.repeat }),
+                PhysicalKey::Unidentified(_) => None,
+            },
+            WindowEvent::MouseInput { state, button, .. } => Some(Self::MouseButton { button: *button, state: *state }),
+            WindowEvent::ModifiersChanged(modifiers) => Some(Self::Modifiers(modifiers.state())),
+            WindowEvent::CursorMoved { position, .. } => Some(Self::CursorMoved { x: position.x, y: position.y }),
+            WindowEvent::CursorLeft { .. } => Some(Self::CursorLeft),
+            WindowEvent::MouseWheel { delta, .. } => Some(Self::Scroll(match delta {
+                MouseScrollDelta::LineDelta(_, y) => *y,
+                MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_LINE,
+            })),
+            WindowEvent::Focused(focused) => Some(Self::Focused(*focused)),
+            _ => None,
+        }
+    }
+
+    // Raw, unaccelerated mouse motion, only reported by winit as a device event.
+    pub fn from_device_event(event: &DeviceEvent) -> Option<Self> {
+        match event {
+            DeviceEvent::MouseMotion { delta } => Some(Self::MouseMotion { dx: delta.0, dy: delta.1 }),
+            _ => None,
+        }
+    }
+}
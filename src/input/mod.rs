@@ -0,0 +1,283 @@
+mod actions;
+mod event;
+mod recording;
+
+pub use actions::{
+    Action,
+    ActionMap,
+};
+
+use std::{
+    collections::HashSet,
+    path::Path,
+    time::Instant,
+};
+use actions::Trigger;
+use anyhow::Result;
+use event::InputEvent;
+use recording::{
+    Player,
+    Recorder,
+};
+use winit::{
+    dpi::PhysicalPosition,
+    event::{
+        DeviceEvent,
+        ElementState,
+        MouseButton,
+        WindowEvent,
+    },
+    keyboard::{
+        KeyCode,
+        ModifiersState,
+    },
+};
+
+// Keyboard and mouse state collected from winit events.
+// `pressed`/`released` only hold for the frame the transition happened in,
+// `held` stays true until the button goes up. A frame is everything between
+// `begin_frame` and `end_frame`, usually the body of `about_to_wait`.
+//
+// Input can be recorded to a file and replayed later, live events are ignored
+// while a replay is running and `dt` comes from the recording.
+pub struct Input {
+    action_map: ActionMap,
+    keys_held: HashSet<KeyCode>,
+    keys_pressed: HashSet<KeyCode>,
+    keys_released: HashSet<KeyCode>,
+    buttons_held: HashSet<MouseButton>,
+    buttons_pressed: HashSet<MouseButton>,
+    buttons_released: HashSet<MouseButton>,
+    modifiers: ModifiersState,
+    cursor_position: Option<PhysicalPosition<f64>>,
+    mouse_motion: (f64, f64),
+    scroll: f32,
+    last_frame: Instant,
+    dt: f32,
+    recorder: Option<Recorder>,
+    player: Option<Player>,
+}
+impl Input {
+    pub fn new(action_map: ActionMap) -> Self {
+        Self {
+            action_map,
+            keys_held: HashSet::new(),
+            keys_pressed: HashSet::new(),
+            keys_released: HashSet::new(),
+            buttons_held: HashSet::new(),
+            buttons_pressed: HashSet::new(),
+            buttons_released: HashSet::new(),
+            modifiers: ModifiersState::empty(),
+            cursor_position: None,
+            mouse_motion: (0.0, 0.0),
+            scroll: 0.0,
+            last_frame: Instant::now(),
+            dt: 0.0,
+            recorder: None,
+            player: None,
+        }
+    }
+
+    pub fn record(&mut self, path: &Path) -> Result<()> {
+        self.recorder = Some(Recorder::create(path)?);
+        Ok(())
+    }
+
+    pub fn replay(&mut self, path: &Path) -> Result<()> {
+        self.player = Some(Player::load(path)?);
+        Ok(())
+    }
+
+    pub fn replaying(&self) -> bool {
+        self.player.is_some()
+    }
+
+    pub fn handle_window_event(&mut self, event: &WindowEvent) {
+        if let Some(event) = InputEvent::from_window_event(event).filter(|_| !self.replaying()) {
+            self.apply(event);
+        }
+    }
+
+    pub fn handle_device_event(&mut self, event: &DeviceEvent) {
+        if let Some(event) = InputEvent::from_device_event(event).filter(|_| !self.replaying()) {
+            self.apply(event);
+        }
+    }
+
+    // Measures the frame time, or feeds the next recorded frame when replaying.
+    pub fn begin_frame(&mut self) {
+        let now = Instant::now();
+        self.dt = (now - self.last_frame).as_secs_f32().min(0.1);
+        self.last_frame = now;
+
+        let Some(player) = self.player.as_mut() else {
+            return;
+        };
+        match player.next_frame() {
+            Some(frame) => {
+                self.dt = frame.dt;
+                frame.events.into_iter().for_each(|event| self.apply(event));
+            }
+            None => {
+                println!("Replay finished");
+                self.player = None;
+            }
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        if let Some(recorder) = self.recorder.as_mut()
+            && let Err(error) = recorder.end_frame(self.dt) {
+            eprintln!("Recording stopped: {error:#}");
+            self.recorder = None;
+        }
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        self.buttons_pressed.clear();
+        self.buttons_released.clear();
+        self.mouse_motion = (0.0, 0.0);
+        self.scroll = 0.0;
+    }
+
+    fn apply(&mut self, event: InputEvent) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.push(event);
+        }
+        match event {
+            InputEvent::Key { key, state: ElementState::Pressed, repeat: false } => {
+                self.keys_held.insert(key);
+                self.keys_pressed.insert(key);
+            }
+            InputEvent::Key { state: ElementState::Pressed, .. } => {}
+            InputEvent::Key { key, state: ElementState::Released, .. } => {
+                self.keys_held.remove(&key);
+                self.keys_released.insert(key);
+            }
+            InputEvent::MouseButton { button, state: ElementState::Pressed } => {
+                self.buttons_held.insert(button);
+                self.buttons_pressed.insert(button);
+            }
+            InputEvent::MouseButton { button, state: ElementState::Released } => {
+                self.buttons_held.remove(&button);
+                self.buttons_released.insert(button);
+            }
+            InputEvent::Modifiers(modifiers) => { self.modifiers = modifiers; }
+            InputEvent::CursorMoved { x, y } => { self.cursor_position = Some(PhysicalPosition::new(x, y)); }
+            InputEvent::CursorLeft => { self.cursor_position = None; }
+            InputEvent::Scroll(lines) => { self.scroll += lines; }
+            // Nothing is released while the window is unfocused, forget what was held.
+            InputEvent::Focused(false) => {
+                self.keys_held.clear();
+                self.buttons_held.clear();
+                self.modifiers = ModifiersState::empty();
+            }
+            InputEvent::Focused(true) => {}
+            InputEvent::MouseMotion { dx, dy } => {
+                self.mouse_motion.0 += dx;
+                self.mouse_motion.1 += dy;
+            }
+        }
+    }
+
+    // Seconds since the previous frame, the recorded value while replaying.
+    pub fn dt(&self) -> f32 {
+        self.dt
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.any_binding(action, |trigger| match trigger {
+            Trigger::Key(key) => self.keys_pressed.contains(&key),
+            Trigger::Mouse(button) => self.buttons_pressed.contains(&button),
+        })
+    }
+
+    pub fn held(&self, action: Action) -> bool {
+        self.any_binding(action, |trigger| match trigger {
+            Trigger::Key(key) => self.keys_held.contains(&key),
+            Trigger::Mouse(button) => self.buttons_held.contains(&button),
+        })
+    }
+
+    pub fn released(&self, action: Action) -> bool {
+        self.any_binding(action, |trigger| match trigger {
+            Trigger::Key(key) => self.keys_released.contains(&key),
+            Trigger::Mouse(button) => self.buttons_released.contains(&button),
+        })
+    }
+
+    pub fn cursor_position(&self) -> Option<PhysicalPosition<f64>> {
+        self.cursor_position
+    }
+
+    pub fn mouse_motion(&self) -> (f64, f64) {
+        self.mouse_motion
+    }
+
+    pub fn scroll(&self) -> f32 {
+        self.scroll
+    }
+
+    // A binding matches when its trigger fired and all of its modifiers are held.
+    fn any_binding(&self, action: Action, fired: impl Fn(Trigger) -> bool) -> bool {
+        self.action_map.bindings(action).iter()
+            .any(|binding| self.modifiers.contains(binding.modifiers) && fired(binding.trigger))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key: KeyCode, state: ElementState) -> InputEvent {
+        InputEvent::Key { key, state, repeat: false }
+    }
+
+    #[test]
+    fn pressed_and_released_last_one_frame_held_lasts_until_release() {
+        let mut input = Input::new(ActionMap::default());
+        input.begin_frame();
+        input.apply(key(KeyCode::KeyW, ElementState::Pressed));
+        assert!(input.pressed(Action::CameraForward));
+        assert!(input.held(Action::CameraForward));
+        assert!(!input.released(Action::CameraForward));
+        input.end_frame();
+
+        // key repeat is neither a new press nor a release
+        input.begin_frame();
+        input.apply(InputEvent::Key { key: KeyCode::KeyW, state: ElementState::Pressed, repeat: true });
+        assert!(!input.pressed(Action::CameraForward));
+        assert!(input.held(Action::CameraForward));
+        input.end_frame();
+
+        input.begin_frame();
+        input.apply(key(KeyCode::KeyW, ElementState::Released));
+        assert!(!input.pressed(Action::CameraForward));
+        assert!(!input.held(Action::CameraForward));
+        assert!(input.released(Action::CameraForward));
+        input.end_frame();
+
+        input.begin_frame();
+        assert!(!input.released(Action::CameraForward));
+        input.end_frame();
+    }
+
+    #[test]
+    fn mouse_buttons_and_modifiers_follow_their_bindings() {
+        let mut input = Input::new(ActionMap::default());
+        input.begin_frame();
+        input.apply(InputEvent::MouseButton { button: MouseButton::Right, state: ElementState::Pressed });
+        input.apply(key(KeyCode::Enter, ElementState::Pressed));
+        assert!(input.pressed(Action::CameraOrbit));
+        // bound to Alt+Enter
+        assert!(!input.pressed(Action::ToggleExclusiveFullscreen));
+        input.end_frame();
+
+        input.begin_frame();
+        input.apply(InputEvent::Modifiers(ModifiersState::ALT));
+        assert!(input.held(Action::ToggleExclusiveFullscreen));
+        input.apply(InputEvent::Focused(false));
+        assert!(!input.held(Action::CameraOrbit));
+        assert!(!input.held(Action::ToggleExclusiveFullscreen));
+        input.end_frame();
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    collections::VecDeque,
+    fs::{
+        self,
+        File,
+    },
+    io::{
+        BufWriter,
+        Write,
+    },
+    path::Path,
+};
+use anyhow::{
+    Context,
+    Result,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use crate::input::event::InputEvent;
+
+// Everything that happened between two `Input::end_frame` calls and how long the frame took.
+// Replaying the same frames with the same `dt` gives the same camera path every time.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    pub dt: f32,
+    pub events: Vec<InputEvent>,
+}
+
+// Writes one JSON encoded `Frame` per line, flushed every frame so a crash keeps the recording.
+pub struct Recorder {
+    writer: BufWriter<File>,
+    events: Vec<InputEvent>,
+}
+impl Recorder {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        Ok(Self { writer: BufWriter::new(file), events: Vec::new() })
+    }
+
+    pub fn push(&mut self, event: InputEvent) {
+        self.events.push(event);
+    }
+
+    pub fn end_frame(&mut self, dt: f32) -> Result<()> {
+        let frame = Frame { dt, events: std::mem::take(&mut self.events) };
+        serde_json::to_writer(&mut self.writer, &frame)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+pub struct Player {
+    frames: VecDeque<Frame>,
+}
+impl Player {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let frames = text.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(number, line)| serde_json::from_str(line)
+                .with_context(|| format!("{}:{}: invalid frame", path.display(), number + 1)))
+            .collect::<Result<_>>()?;
+        Ok(Self { frames })
+    }
+
+    pub fn next_frame(&mut self) -> Option<Frame> {
+        self.frames.pop_front()
+    }
+}
@@ -1,8 +1,104 @@
 mod examples;
+mod input;
+mod window;
 
+use std::path::{
+    Path,
+    PathBuf,
+};
+use anyhow::{
+    bail,
+    Context,
+    Result,
+};
+use input::{
+    ActionMap,
+    Input,
+};
+use window::WindowConfig;
+
+// cargo run -- [example] [--config window.toml] [--size 800x600] [--position 100,100 | --centered]
+//              [--title T] [--mode windowed|borderless|exclusive] [--monitor N] [--video-mode 1920x1080@60]
+//              [--resizable true|false] [--vsync true|false]
+//              [--record input.jsonl | --replay input.jsonl [--headless]] [--material dir (pbr)]
+//              [--environment map.hdr (pbr)] [--skybox dir (pbr)]
+// cargo run -- filter in.png [out.png] [--grayscale] [--sobel] [--blur radius] [--histogram]
 fn main() {
-    // examples::triangle::run();
-    // examples::buffers_and_indexes::run();
-    // examples::textures_and_bind_groups::run();
-    examples::perspective_camera::run();
-}
\ No newline at end of file
+    if let Err(error) = run() {
+        eprintln!("{error:#}");
+        std::process::exit(2);
+    }
+}
+
+fn run() -> Result<()> {
+    let mut args = std::env::args().skip(1).peekable();
+    let example = args.next_if(|arg| !arg.starts_with("--"));
+    let mut args: Vec<String> = args.collect();
+    // Not a window, the filters run on an image and exit.
+    if example.as_deref() == Some("filter") {
+        return examples::image_filters::run(&args);
+    }
+    let record = take_value(&mut args, "--record")?;
+    let replay = take_value(&mut args, "--replay")?;
+    let headless = take_flag(&mut args, "--headless");
+    let material = take_value(&mut args, "--material")?;
+    let environment = take_value(&mut args, "--environment")?;
+    let skybox = take_value(&mut args, "--skybox")?;
+    let window_config = WindowConfig::from_args(&args)?;
+
+    // Key bindings, see `ActionMap::load` for the format.
+    let mut input = Input::new(ActionMap::load(Path::new("input.toml"))?);
+    if let Some(path) = record {
+        input.record(&path)?;
+    }
+    if let Some(path) = replay {
+        input.replay(&path)?;
+    } else if headless {
+        bail!("`--headless` needs a recording to `--replay`");
+    }
+
+    if material.is_some() && example.as_deref() != Some("pbr") {
+        bail!("`--material` only works with the pbr example");
+    }
+    if environment.is_some() && example.as_deref() != Some("pbr") {
+        bail!("`--environment` only works with the pbr example");
+    }
+    if skybox.is_some() && example.as_deref() != Some("pbr") {
+        bail!("`--skybox` only works with the pbr example");
+    }
+
+    match example.as_deref() {
+        Some("perspective_camera") | None if headless => {
+            let camera = examples::perspective_camera::replay_headless(&window_config, input);
+            println!("eye: {:?}", camera.eye);
+            println!("view_proj: {:?}", camera.build_view_projection_matrix());
+        }
+        Some(name) if headless => bail!("Headless replay is not supported by {name}"),
+        Some("triangle") => examples::triangle::run(window_config, input),
+        Some("buffers_and_indexes") => examples::buffers_and_indexes::run(window_config, input),
+        Some("textures_and_bind_groups") => examples::textures_and_bind_groups::run(window_config, input),
+        Some("perspective_camera") | None => examples::perspective_camera::run(window_config, input),
+        Some("lighting") => examples::lighting::run(window_config, input),
+        Some("pbr") => examples::pbr::run(window_config, input, material, environment, skybox),
+        Some("instancing") => examples::instancing::run(window_config, input),
+        Some("particles") => examples::particles::run(window_config, input),
+        Some(name) => bail!("Unknown example: {name}"),
+    }
+    Ok(())
+}
+
+fn take_value(args: &mut Vec<String>, flag: &str) -> Result<Option<PathBuf>> {
+    let Some(index) = args.iter().position(|arg| arg == flag) else {
+        return Ok(None);
+    };
+    args.remove(index);
+    let value = (index < args.len()).then(|| args.remove(index))
+        .with_context(|| format!("Missing value for `{flag}`"))?;
+    Ok(Some(value.into()))
+}
+
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let before = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != before
+}
@@ -0,0 +1,143 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+};
+use anyhow::{
+    bail,
+    Context,
+    Result,
+};
+use winit::{
+    dpi::PhysicalSize,
+    window::{
+        Window,
+        WindowId,
+    },
+};
+
+// A window together with its surface. The surface owns an `Arc` of the window,
+// so it is `'static` and can live next to other windows sharing the same device.
+// `surface` is `None` while the application is suspended, the configuration is kept
+// so the surface comes back with the same format and size.
+pub struct ManagedWindow {
+    pub window: Arc<Window>,
+    pub surface: Option<wgpu::Surface<'static>>,
+    pub surface_config: wgpu::SurfaceConfiguration,
+}
+impl ManagedWindow {
+    // `format` lets secondary windows match the format the pipelines were built for, it is an
+    // error when the surface cannot use it. Without one the surface's preferred format is used.
+    pub fn new(
+        window: Arc<Window>,
+        instance: &wgpu::Instance,
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        present_mode: wgpu::PresentMode,
+        format: Option<wgpu::TextureFormat>,
+    ) -> Result<Self> {
+        let surface = instance.create_surface(window.clone())?;
+        let formats = surface.get_capabilities(adapter).formats;
+        let surface_format = match format {
+            Some(format) if !formats.contains(&format) => {
+                bail!("The surface of `{}` cannot use {format:?}, it supports {formats:?}", window.title());
+            }
+            Some(format) => format,
+            None => *formats.first().context("The surface is not supported by the adapter")?,
+        };
+        let surface_config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: surface_format,
+            width: window.inner_size().width.max(1),
+            height: window.inner_size().height.max(1),
+            present_mode,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        surface.configure(device, &surface_config);
+
+        Ok(Self { window, surface: Some(surface), surface_config })
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    pub fn aspect(&self) -> f32 {
+        self.surface_config.width as f32 / self.surface_config.height as f32
+    }
+
+    // Returns false for a zero sized (minimized) window, the surface is left untouched then.
+    pub fn resize(&mut self, device: &wgpu::Device, size: PhysicalSize<u32>) -> bool {
+        if size.width == 0 || size.height == 0 {
+            return false;
+        }
+        self.surface_config.width = size.width;
+        self.surface_config.height = size.height;
+        if let Some(surface) = self.surface.as_ref() {
+            surface.configure(device, &self.surface_config);
+        }
+        true
+    }
+
+    pub fn suspend(&mut self) {
+        self.surface = None;
+    }
+
+    // Recreates the surface dropped by `suspend`, nothing to do when it is still there.
+    // The native window may have changed size while suspended (e.g. rotated on Android).
+    pub fn resume(&mut self, instance: &wgpu::Instance, device: &wgpu::Device) -> Result<()> {
+        if self.surface.is_some() {
+            return Ok(());
+        }
+        let surface = instance.create_surface(self.window.clone())
+            .with_context(|| format!("Failed to recreate the surface of `{}`", self.window.title()))?;
+        let size = self.window.inner_size();
+        self.surface_config.width = size.width.max(1);
+        self.surface_config.height = size.height.max(1);
+        surface.configure(device, &self.surface_config);
+        self.surface = Some(surface);
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct WindowManager {
+    windows: HashMap<WindowId, ManagedWindow>,
+}
+impl WindowManager {
+    pub fn insert(&mut self, window: ManagedWindow) -> WindowId {
+        let id = window.id();
+        self.windows.insert(id, window);
+        id
+    }
+
+    pub fn remove(&mut self, id: WindowId) -> Option<ManagedWindow> {
+        self.windows.remove(&id)
+    }
+
+    pub fn get(&self, id: WindowId) -> Option<&ManagedWindow> {
+        self.windows.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: WindowId) -> Option<&mut ManagedWindow> {
+        self.windows.get_mut(&id)
+    }
+
+    // Drops every surface, the windows and all other GPU resources stay alive.
+    pub fn suspend(&mut self) {
+        self.windows.values_mut().for_each(ManagedWindow::suspend);
+    }
+
+    // Recreates the surfaces dropped by `suspend` and returns the ids of the resumed windows,
+    // so the caller can rebuild its own swapchain sized targets and request a redraw.
+    pub fn resume(&mut self, instance: &wgpu::Instance, device: &wgpu::Device) -> Result<Vec<WindowId>> {
+        self.windows.values_mut()
+            .filter(|managed| managed.surface.is_none())
+            .map(|managed| {
+                managed.resume(instance, device)?;
+                Ok(managed.id())
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,254 @@
+mod manager;
+mod platform;
+mod settings;
+
+pub use manager::{
+    ManagedWindow,
+    WindowManager,
+};
+pub use platform::PlatformAttributes;
+pub use settings::{
+    WindowMode,
+    WindowSettings,
+};
+
+use std::path::PathBuf;
+use crate::input::{
+    Action,
+    Input,
+};
+use anyhow::{
+    Context,
+    Result,
+};
+use winit::{
+    dpi::{
+        LogicalSize,
+        PhysicalPosition,
+        Position,
+    },
+    event_loop::ActiveEventLoop,
+    monitor::{
+        MonitorHandle,
+        VideoModeHandle,
+    },
+    window::{
+        Fullscreen,
+        Window,
+        WindowAttributes,
+    },
+};
+
+const DEFAULT_CONFIG_PATH: &str = "window.toml";
+
+// Everything the examples need to know to open their window.
+// Platform specific tweaks live in `PlatformAttributes` so that a macOS or
+// Windows only option never leaks into code compiled on other targets.
+#[derive(Clone, Debug)]
+pub struct WindowConfig {
+    pub settings: WindowSettings,
+    pub platform: PlatformAttributes,
+    pub path: PathBuf,
+}
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            settings: WindowSettings::default(),
+            platform: PlatformAttributes::default(),
+            path: PathBuf::from(DEFAULT_CONFIG_PATH),
+        }
+    }
+}
+impl WindowConfig {
+    // Loads the persisted settings (`--config <path>`, `window.toml` by default)
+    // and applies the remaining command line arguments on top.
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let mut config = Self::default();
+        let mut overrides = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--config" {
+                config.path = args.next().context("Missing value for `--config`")?.into();
+            } else {
+                overrides.push(arg.clone());
+            }
+        }
+        config.settings = WindowSettings::load(&config.path)?;
+        config.settings.apply_args(&overrides)?;
+        Ok(config)
+    }
+
+    pub fn attributes(&self, event_loop: &ActiveEventLoop) -> WindowAttributes {
+        let settings = &self.settings;
+        let monitor = self.monitor(event_loop.available_monitors(), event_loop.primary_monitor());
+        let mut attributes = WindowAttributes::default()
+            .with_title(settings.title.clone())
+            .with_inner_size(LogicalSize::new(settings.width, settings.height))
+            .with_resizable(settings.resizable)
+            .with_fullscreen(self.fullscreen(monitor.clone()));
+        let position = match (settings.position, monitor) {
+            (Some([x, y]), _) => Some(PhysicalPosition::new(x, y)),
+            (None, Some(monitor)) => Some(self.centered_on(&monitor)),
+            // No monitor information (e.g. Wayland), let the compositor decide.
+            (None, None) => None,
+        };
+        if let Some(position) = position {
+            attributes = attributes.with_position(Position::Physical(position));
+        }
+        self.platform.apply(attributes)
+    }
+
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        if self.settings.vsync { wgpu::PresentMode::Fifo } else { wgpu::PresentMode::AutoNoVsync }
+    }
+
+    // The configured MSAA sample count, or the highest lower one every format in `formats` can be
+    // rendered with. Without `TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES` only 1 and 4 are guaranteed.
+    pub fn sample_count(&self, adapter: &wgpu::Adapter, device: &wgpu::Device, formats: &[wgpu::TextureFormat]) -> u32 {
+        let supported = |count: u32| formats.iter().all(|format| {
+            let features = if device.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+                adapter.get_texture_format_features(*format)
+            } else {
+                format.guaranteed_format_features(device.features())
+            };
+            features.flags.sample_count_supported(count)
+        });
+        let requested = self.settings.msaa;
+        let count = [8, 4, 2, 1].into_iter()
+            .find(|&count| count <= requested && supported(count))
+            .unwrap_or(1);
+        if count != requested {
+            eprintln!("{requested}x MSAA is not supported for {formats:?}, using {count}x");
+        }
+        count
+    }
+
+    // Device features for `sample_count`, request them so it can use every MSAA count the adapter
+    // has instead of just 1 and 4.
+    pub fn msaa_features(adapter: &wgpu::Adapter) -> wgpu::Features {
+        adapter.features() & wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+    }
+
+    // Multisampled color target sized like the surface, drawn into and resolved to the frame (or
+    // whatever `format` target the example renders to). `None` without MSAA. Recreate it whenever
+    // the surface is resized.
+    pub fn create_msaa_view(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Option<wgpu::TextureView> {
+        (sample_count > 1).then(|| {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("MSAA Texture"),
+                size: wgpu::Extent3d {
+                    width: config.width.max(1),
+                    height: config.height.max(1),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            });
+            texture.create_view(&wgpu::TextureViewDescriptor::default())
+        })
+    }
+
+    // Applies the fullscreen toggles of the action map (F11 / Alt+Enter by default).
+    // Returns true when the window mode changed and the surface needs to be reconfigured.
+    pub fn handle_input(&mut self, window: &Window, input: &Input) -> bool {
+        let mode = if input.pressed(Action::ToggleFullscreen) {
+            WindowMode::Borderless
+        } else if input.pressed(Action::ToggleExclusiveFullscreen) {
+            WindowMode::Exclusive
+        } else {
+            return false;
+        };
+        self.toggle_fullscreen(window, mode);
+        true
+    }
+
+    // Switches to `mode`, or back to windowed when it is already active.
+    // The surface has to be reconfigured with the new `inner_size` afterwards.
+    pub fn toggle_fullscreen(&mut self, window: &Window, mode: WindowMode) {
+        self.settings.mode = if self.settings.mode == mode { WindowMode::Windowed } else { mode };
+        let monitor = self.monitor(window.available_monitors(), window.current_monitor());
+        let fullscreen = self.fullscreen(monitor);
+        if self.settings.mode == WindowMode::Exclusive && fullscreen.is_none() {
+            eprintln!("No video mode available for exclusive fullscreen, staying windowed");
+            self.settings.mode = WindowMode::Windowed;
+        }
+        window.set_fullscreen(fullscreen);
+        println!("Window mode: {:?}", self.settings.mode);
+    }
+
+    // Remembers where the window ended up and writes it back to disk, see `WindowSettings::persisted`.
+    pub fn store(&self, window: &Window) {
+        let mut current = self.settings.clone();
+        if current.mode == WindowMode::Windowed {
+            let size = window.inner_size().to_logical::<u32>(window.scale_factor());
+            current.width = size.width;
+            current.height = size.height;
+            if let Ok(position) = window.outer_position() {
+                current.position = Some([position.x, position.y]);
+            }
+        }
+        let result = WindowSettings::load(&self.path)
+            .and_then(|loaded| loaded.persisted(&current).save(&self.path));
+        if let Err(error) = result {
+            eprintln!("{error:#}");
+        }
+    }
+
+    // The configured monitor if it is still connected, `fallback` otherwise.
+    fn monitor(
+        &self,
+        mut monitors: impl Iterator<Item = MonitorHandle>,
+        fallback: Option<MonitorHandle>,
+    ) -> Option<MonitorHandle> {
+        let configured = self.settings.monitor.and_then(|index| monitors.nth(index));
+        configured.or(fallback)
+    }
+
+    fn fullscreen(&self, monitor: Option<MonitorHandle>) -> Option<Fullscreen> {
+        match self.settings.mode {
+            WindowMode::Windowed => None,
+            WindowMode::Borderless => Some(Fullscreen::Borderless(monitor)),
+            WindowMode::Exclusive => monitor
+                .and_then(|monitor| self.video_mode(&monitor))
+                .map(Fullscreen::Exclusive),
+        }
+    }
+
+    fn video_mode(&self, monitor: &MonitorHandle) -> Option<VideoModeHandle> {
+        let requested = self.settings.video_mode.and_then(|requested| {
+            monitor.video_modes().find(|mode| {
+                let size = mode.size();
+                size.width == requested.width
+                    && size.height == requested.height
+                    && (mode.refresh_rate_millihertz() + 500) / 1000 == requested.refresh_rate
+            })
+        });
+        if requested.is_none() && self.settings.video_mode.is_some() {
+            eprintln!("Requested video mode is not supported by {:?}, using the largest one", monitor.name());
+        }
+        requested.or_else(|| monitor.video_modes().max_by_key(|mode| {
+            let size = mode.size();
+            (size.width * size.height, mode.refresh_rate_millihertz())
+        }))
+    }
+
+    fn centered_on(&self, monitor: &MonitorHandle) -> PhysicalPosition<i32> {
+        let size = LogicalSize::new(self.settings.width, self.settings.height)
+            .to_physical::<i32>(monitor.scale_factor());
+        let origin = monitor.position();
+        let area = monitor.size();
+        PhysicalPosition::new(
+            origin.x + (area.width as i32 - size.width) / 2,
+            origin.y + (area.height as i32 - size.height) / 2,
+        )
+    }
+}
@@ -0,0 +1,19 @@
+use winit::{
+    platform::macos::WindowAttributesExtMacOS,
+    window::WindowAttributes,
+};
+
+#[derive(Clone, Debug, Default)]
+pub struct PlatformAttributes {
+    pub titlebar_transparent: bool,
+    pub title_hidden: bool,
+    pub fullsize_content_view: bool,
+}
+impl PlatformAttributes {
+    pub fn apply(&self, attributes: WindowAttributes) -> WindowAttributes {
+        attributes
+            .with_titlebar_transparent(self.titlebar_transparent)
+            .with_title_hidden(self.title_hidden)
+            .with_fullsize_content_view(self.fullsize_content_view)
+    }
+}
@@ -0,0 +1,9 @@
+use winit::window::WindowAttributes;
+
+#[derive(Clone, Debug, Default)]
+pub struct PlatformAttributes {}
+impl PlatformAttributes {
+    pub fn apply(&self, attributes: WindowAttributes) -> WindowAttributes {
+        attributes
+    }
+}
@@ -0,0 +1,27 @@
+use winit::{
+    platform::{
+        wayland::WindowAttributesExtWayland,
+        x11::WindowAttributesExtX11,
+    },
+    window::WindowAttributes,
+};
+
+#[derive(Clone, Debug)]
+pub struct PlatformAttributes {
+    // WM_CLASS on X11, xdg app id on Wayland
+    pub app_id: String,
+}
+impl Default for PlatformAttributes {
+    fn default() -> Self {
+        Self {
+            app_id: env!("CARGO_PKG_NAME").to_string(),
+        }
+    }
+}
+impl PlatformAttributes {
+    pub fn apply(&self, attributes: WindowAttributes) -> WindowAttributes {
+        // Both backends are compiled in, winit uses whichever one it connects to at runtime.
+        let attributes = WindowAttributesExtX11::with_name(attributes, &self.app_id, &self.app_id);
+        WindowAttributesExtWayland::with_name(attributes, &self.app_id, &self.app_id)
+    }
+}
@@ -0,0 +1,19 @@
+use winit::{
+    platform::windows::WindowAttributesExtWindows,
+    window::{
+        Icon,
+        WindowAttributes,
+    },
+};
+
+#[derive(Clone, Debug, Default)]
+pub struct PlatformAttributes {
+    pub icon: Option<Icon>,
+}
+impl PlatformAttributes {
+    pub fn apply(&self, attributes: WindowAttributes) -> WindowAttributes {
+        attributes
+            .with_window_icon(self.icon.clone())
+            .with_taskbar_icon(self.icon.clone())
+    }
+}
@@ -0,0 +1,239 @@
+use std::{
+    fs,
+    path::Path,
+};
+use anyhow::{
+    bail,
+    ensure,
+    Context,
+    Error,
+    Result,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowMode {
+    Windowed,
+    Borderless,
+    Exclusive,
+}
+impl std::str::FromStr for WindowMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "windowed" => Ok(Self::Windowed),
+            "borderless" => Ok(Self::Borderless),
+            "exclusive" => Ok(Self::Exclusive),
+            _ => bail!("Unknown window mode `{s}`, expected windowed, borderless or exclusive"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VideoModeSettings {
+    pub width: u32,
+    pub height: u32,
+    pub refresh_rate: u32,
+}
+impl std::str::FromStr for VideoModeSettings {
+    type Err = Error;
+
+    // 1920x1080@60
+    fn from_str(s: &str) -> Result<Self> {
+        let (size, refresh_rate) = s.split_once('@')
+            .with_context(|| format!("Expected WIDTHxHEIGHT@HZ, got `{s}`"))?;
+        let (width, height) = size.split_once('x')
+            .with_context(|| format!("Expected WIDTHxHEIGHT@HZ, got `{s}`"))?;
+        let video_mode = Self {
+            width: width.parse()?,
+            height: height.parse()?,
+            refresh_rate: refresh_rate.parse()?,
+        };
+        ensure!(video_mode.width > 0 && video_mode.height > 0, "Expected a video mode larger than 0x0, got `{s}`");
+        Ok(video_mode)
+    }
+}
+
+// User facing window settings, stored as TOML between runs.
+// `position: None` means the window is centered on the primary monitor.
+// `monitor` indexes winit's `available_monitors`, `video_mode` is only used in exclusive mode,
+// the largest mode of the monitor is picked when it is not set or not available.
+// `msaa` is the requested sample count, lowered to what the adapter supports when rendering starts.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowSettings {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    pub position: Option<[i32; 2]>,
+    pub mode: WindowMode,
+    pub monitor: Option<usize>,
+    pub video_mode: Option<VideoModeSettings>,
+    pub resizable: bool,
+    pub vsync: bool,
+    pub msaa: u32,
+}
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            title: "My Window".to_string(),
+            width: 512,
+            height: 512,
+            position: None,
+            mode: WindowMode::Windowed,
+            monitor: None,
+            video_mode: None,
+            resizable: true,
+            vsync: true,
+            msaa: 4,
+        }
+    }
+}
+impl WindowSettings {
+    // A missing file is not an error, it just means this is the first run.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let settings: Self = toml::from_str(&text)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        ensure!(settings.width > 0 && settings.height > 0, "Window size in {} has to be larger than 0x0", path.display());
+        Ok(settings)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = toml::to_string_pretty(self)?;
+        fs::write(path, text)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    // Command line overrides, e.g. `--size 800x600 --position 100,100 --mode borderless --vsync false --msaa 4`.
+    pub fn apply_args(&mut self, args: &[String]) -> Result<()> {
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let mut value = || args.next()
+                .with_context(|| format!("Missing value for `{flag}`"));
+            match flag.as_str() {
+                "--title" => self.title = value()?.clone(),
+                "--size" => {
+                    let size = value()?;
+                    let (width, height) = size.split_once('x')
+                        .with_context(|| format!("Expected WIDTHxHEIGHT, got `{size}`"))?;
+                    self.width = width.parse()?;
+                    self.height = height.parse()?;
+                    ensure!(self.width > 0 && self.height > 0, "Expected a size larger than 0x0, got `{size}`");
+                }
+                "--position" => {
+                    let position = value()?;
+                    let (x, y) = position.split_once(',')
+                        .with_context(|| format!("Expected X,Y, got `{position}`"))?;
+                    self.position = Some([x.parse()?, y.parse()?]);
+                }
+                "--centered" => self.position = None,
+                "--mode" => self.mode = value()?.parse()?,
+                "--monitor" => self.monitor = Some(value()?.parse()?),
+                "--video-mode" => self.video_mode = Some(value()?.parse()?),
+                "--resizable" => self.resizable = value()?.parse()?,
+                "--vsync" => self.vsync = value()?.parse()?,
+                "--msaa" => {
+                    let msaa = value()?;
+                    self.msaa = msaa.parse()?;
+                    if ![1, 2, 4, 8].contains(&self.msaa) {
+                        bail!("Expected an MSAA sample count of 1, 2, 4 or 8, got `{msaa}`");
+                    }
+                }
+                _ => bail!("Unknown argument `{flag}`"),
+            }
+        }
+        Ok(())
+    }
+
+    // What gets written back to disk: the size, position and mode of `current` on top of these,
+    // the settings as they were loaded. Command line overrides are not persisted, and a centered
+    // window (no `position` here) stays centered.
+    pub fn persisted(&self, current: &WindowSettings) -> Self {
+        Self {
+            width: current.width,
+            height: current.height,
+            position: self.position.and(current.position),
+            mode: current.mode,
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn apply_args_overrides_every_setting() {
+        let mut settings = WindowSettings::default();
+        settings.apply_args(&args("--title Demo --size 800x600 --position -10,20 --mode borderless --monitor 1 \
+            --video-mode 1920x1080@60 --resizable false --vsync false --msaa 8")).unwrap();
+        assert_eq!(settings, WindowSettings {
+            title: "Demo".to_string(),
+            width: 800,
+            height: 600,
+            position: Some([-10, 20]),
+            mode: WindowMode::Borderless,
+            monitor: Some(1),
+            video_mode: Some(VideoModeSettings { width: 1920, height: 1080, refresh_rate: 60 }),
+            resizable: false,
+            vsync: false,
+            msaa: 8,
+        });
+        settings.apply_args(&args("--centered")).unwrap();
+        assert_eq!(settings.position, None);
+    }
+
+    #[test]
+    fn apply_args_rejects_bad_values() {
+        for bad in ["--size 0x0", "--size 0x600", "--size 800x0", "--size 800", "--position 10", "--mode tiled",
+            "--msaa 3", "--vsync maybe", "--size", "--unknown 1"] {
+            assert!(WindowSettings::default().apply_args(&args(bad)).is_err(), "`{bad}` was accepted");
+        }
+    }
+
+    #[test]
+    fn window_mode_parses_every_mode() {
+        assert_eq!("windowed".parse::<WindowMode>().unwrap(), WindowMode::Windowed);
+        assert_eq!("borderless".parse::<WindowMode>().unwrap(), WindowMode::Borderless);
+        assert_eq!("exclusive".parse::<WindowMode>().unwrap(), WindowMode::Exclusive);
+        assert!("Windowed".parse::<WindowMode>().is_err());
+    }
+
+    #[test]
+    fn video_mode_parses_and_rejects_empty_modes() {
+        assert_eq!("2560x1440@144".parse::<VideoModeSettings>().unwrap(),
+            VideoModeSettings { width: 2560, height: 1440, refresh_rate: 144 });
+        for bad in ["0x1080@60", "1920x0@60", "1920x1080", "1920@60", "1920xabc@60"] {
+            assert!(bad.parse::<VideoModeSettings>().is_err(), "`{bad}` was accepted");
+        }
+    }
+
+    #[test]
+    fn persisted_keeps_overrides_and_centering_out_of_the_file() {
+        let loaded = WindowSettings::default();
+        let mut current = loaded.clone();
+        current.apply_args(&args("--title Demo --vsync false --msaa 1 --mode exclusive")).unwrap();
+        current.width = 1024;
+        current.position = Some([300, 200]);
+        let persisted = loaded.persisted(&current);
+        assert_eq!(persisted, WindowSettings { width: 1024, mode: WindowMode::Exclusive, ..WindowSettings::default() });
+
+        let loaded = WindowSettings { position: Some([0, 0]), ..WindowSettings::default() };
+        assert_eq!(loaded.persisted(&current).position, Some([300, 200]));
+    }
+}